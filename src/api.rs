@@ -1,81 +1,288 @@
-use std::sync::Arc;
-
-use anyhow::Result;
-use axum::{
-    Extension, Router,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result};
+use axum::{Extension, Router, http::HeaderValue, middleware};
 use tokio::sync::Mutex;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
-use crate::config::Config;
+use crate::config::{Config, project::ApiConfig};
+use crate::pipeline::ducklake::SharedDuckLake;
 
 mod adapter;
+mod auth;
 mod connection;
 mod dashboard;
+mod error;
 mod model;
 mod pipeline;
 mod query;
 mod run;
 mod secret;
+mod sql_guard;
+
+pub use error::{AppError, app_error};
 
-pub enum AppError {
-    StatusCode(StatusCode),
-    Exception(anyhow::Error),
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub ui_port: u16,
+    pub tls: Option<TlsConfig>,
+    /// Serve the UI from the API listener instead of a separate port.
+    pub single_listener: bool,
+    pub open_browser: bool,
+    /// Listen on a non-loopback address even though `api.auth` is off.
+    pub allow_unauthenticated: bool,
 }
 
-pub fn app_error<T>(status_code: StatusCode) -> Result<T, AppError> {
-    Err(AppError::StatusCode(status_code))
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        match self {
-            AppError::StatusCode(status_code) => status_code.into_response(),
-            AppError::Exception(error) => {
-                tracing::error!("{}", error);
-                eprintln!("{}", error);
-                StatusCode::INTERNAL_SERVER_ERROR.into_response()
-            }
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 3015,
+            ui_port: 8015,
+            tls: None,
+            single_listener: false,
+            open_browser: true,
+            allow_unauthenticated: false,
         }
     }
 }
 
-impl<E> From<E> for AppError
-where
-    E: Into<anyhow::Error>,
-{
-    fn from(err: E) -> Self {
-        Self::Exception(err.into())
+impl ServerConfig {
+    pub fn api_url(&self) -> String {
+        self.url(self.port)
+    }
+
+    pub fn ui_url(&self) -> String {
+        if self.single_listener {
+            self.url(self.port)
+        } else {
+            self.url(self.ui_port)
+        }
+    }
+
+    fn url(&self, port: u16) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let host = match self.host.as_str() {
+            "0.0.0.0" | "::" | "[::]" => "localhost",
+            host => host,
+        };
+        format!("{scheme}://{host}:{port}")
     }
 }
 
-pub async fn main(config: Config) -> Result<()> {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+pub fn router(config: Config) -> Result<Router> {
+    let cors = cors_layer(config.project.api.as_ref())?;
 
     let routes = Router::new()
+        .merge(auth::routes())
         .merge(adapter::routes())
         .merge(connection::routes())
         .merge(dashboard::router())
         .merge(model::routes())
         .merge(query::routes())
-        // .merge(secret::routes())
+        .merge(secret::routes())
         .merge(pipeline::routes());
 
-    let app = Router::new()
+    Ok(Router::new()
         .nest("/api", routes)
+        .layer(middleware::map_response(error::json_errors))
+        .layer(middleware::from_fn(auth::require_token))
         .layer(cors)
-        .layer(Extension(Arc::new(Mutex::new(config))));
+        .layer(Extension(Arc::new(Mutex::new(config))))
+        .layer(Extension(Arc::new(SharedDuckLake::default())))
+        .layer(Extension(Arc::new(Mutex::new(run::RunRegistry::default()))))
+        .layer(Extension(Arc::new(query::RunningQueries::default()))))
+}
+
+pub async fn main(config: Config, server: ServerConfig) -> Result<()> {
+    let app = router(config)?;
+
+    if server.single_listener {
+        return serve(crate::ui::with_ui(app), &server, server.port).await;
+    }
+
+    tokio::try_join!(
+        serve(app.clone(), &server, server.port),
+        crate::ui::start_ui_server(&server, app),
+    )?;
+
+    Ok(())
+}
+
+/// Refuses to expose an API without auth beyond the loopback interface, since every
+/// caller is an admin then.
+pub async fn check_exposure(config: &Config, server: &ServerConfig) -> Result<()> {
+    if config.project.api.as_ref().is_some_and(|api| api.auth) {
+        return Ok(());
+    }
+
+    let loopback = tokio::net::lookup_host((server.host.as_str(), server.port))
+        .await
+        .with_context(|| format!("Failed to resolve {}", server.host))?
+        .all(|address| address.ip().is_loopback());
+    if loopback {
+        return Ok(());
+    }
+
+    if !server.allow_unauthenticated {
+        return Err(anyhow::anyhow!(
+            "Refusing to listen on {} without authentication. Set `auth: true` in the `api` section of project.yml, or pass --allow-unauthenticated",
+            server.host
+        ));
+    }
+    eprintln!(
+        "Warning: listening on {} without authentication. Anyone who can reach it has admin access",
+        server.host
+    );
+    Ok(())
+}
+
+pub async fn serve(app: Router, server: &ServerConfig, port: u16) -> Result<()> {
+    let address = tokio::net::lookup_host((server.host.as_str(), port))
+        .await?
+        .next()
+        .with_context(|| format!("Failed to resolve {}:{port}", server.host))?;
 
-    let port = 3015;
-    let listener = tokio::net::TcpListener::bind(format!("localhost:{}", port)).await?;
+    println!("Listening on {}", server.url(port));
 
-    println!("API server listening on http://localhost:{}", port);
-    axum::serve(listener, app).await?;
+    match &server.tls {
+        Some(tls) => {
+            let rustls_config =
+                axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                    .await
+                    .context("Failed to load TLS certificate or key")?;
+            axum_server::bind_rustls(address, rustls_config)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .with_context(|| format!("Failed to bind to {address}"))?;
+            axum::serve(listener, app).await?;
+        }
+    }
 
     Ok(())
 }
+
+/// The UI is served from the API's origin or proxies to it, so only the configured
+/// `cors_origins` may call the API from other origins.
+fn cors_layer(api_config: Option<&ApiConfig>) -> Result<CorsLayer> {
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+
+    let origins = api_config
+        .map(|api| api.cors_origins.as_slice())
+        .unwrap_or_default();
+    if origins.is_empty() {
+        return Ok(cors);
+    }
+
+    let origins = origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin)
+                .map_err(|_| anyhow::anyhow!("Invalid CORS origin '{origin}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(cors.allow_origin(AllowOrigin::list(origins)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_urls() {
+        let server = ServerConfig::default();
+        assert_eq!(server.api_url(), "http://localhost:3015");
+        assert_eq!(server.ui_url(), "http://localhost:8015");
+
+        let server = ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8443,
+            tls: Some(TlsConfig {
+                cert_path: "cert.pem".into(),
+                key_path: "key.pem".into(),
+            }),
+            single_listener: true,
+            ..ServerConfig::default()
+        };
+        assert_eq!(server.api_url(), "https://localhost:8443");
+        assert_eq!(server.ui_url(), "https://localhost:8443");
+    }
+
+    #[tokio::test]
+    async fn test_check_exposure() {
+        let mut config = Config::new(PathBuf::new());
+        let local = ServerConfig::default();
+        assert!(check_exposure(&config, &local).await.is_ok());
+
+        let exposed = ServerConfig {
+            host: "0.0.0.0".to_string(),
+            ..ServerConfig::default()
+        };
+        assert!(check_exposure(&config, &exposed).await.is_err());
+
+        let allowed = ServerConfig {
+            allow_unauthenticated: true,
+            ..exposed.clone()
+        };
+        assert!(check_exposure(&config, &allowed).await.is_ok());
+
+        config.project.api = Some(ApiConfig {
+            auth: true,
+            cors_origins: vec![],
+            unrestricted_queries: false,
+        });
+        assert!(check_exposure(&config, &exposed).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_query_without_auth_is_read_only() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let server =
+            axum_test::TestServer::new(router(Config::new(temp_dir.path().to_path_buf()))?)?;
+
+        for (sql, code) in [
+            ("DROP TABLE users", "not_read_only"),
+            ("COPY users TO '/tmp/users.csv'", "not_read_only"),
+            ("SELECT * FROM read_csv('/etc/passwd')", "file_access"),
+            ("SELECT 1; SELECT 2", "multiple_statements"),
+        ] {
+            let response = server
+                .post("/api/query")
+                .json(&serde_json::json!({"sql": sql}))
+                .await;
+            response.assert_status(axum::http::StatusCode::FORBIDDEN);
+            assert_eq!(response.json::<serde_json::Value>()["code"], code, "{sql}");
+        }
+
+        server
+            .post("/api/queries")
+            .json(&serde_json::json!({
+                "name": "passwd",
+                "sql": "SELECT * FROM read_csv('/etc/passwd')",
+            }))
+            .await
+            .assert_status_ok();
+        server
+            .post("/api/queries/passwd/run")
+            .await
+            .assert_status(axum::http::StatusCode::FORBIDDEN);
+        server
+            .get("/api/queries/passwd/export?format=csv")
+            .await
+            .assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
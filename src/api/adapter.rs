@@ -1,7 +1,7 @@
 use crate::api::{AppError, app_error};
 use crate::config::Config;
 use crate::config::adapter::AdapterConfig;
-use crate::core::graph::Graph;
+use crate::metadata::Metadata;
 use anyhow::Result;
 use axum::Extension;
 use axum::extract::Path;
@@ -82,9 +82,9 @@ async fn create_adapter(
         return app_error(StatusCode::CONFLICT);
     }
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    graph.create_node(&adapter.name, &[]);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.set_dependencies(&adapter.name, vec![]);
+    metadata.save(&config.project_dir).await?;
 
     let adapter_file = config.upsert_adapter(&adapter.name, &adapter.config)?;
     adapter_file.save()?;
@@ -103,9 +103,9 @@ async fn update_adapter(
         return app_error(StatusCode::NOT_FOUND);
     };
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    graph.update_node(&name);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.invalidate(&name);
+    metadata.save(&config.project_dir).await?;
 
     let adapter_file = config.upsert_adapter(&name, &adapter)?;
     adapter_file.save()?;
@@ -123,9 +123,9 @@ async fn delete_adapter(
         return app_error(StatusCode::NOT_FOUND);
     };
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    graph.delete_node(&name);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.remove_node(&name);
+    metadata.save(&config.project_dir).await?;
 
     let adapter_file = config.delete_adapter(&name)?;
     adapter_file.save()?;
@@ -169,8 +169,8 @@ mod tests {
         let response = server.post("/adapters").json(&new_adapter).await;
         response.assert_status_ok();
 
-        let graph = Graph::load(test.directory()).await?;
-        assert!(graph.has_node("test_csv_adapter"));
+        let metadata = Metadata::load(test.directory()).await?;
+        assert!(metadata.get_node("test_csv_adapter").is_some());
 
         // Get request
         let response = server.get("/adapters/test_csv_adapter").await;
@@ -202,12 +202,16 @@ mod tests {
 
         let original_adapter = AdapterConfig {
             connection: "test_connection".to_string(),
+            mode: crate::config::adapter::ImportMode::Full,
+            tests: vec![],
             description: Some("Original adapter".to_string()),
             source: crate::config::adapter::AdapterSource::File {
                 file: crate::config::adapter::FileConfig {
                     path: "original.csv".to_string(),
                     compression: None,
                     max_batch_size: None,
+                    lookback_days: None,
+                    partition_column: None,
                 },
                 format: crate::config::adapter::FormatConfig {
                     ty: "csv".to_string(),
@@ -227,10 +231,10 @@ mod tests {
         }
 
         // Create node in graph
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("test_adapter", &[]);
-        graph.set_current_time("test_adapter");
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("test_adapter", vec![]);
+        metadata.update_node_timestamp("test_adapter", chrono::Utc::now());
+        metadata.save(test.directory()).await?;
 
         // Update request
         let updated_config = json!({
@@ -275,9 +279,9 @@ mod tests {
         }
 
         // Verify graph node was updated (timestamp reset)
-        let graph = Graph::load(test.directory()).await?;
-        assert!(graph.has_node("test_adapter"));
-        assert!(graph.get_node("test_adapter").unwrap().updated_at.is_none());
+        let metadata = Metadata::load(test.directory()).await?;
+        let node = metadata.get_node("test_adapter").unwrap();
+        assert!(node.last_updated_at.is_none());
 
         Ok(())
     }
@@ -289,9 +293,14 @@ mod tests {
 
         let adapter_config = AdapterConfig {
             connection: "test_connection".to_string(),
+            mode: crate::config::adapter::ImportMode::Full,
+            tests: vec![],
             description: Some("Adapter to delete".to_string()),
             source: crate::config::adapter::AdapterSource::Database {
-                table_name: "test_table".to_string(),
+                table_name: Some("test_table".to_string()),
+                query: None,
+                cursor_column: None,
+                primary_key: vec![],
             },
             columns: vec![],
         };
@@ -302,9 +311,9 @@ mod tests {
             adapter_file.save()?;
         }
 
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("adapter_to_delete", &[]);
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("adapter_to_delete", vec![]);
+        metadata.save(test.directory()).await?;
 
         let get_response = server.get("/adapters/adapter_to_delete").await;
         get_response.assert_status_ok();
@@ -315,8 +324,8 @@ mod tests {
         let get_response_after = server.get("/adapters/adapter_to_delete").await;
         get_response_after.assert_status(StatusCode::NOT_FOUND);
 
-        let graph = Graph::load(test.directory()).await?;
-        assert!(!graph.has_node("adapter_to_delete"));
+        let metadata = Metadata::load(test.directory()).await?;
+        assert!(metadata.get_node("adapter_to_delete").is_none());
 
         Ok(())
     }
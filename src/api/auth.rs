@@ -0,0 +1,246 @@
+use crate::api::{AppError, app_error};
+use crate::auth::{Role, TokenStore};
+use crate::config::Config;
+use axum::{
+    Extension, Router,
+    extract::Request,
+    http::{Method, StatusCode, header},
+    middleware::Next,
+    response::{Json, Response},
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub auth_enabled: bool,
+    pub name: Option<String>,
+    pub role: Role,
+}
+
+pub fn routes() -> Router {
+    Router::new().route("/auth/login", post(login))
+}
+
+/// Lets the UI check a token before storing it and learn which role it has.
+async fn login(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let config = config.lock().await;
+    if !auth_enabled(&config) {
+        return Ok(Json(LoginResponse {
+            auth_enabled: false,
+            name: None,
+            role: Role::Admin,
+        }));
+    }
+
+    match TokenStore::new(&config.project_dir).authenticate(&req.token)? {
+        Some(token) => Ok(Json(LoginResponse {
+            auth_enabled: true,
+            name: Some(token.name),
+            role: token.role,
+        })),
+        None => app_error(StatusCode::UNAUTHORIZED),
+    }
+}
+
+fn auth_enabled(config: &Config) -> bool {
+    config.project.api.as_ref().is_some_and(|api| api.auth)
+}
+
+/// Rejects requests without a valid bearer token when auth is enabled, and requests
+/// that need a higher role than the token has. Handlers can read the caller's [`Role`]
+/// from the request extensions. Without auth callers are read-only, so ad-hoc SQL stays
+/// limited, unless `api.unrestricted_queries` is set.
+pub async fn require_token(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let path = request.uri().path();
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    let mut role = Role::ReadOnly;
+
+    if path != "/auth/login" {
+        let config = config.lock().await;
+        if auth_enabled(&config) {
+            let Some(value) = request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+            else {
+                return app_error(StatusCode::UNAUTHORIZED);
+            };
+
+            let Some(token) = TokenStore::new(&config.project_dir).authenticate(value.trim())?
+            else {
+                return app_error(StatusCode::UNAUTHORIZED);
+            };
+
+            if token.role != Role::Admin && requires_admin(request.method(), path) {
+                return app_error(StatusCode::FORBIDDEN);
+            }
+            role = token.role;
+        } else if unrestricted_queries(&config) {
+            role = Role::Admin;
+        }
+    }
+
+    request.extensions_mut().insert(role);
+    Ok(next.run(request).await)
+}
+
+fn unrestricted_queries(config: &Config) -> bool {
+    config
+        .project
+        .api
+        .as_ref()
+        .is_some_and(|api| api.unrestricted_queries)
+}
+
+fn requires_admin(method: &Method, path: &str) -> bool {
+    if path.starts_with("/connections") || path.starts_with("/secrets") {
+        return true;
+    }
+
+    let runs_query = *method == Method::POST
+        && (path == "/query"
+            || path == "/query/export"
+            || (path.starts_with("/query/") && path.ends_with("/cancel"))
+            || (path.starts_with("/queries/") && path.ends_with("/run")));
+
+    !(method.is_safe() || runs_query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::project::ApiConfig;
+    use crate::test_helpers::TestManager;
+    use axum::{middleware, routing::get};
+    use serde_json::json;
+
+    fn test_routes() -> Router {
+        let routes = Router::new()
+            .merge(routes())
+            .route(
+                "/models",
+                get(|| async { "models" }).post(|| async { "created" }),
+            )
+            .route("/connections", get(|| async { "connections" }))
+            .route("/query", post(|| async { "rows" }));
+
+        Router::new()
+            .nest("/api", routes)
+            .layer(middleware::from_fn(require_token))
+    }
+
+    #[test]
+    fn test_requires_admin() {
+        assert!(!requires_admin(&Method::GET, "/models"));
+        assert!(!requires_admin(&Method::POST, "/query"));
+        assert!(!requires_admin(&Method::POST, "/queries/daily/run"));
+        assert!(!requires_admin(&Method::POST, "/query/export"));
+        assert!(!requires_admin(&Method::POST, "/query/3f2a/cancel"));
+        assert!(requires_admin(&Method::POST, "/pipeline/run"));
+        assert!(requires_admin(&Method::PUT, "/models/users"));
+        assert!(requires_admin(&Method::GET, "/connections/warehouse"));
+        assert!(requires_admin(&Method::GET, "/secrets"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_disabled_allows_anonymous_requests() {
+        let test = TestManager::new();
+        let server = test.setup_project(test_routes);
+
+        server.get("/api/connections").await.assert_status_ok();
+        server.post("/api/models").await.assert_status_ok();
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&json!({"token": "anything"}))
+            .await;
+        let login: LoginResponse = response.json();
+        assert!(!login.auth_enabled);
+    }
+
+    #[tokio::test]
+    async fn test_auth_enforces_roles() -> anyhow::Result<()> {
+        let test = TestManager::new();
+        test.config().await.project.api = Some(ApiConfig {
+            auth: true,
+            cors_origins: vec![],
+            unrestricted_queries: false,
+        });
+        let store = TokenStore::new(test.directory());
+        let admin = store.create("admin", Role::Admin)?;
+        let reader = store.create("reader", Role::ReadOnly)?;
+        let server = test.setup_project(test_routes);
+
+        server.get("/api/models").await.assert_status_unauthorized();
+        server
+            .get("/api/models")
+            .authorization_bearer("fbx_invalid")
+            .await
+            .assert_status_unauthorized();
+
+        server
+            .get("/api/models")
+            .authorization_bearer(&reader)
+            .await
+            .assert_status_ok();
+        server
+            .post("/api/query")
+            .authorization_bearer(&reader)
+            .await
+            .assert_status_ok();
+        server
+            .post("/api/models")
+            .authorization_bearer(&reader)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        server
+            .get("/api/connections")
+            .authorization_bearer(&reader)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+
+        server
+            .post("/api/models")
+            .authorization_bearer(&admin)
+            .await
+            .assert_status_ok();
+        server
+            .get("/api/connections")
+            .authorization_bearer(&admin)
+            .await
+            .assert_status_ok();
+
+        let response = server
+            .post("/api/auth/login")
+            .json(&json!({"token": reader}))
+            .await;
+        response.assert_status_ok();
+        let login: LoginResponse = response.json();
+        assert_eq!(login.name.as_deref(), Some("reader"));
+        assert_eq!(login.role, Role::ReadOnly);
+
+        server
+            .post("/api/auth/login")
+            .json(&json!({"token": "fbx_invalid"}))
+            .await
+            .assert_status_unauthorized();
+
+        Ok(())
+    }
+}
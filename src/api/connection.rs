@@ -44,9 +44,9 @@ pub fn routes() -> Router {
 async fn list_connections(
     Extension(config): Extension<Arc<Mutex<Config>>>,
 ) -> Result<Json<Vec<ConnectionSummary>>, AppError> {
-    let config = config.lock().await;
+    let project = config.lock().await.project_source()?;
     let mut connections = Vec::new();
-    for (name, conn_config) in &config.project.connections {
+    for (name, conn_config) in &project.connections {
         let (connection_type, details) = match conn_config {
             ConnectionConfig::LocalFile { base_path } => {
                 ("localfile".to_string(), base_path.clone())
@@ -76,9 +76,9 @@ async fn get_connection(
     Extension(config): Extension<Arc<Mutex<Config>>>,
     Path(name): Path<String>,
 ) -> Result<Json<ConnectionConfig>, AppError> {
-    let config = config.lock().await;
-    match config.project.connections.get(&name) {
-        Some(conn_config) => Ok(Json(conn_config.clone())),
+    let mut project = config.lock().await.project_source()?;
+    match project.connections.remove(&name) {
+        Some(conn_config) => Ok(Json(conn_config.masked())),
         None => app_error(StatusCode::NOT_FOUND),
     }
 }
@@ -88,15 +88,22 @@ async fn create_connection(
     Json(req): Json<CreateConnectionRequest>,
 ) -> Result<StatusCode, AppError> {
     let mut config = config.lock().await;
-    let mut project_config = config.project.clone();
+    let mut project_config = config.project_source()?;
 
     if project_config.connections.contains_key(&req.name) {
         return app_error(StatusCode::CONFLICT);
     }
 
-    project_config
-        .connections
-        .insert(req.name.clone(), req.config.clone());
+    let mut connection = req.config;
+    if let Err(e) = connection.restore_masked(None) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_connection",
+            e.to_string(),
+        ));
+    }
+
+    project_config.connections.insert(req.name, connection);
     let project_file = config.add_project_setting(&project_config)?;
     project_file.save()?;
 
@@ -109,13 +116,23 @@ async fn update_connection(
     Json(req): Json<UpdateConnectionRequest>,
 ) -> Result<StatusCode, AppError> {
     let mut config = config.lock().await;
-    let mut project_config = config.project.clone();
+    let mut project_config = config.project_source()?;
 
-    if !project_config.connections.contains_key(&name) {
+    let Some(stored) = project_config.connections.get(&name) else {
         return app_error(StatusCode::NOT_FOUND);
+    };
+
+    // Credentials are write-only: the UI sends back the mask for unchanged ones.
+    let mut connection = req.config;
+    if let Err(e) = connection.restore_masked(Some(stored)) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_connection",
+            e.to_string(),
+        ));
     }
 
-    project_config.connections.insert(name, req.config.clone());
+    project_config.connections.insert(name, connection);
     let project_file = config.add_project_setting(&project_config)?;
     project_file.save()?;
 
@@ -127,7 +144,7 @@ async fn delete_connection(
     Path(name): Path<String>,
 ) -> Result<StatusCode, AppError> {
     let mut config = config.lock().await;
-    let mut project_config = config.project.clone();
+    let mut project_config = config.project_source()?;
 
     if !project_config.connections.contains_key(&name) {
         return app_error(StatusCode::NOT_FOUND);
@@ -140,6 +157,62 @@ async fn delete_connection(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[cfg(test)]
+mod redaction_tests {
+    use super::*;
+    use crate::redact::MASK;
+    use crate::test_helpers::TestManager;
+    use serde_json::{Value, json};
+
+    #[tokio::test]
+    async fn test_credentials_are_write_only() {
+        let test = TestManager::new();
+        let server = test.setup_project(routes);
+
+        server
+            .post("/connections")
+            .json(&json!({
+                "name": "warehouse",
+                "config": {
+                    "type": "postgresql",
+                    "host": "localhost",
+                    "port": 5432,
+                    "database": "analytics",
+                    "username": "reader",
+                    "password": "hunter2"
+                }
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let response = server.get("/connections/warehouse").await;
+        let mut connection: Value = response.json();
+        assert_eq!(connection["password"], MASK);
+
+        connection["host"] = json!("db.internal");
+        server
+            .put("/connections/warehouse")
+            .json(&json!({ "config": connection }))
+            .await
+            .assert_status_ok();
+
+        let project = test.config().await.project_source().unwrap();
+        let ConnectionConfig::PostgreSql { host, password, .. } = &project.connections["warehouse"]
+        else {
+            panic!("expected a postgresql connection");
+        };
+        assert_eq!(host, "db.internal");
+        assert_eq!(password, "hunter2");
+
+        connection["password"] = json!(MASK);
+        server
+            .post("/connections")
+            .json(&json!({ "name": "copy", "config": connection }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
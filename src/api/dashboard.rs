@@ -1,11 +1,12 @@
 use crate::api::{AppError, app_error};
 use crate::config::Config;
 use crate::config::dashboard::{ChartType, DashboardConfig};
-use crate::pipeline::ducklake::DuckLake;
+use crate::pipeline::ducklake::SharedDuckLake;
 use axum::{Extension, Router, extract::Path, http::StatusCode, response::Json, routing::get};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 pub fn router() -> Router {
     Router::new()
@@ -98,7 +99,7 @@ async fn create_dashboard(
             chart_type: match request.chart.chart_type.as_str() {
                 "line" => ChartType::Line,
                 "bar" => ChartType::Bar,
-                _ => return app_error(StatusCode::BAD_REQUEST),
+                chart_type => return Err(unknown_chart_type(chart_type)),
             },
             x_column: request.chart.x_column,
             y_column: request.chart.y_column,
@@ -129,7 +130,7 @@ async fn update_dashboard(
             chart_type: match request.chart.chart_type.as_str() {
                 "line" => ChartType::Line,
                 "bar" => ChartType::Bar,
-                _ => return app_error(StatusCode::BAD_REQUEST),
+                chart_type => return Err(unknown_chart_type(chart_type)),
             },
             x_column: request.chart.x_column,
             y_column: request.chart.y_column,
@@ -159,23 +160,37 @@ async fn delete_dashboard(
 
 async fn get_dashboard_data(
     Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
     Path(name): Path<String>,
 ) -> Result<Json<DashboardDataResponse>, AppError> {
     let config = config.lock().await;
     let dashboard_config = match config.dashboards.get(&name) {
-        Some(c) => c,
+        Some(c) => c.clone(),
         None => return app_error(StatusCode::NOT_FOUND),
     };
 
-    let query_config = match config.queries.get(&dashboard_config.query) {
-        Some(q) => q,
+    let sql = match config.queries.get(&dashboard_config.query) {
+        Some(q) => q.sql.clone(),
         None => return app_error(StatusCode::NOT_FOUND),
     };
 
-    let ducklake = DuckLake::from_config(&config).await?;
+    let ducklake = shared_ducklake.get(&config).await?;
+    let timeout = config
+        .project
+        .engine
+        .clone()
+        .unwrap_or_default()
+        .query_timeout();
+    drop(config);
 
-    let describe_sql = format!("DESCRIBE ({})", query_config.sql);
-    let describe_results = ducklake.query(&describe_sql)?;
+    let (describe_results, query_results) = ducklake
+        .run_interruptible(timeout, &CancellationToken::new(), move |ducklake| {
+            let describe_results = ducklake.query(&format!("DESCRIBE ({sql})"))?;
+            let query_results = ducklake.query(&sql)?;
+            Ok((describe_results, query_results))
+        })
+        .await
+        .map_err(AppError::sql)?;
 
     let mut x_column_index = None;
     let mut y_column_index = None;
@@ -192,10 +207,8 @@ async fn get_dashboard_data(
         }
     }
 
-    let x_idx = x_column_index.ok_or(AppError::StatusCode(StatusCode::BAD_REQUEST))?;
-    let y_idx = y_column_index.ok_or(AppError::StatusCode(StatusCode::BAD_REQUEST))?;
-
-    let query_results = ducklake.query(&query_config.sql)?;
+    let x_idx = x_column_index.ok_or_else(|| unknown_column(&dashboard_config.chart.x_column))?;
+    let y_idx = y_column_index.ok_or_else(|| unknown_column(&dashboard_config.chart.y_column))?;
 
     let mut labels = Vec::new();
     let mut values = Vec::new();
@@ -217,6 +230,24 @@ async fn get_dashboard_data(
     Ok(Json(DashboardDataResponse { labels, values }))
 }
 
+fn unknown_chart_type(chart_type: &str) -> AppError {
+    AppError::new(
+        StatusCode::BAD_REQUEST,
+        "unknown_chart_type",
+        format!("Unknown chart type '{chart_type}', expected 'line' or 'bar'"),
+    )
+    .with_details(serde_json::json!({ "chart_type": chart_type }))
+}
+
+fn unknown_column(column: &str) -> AppError {
+    AppError::new(
+        StatusCode::BAD_REQUEST,
+        "unknown_column",
+        format!("Column '{column}' is not in the dashboard query's results"),
+    )
+    .with_details(serde_json::json!({ "column_name": column }))
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
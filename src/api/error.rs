@@ -0,0 +1,299 @@
+use crate::dependency::GraphError;
+use crate::pipeline::ducklake::Interrupted;
+use crate::redact::redact;
+use axum::{
+    body::to_bytes,
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::sync::LazyLock;
+
+/// Largest plain-text error body that [`json_errors`] wraps; longer ones are replaced
+/// by the status reason.
+const MAX_TEXT_ERROR_BYTES: usize = 64 * 1024;
+
+/// The JSON body of every error response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    /// Stable, machine-readable error code, e.g. `not_found` or `sql_parse_error`.
+    pub code: String,
+    pub message: String,
+    /// Where the error is, e.g. the SQL line and column or the missing table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+pub enum AppError {
+    StatusCode(StatusCode),
+    Exception(anyhow::Error),
+    Body(StatusCode, ErrorBody),
+}
+
+pub fn app_error<T>(status_code: StatusCode) -> Result<T, AppError> {
+    Err(AppError::StatusCode(status_code))
+}
+
+impl AppError {
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        AppError::Body(
+            status,
+            ErrorBody {
+                code: code.to_string(),
+                message: message.into(),
+                details: None,
+            },
+        )
+    }
+
+    pub fn with_details(self, details: Value) -> Self {
+        match self {
+            AppError::Body(status, body) => AppError::Body(
+                status,
+                ErrorBody {
+                    details: Some(details),
+                    ..body
+                },
+            ),
+            error => error,
+        }
+    }
+
+    /// A query or statement written by the user failed, so the DuckDB error is returned
+    /// to them instead of being logged as a server error.
+    pub fn sql(error: anyhow::Error) -> Self {
+        match error.downcast::<Interrupted>() {
+            Ok(interrupted) => interrupted.into_app_error(),
+            Err(error) => {
+                let message = redact(&format!("{error:#}"));
+                let details = sql_error_details(&message);
+                let error = AppError::new(StatusCode::BAD_REQUEST, "query_error", message);
+                match details {
+                    Some(details) => error.with_details(details),
+                    None => error,
+                }
+            }
+        }
+    }
+}
+
+impl Interrupted {
+    fn into_app_error(self) -> AppError {
+        match self {
+            Interrupted::TimedOut(_) => {
+                AppError::new(StatusCode::REQUEST_TIMEOUT, "timeout", self.to_string())
+            }
+            Interrupted::Cancelled => {
+                AppError::new(StatusCode::CONFLICT, "cancelled", self.to_string())
+            }
+        }
+    }
+}
+
+impl GraphError {
+    fn into_app_error(self) -> AppError {
+        let message = self.to_string();
+        let (code, details) = match self {
+            GraphError::CircularDependency { nodes } => {
+                ("circular_dependency", json!({ "nodes": nodes }))
+            }
+            GraphError::NonExistentTableReference {
+                model_name,
+                table_name,
+            } => (
+                "unknown_table",
+                json!({ "model": model_name, "table_name": table_name }),
+            ),
+            GraphError::SqlParseError { model_name, error } => {
+                let mut details = sql_error_details(&error).unwrap_or_else(|| json!({}));
+                details["model"] = json!(model_name);
+                ("sql_parse_error", details)
+            }
+        };
+
+        AppError::new(StatusCode::UNPROCESSABLE_ENTITY, code, message).with_details(details)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self {
+            AppError::StatusCode(status_code) => {
+                status_error(status_code, status_reason(status_code)).into_response()
+            }
+            AppError::Body(status_code, body) => (status_code, Json(body)).into_response(),
+            AppError::Exception(error) => {
+                let error = match error.downcast::<GraphError>() {
+                    Ok(graph_error) => return graph_error.into_app_error().into_response(),
+                    Err(error) => error,
+                };
+                let error = match error.downcast::<Interrupted>() {
+                    Ok(interrupted) => return interrupted.into_app_error().into_response(),
+                    Err(error) => error,
+                };
+
+                let message = redact(&error.to_string());
+                tracing::error!("{}", message);
+                eprintln!("{}", message);
+                AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+                    .into_response()
+            }
+        }
+    }
+}
+
+impl<E> From<E> for AppError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        Self::Exception(err.into())
+    }
+}
+
+fn status_error(status: StatusCode, message: &str) -> AppError {
+    let code = match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::REQUEST_TIMEOUT => "timeout",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PRECONDITION_FAILED => "precondition_failed",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::UNPROCESSABLE_ENTITY => "invalid_request",
+        status if status.is_server_error() => "internal_error",
+        _ => "error",
+    };
+    AppError::new(status, code, message)
+}
+
+fn status_reason(status: StatusCode) -> &'static str {
+    status.canonical_reason().unwrap_or("Error")
+}
+
+/// Gives error responses that weren't built from an [`AppError`], such as rejected
+/// request bodies or unknown routes, the same JSON shape.
+pub async fn json_errors(response: Response) -> Response {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let text = to_bytes(body, MAX_TEXT_ERROR_BYTES)
+        .await
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+        .filter(|text| !text.trim().is_empty());
+    let message = text.unwrap_or_else(|| status_reason(status).to_string());
+
+    let error = status_error(status, message.trim()).into_response();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, error.into_body())
+}
+
+static LINE_COLUMN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Line: (\d+), Column: (\d+)").unwrap());
+static DUCKDB_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bLINE (\d+):").unwrap());
+static MISSING_COLUMN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"Referenced column "([^"]+)" not found"#).unwrap());
+static MISSING_TABLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Table with name ([^\s!]+) does not exist").unwrap());
+
+/// Pulls the location of a SQL error out of sqlparser and DuckDB error messages.
+fn sql_error_details(message: &str) -> Option<Value> {
+    let mut details = Map::new();
+
+    if let Some(captures) = LINE_COLUMN.captures(message) {
+        details.insert("line".to_string(), json!(captures[1].parse::<u64>().ok()));
+        details.insert("column".to_string(), json!(captures[2].parse::<u64>().ok()));
+    } else if let Some(captures) = DUCKDB_LINE.captures(message) {
+        details.insert("line".to_string(), json!(captures[1].parse::<u64>().ok()));
+    }
+    if let Some(captures) = MISSING_COLUMN.captures(message) {
+        details.insert("column_name".to_string(), json!(&captures[1]));
+    }
+    if let Some(captures) = MISSING_TABLE.captures(message) {
+        details.insert("table_name".to_string(), json!(&captures[1]));
+    }
+
+    (!details.is_empty()).then_some(Value::Object(details))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_error_details() {
+        assert_eq!(
+            sql_error_details(
+                "sql parser error: Expected: an expression, found: FROM at Line: 2, Column: 8"
+            ),
+            Some(json!({ "line": 2, "column": 8 }))
+        );
+        assert_eq!(
+            sql_error_details(
+                "Binder Error: Referenced column \"amount\" not found in FROM clause!\nLINE 1: SELECT amount FROM orders"
+            ),
+            Some(json!({ "line": 1, "column_name": "amount" }))
+        );
+        assert_eq!(
+            sql_error_details("Catalog Error: Table with name users does not exist!"),
+            Some(json!({ "table_name": "users" }))
+        );
+        assert_eq!(sql_error_details("Something else went wrong"), None);
+    }
+
+    #[tokio::test]
+    async fn test_error_bodies() {
+        let response = app_error::<()>(StatusCode::NOT_FOUND)
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: ErrorBody =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body.code, "not_found");
+        assert_eq!(body.message, "Not Found");
+
+        let error = GraphError::NonExistentTableReference {
+            model_name: "orders_summary".to_string(),
+            table_name: "orderz".to_string(),
+        };
+        let response = AppError::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: ErrorBody =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body.code, "unknown_table");
+        assert_eq!(
+            body.details,
+            Some(json!({ "model": "orders_summary", "table_name": "orderz" }))
+        );
+
+        let response =
+            json_errors((StatusCode::UNPROCESSABLE_ENTITY, "missing field `sql`").into_response())
+                .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: ErrorBody =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body.code, "invalid_request");
+        assert_eq!(body.message, "missing field `sql`");
+    }
+}
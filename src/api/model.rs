@@ -1,7 +1,8 @@
 use crate::api::{AppError, app_error};
 use crate::config::Config;
 use crate::config::model::ModelConfig;
-use crate::core::graph::{Graph, dependent_tables};
+use crate::dependency::model_dependencies;
+use crate::metadata::Metadata;
 use anyhow::Result;
 use axum::Extension;
 use axum::extract::Path;
@@ -78,13 +79,11 @@ async fn create_model(
         return app_error(StatusCode::CONFLICT);
     }
 
-    let dependencies = dependent_tables(&model.config.sql)
-        .map_err(|_| AppError::from(anyhow::anyhow!("Failed to parse SQL")))?;
+    let dependencies = model_dependencies(&config, &model.name, &model.config)?;
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    let deps: Vec<&str> = dependencies.iter().map(|s| s.as_str()).collect();
-    graph.create_node(&model.name, &deps);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.set_dependencies(&model.name, dependencies);
+    metadata.save(&config.project_dir).await?;
 
     let model_file = config.upsert_model(&model.name, &model.config)?;
     model_file.save()?;
@@ -103,14 +102,12 @@ async fn update_model(
         return app_error(StatusCode::NOT_FOUND);
     };
 
-    let dependencies = dependent_tables(&model.sql)
-        .map_err(|_| AppError::from(anyhow::anyhow!("Failed to parse SQL")))?;
+    let dependencies = model_dependencies(&config, &name, &model)?;
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    let deps: Vec<&str> = dependencies.iter().map(|s| s.as_str()).collect();
-    graph.update_dependencies(&name, &deps);
-    graph.update_node(&name);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.set_dependencies(&name, dependencies);
+    metadata.invalidate(&name);
+    metadata.save(&config.project_dir).await?;
 
     let model_file = config.upsert_model(&name, &model)?;
     model_file.save()?;
@@ -128,9 +125,9 @@ async fn delete_model(
         return app_error(StatusCode::NOT_FOUND);
     };
 
-    let mut graph = Graph::load(&config.project_dir).await?;
-    graph.delete_node(&name);
-    graph.save(&config.project_dir).await?;
+    let mut metadata = Metadata::load(&config.project_dir).await?;
+    metadata.remove_node(&name);
+    metadata.save(&config.project_dir).await?;
 
     let model_file = config.delete_model(&name)?;
     model_file.save()?;
@@ -141,6 +138,7 @@ async fn delete_model(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::model::Materialization;
     use crate::test_helpers::TestManager;
     use anyhow::Result;
     use serde_json::json;
@@ -152,9 +150,14 @@ mod tests {
 
         let users_adapter = crate::config::adapter::AdapterConfig {
             connection: "test_connection".to_string(),
+            mode: crate::config::adapter::ImportMode::Full,
+            tests: vec![],
             description: Some("Users table".to_string()),
             source: crate::config::adapter::AdapterSource::Database {
-                table_name: "users".to_string(),
+                table_name: Some("users".to_string()),
+                query: None,
+                cursor_column: None,
+                primary_key: vec![],
             },
             columns: vec![],
         };
@@ -165,9 +168,9 @@ mod tests {
             adapter_file.save()?;
         }
 
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("users", &[]);
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("users", vec![]);
+        metadata.save(test.directory()).await?;
 
         let new_model = json!({
             "name": "active_users",
@@ -180,15 +183,33 @@ mod tests {
         let response = server.post("/models").json(&new_model).await;
         response.assert_status_ok();
 
-        let graph = Graph::load(test.directory()).await?;
-        assert!(graph.has_node("active_users"));
-
+        let graph = Metadata::load(test.directory()).await?.graph();
         let upstream = graph.upstream("active_users");
         assert_eq!(upstream, vec!["users"]);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_create_model_with_invalid_sql() {
+        let test = TestManager::new();
+        let server = test.setup_project(routes);
+
+        let new_model = json!({
+            "name": "broken",
+            "config": {
+                "sql": "SELECT (1 FROM users"
+            }
+        });
+
+        let response = server.post("/models").json(&new_model).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["code"], "sql_parse_error");
+        assert_eq!(error["details"]["model"], "broken");
+        assert_eq!(error["details"]["line"], 1);
+    }
+
     #[tokio::test]
     async fn test_update_model() -> Result<()> {
         let test = TestManager::new();
@@ -196,18 +217,28 @@ mod tests {
 
         let users_adapter = crate::config::adapter::AdapterConfig {
             connection: "test_connection".to_string(),
+            mode: crate::config::adapter::ImportMode::Full,
+            tests: vec![],
             description: Some("Users table".to_string()),
             source: crate::config::adapter::AdapterSource::Database {
-                table_name: "users".to_string(),
+                table_name: Some("users".to_string()),
+                query: None,
+                cursor_column: None,
+                primary_key: vec![],
             },
             columns: vec![],
         };
 
         let orders_adapter = crate::config::adapter::AdapterConfig {
             connection: "test_connection".to_string(),
+            mode: crate::config::adapter::ImportMode::Full,
+            tests: vec![],
             description: Some("Orders table".to_string()),
             source: crate::config::adapter::AdapterSource::Database {
-                table_name: "orders".to_string(),
+                table_name: Some("orders".to_string()),
+                query: None,
+                cursor_column: None,
+                primary_key: vec![],
             },
             columns: vec![],
         };
@@ -218,13 +249,16 @@ mod tests {
             config.upsert_adapter("orders", &orders_adapter)?.save()?;
         }
 
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("users", &[]);
-        graph.create_node("orders", &[]);
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("users", vec![]);
+        metadata.set_dependencies("orders", vec![]);
+        metadata.save(test.directory()).await?;
 
         let original_model = ModelConfig {
             description: Some("Original model".to_string()),
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
             sql: "SELECT * FROM users".to_string(),
         };
 
@@ -234,10 +268,10 @@ mod tests {
             model_file.save()?;
         }
 
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("test_model", &["users"]);
-        graph.set_current_time("test_model");
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("test_model", vec!["users".to_string()]);
+        metadata.update_node_timestamp("test_model", chrono::Utc::now());
+        metadata.save(test.directory()).await?;
 
         let updated_config = json!({
             "description": "Updated model",
@@ -254,14 +288,13 @@ mod tests {
         assert_eq!(model_config.description, Some("Updated model".to_string()));
         assert!(model_config.sql.contains("JOIN orders"));
 
-        let graph = Graph::load(test.directory()).await?;
-        assert!(graph.has_node("test_model"));
-
-        let mut upstream = graph.upstream("test_model");
+        let metadata = Metadata::load(test.directory()).await?;
+        let mut upstream = metadata.graph().upstream("test_model");
         upstream.sort();
         assert_eq!(upstream, vec!["orders", "users"]);
 
-        assert!(graph.get_node("test_model").unwrap().updated_at.is_none());
+        let node = metadata.get_node("test_model").unwrap();
+        assert!(node.last_updated_at.is_none());
 
         Ok(())
     }
@@ -273,6 +306,9 @@ mod tests {
 
         let model_config = ModelConfig {
             description: Some("Model to delete".to_string()),
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
             sql: "SELECT * FROM test_table".to_string(),
         };
 
@@ -282,9 +318,9 @@ mod tests {
             model_file.save()?;
         }
 
-        let mut graph = Graph::load(test.directory()).await?;
-        graph.create_node("model_to_delete", &[]);
-        graph.save(test.directory()).await?;
+        let mut metadata = Metadata::load(test.directory()).await?;
+        metadata.set_dependencies("model_to_delete", vec![]);
+        metadata.save(test.directory()).await?;
 
         let get_response = server.get("/models/model_to_delete").await;
         get_response.assert_status_ok();
@@ -295,8 +331,8 @@ mod tests {
         let get_response_after = server.get("/models/model_to_delete").await;
         get_response_after.assert_status(StatusCode::NOT_FOUND);
 
-        let graph = Graph::load(test.directory()).await?;
-        assert!(!graph.has_node("model_to_delete"));
+        let metadata = Metadata::load(test.directory()).await?;
+        assert!(metadata.get_node("model_to_delete").is_none());
 
         Ok(())
     }
@@ -2,13 +2,19 @@ use super::run;
 use crate::{
     api::AppError,
     config::Config,
-    metadata::{Metadata, Node},
-    status::{PipelineStatus, StatusManager},
+    metadata::Metadata,
+    status::{ErrorInfo, Phase, PipelineStatus, StatusManager},
 };
 use anyhow::Result;
-use axum::{Extension, Router, response::Json, routing::get};
+use axum::{
+    Extension, Router,
+    extract::{Path as AxumPath, Query},
+    response::Json,
+    routing::get,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 
 #[derive(Serialize, Deserialize)]
@@ -16,9 +22,84 @@ pub struct PipelineStatusResponse {
     pub pipeline: Option<PipelineStatus>,
 }
 
+#[derive(Deserialize)]
+pub struct RunListQuery {
+    pub phase: Option<Phase>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default = "default_page")]
+    pub page: usize,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    #[serde(default = "default_page")]
+    pub page: usize,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+fn default_page() -> usize {
+    1
+}
+
+fn default_per_page() -> usize {
+    20
+}
+
+const MAX_PER_PAGE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub phase: Phase,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    pub task_count: usize,
+    pub failed_task_count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RunListResponse {
+    pub runs: Vec<RunSummary>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TableRunEntry {
+    pub run_id: String,
+    pub phase: Phase,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    pub error: Option<ErrorInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TableHistoryResponse {
+    pub table_name: String,
+    pub last_succeeded_at: Option<DateTime<Utc>>,
+    pub runs: Vec<TableRunEntry>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GraphResponse {
-    pub nodes: HashMap<String, Node>,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub last_updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -30,6 +111,11 @@ pub struct GraphEdge {
 pub fn routes() -> Router {
     Router::new()
         .route("/pipeline/status", get(handle_get_latest_status))
+        .route("/pipeline/runs", get(handle_list_runs))
+        .route(
+            "/pipeline/tables/{table_name}/history",
+            get(handle_get_table_history),
+        )
         .route("/graph", get(handle_get_graph))
         .nest("/pipeline", run::routes())
 }
@@ -45,14 +131,158 @@ async fn handle_get_latest_status(
     Ok(Json(PipelineStatusResponse { pipeline: status }))
 }
 
+fn paginate<T>(items: Vec<T>, page: usize, per_page: usize) -> (Vec<T>, usize, usize) {
+    let page = page.max(1);
+    let per_page = per_page.clamp(1, MAX_PER_PAGE);
+    let items = items
+        .into_iter()
+        .skip(page.saturating_sub(1).saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+
+    (items, page, per_page)
+}
+
+async fn handle_list_runs(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Query(query): Query<RunListQuery>,
+) -> Result<Json<RunListResponse>, AppError> {
+    let project_root = config.lock().await.project_dir.clone();
+
+    let runs: Vec<RunSummary> = StatusManager::list_runs(&project_root)
+        .await?
+        .into_iter()
+        .filter(|(_, status)| {
+            query
+                .phase
+                .as_ref()
+                .is_none_or(|phase| status.phase == *phase)
+        })
+        .filter(|(_, status)| {
+            query
+                .from
+                .is_none_or(|from| status.started_at.is_some_and(|started| started >= from))
+        })
+        .filter(|(_, status)| {
+            query
+                .to
+                .is_none_or(|to| status.started_at.is_some_and(|started| started <= to))
+        })
+        .map(|(run_id, status)| RunSummary {
+            run_id,
+            phase: status.phase.clone(),
+            started_at: status.started_at,
+            completed_at: status.completed_at,
+            duration_ms: status.duration_ms(),
+            task_count: status.tasks.len(),
+            failed_task_count: status
+                .tasks
+                .values()
+                .filter(|task| task.phase == Phase::Failed)
+                .count(),
+        })
+        .collect();
+
+    let total = runs.len();
+    let (runs, page, per_page) = paginate(runs, query.page, query.per_page);
+
+    Ok(Json(RunListResponse {
+        runs,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+async fn handle_get_table_history(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    AxumPath(table_name): AxumPath<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<TableHistoryResponse>, AppError> {
+    let project_root = config.lock().await.project_dir.clone();
+
+    let runs: Vec<TableRunEntry> = StatusManager::list_runs(&project_root)
+        .await?
+        .into_iter()
+        .filter_map(|(run_id, mut status)| {
+            status.tasks.remove(&table_name).map(|task| TableRunEntry {
+                run_id,
+                phase: task.phase.clone(),
+                started_at: task.started_at,
+                completed_at: task.completed_at,
+                duration_ms: task.duration_ms(),
+                error: task.error,
+            })
+        })
+        .collect();
+
+    let last_succeeded_at = runs
+        .iter()
+        .find(|entry| entry.phase == Phase::Completed)
+        .and_then(|entry| entry.completed_at);
+
+    let total = runs.len();
+    let (runs, page, per_page) = paginate(runs, query.page, query.per_page);
+
+    Ok(Json(TableHistoryResponse {
+        table_name,
+        last_succeeded_at,
+        runs,
+        total,
+        page,
+        per_page,
+    }))
+}
+
 async fn handle_get_graph(
     Extension(config): Extension<Arc<Mutex<Config>>>,
 ) -> Result<Json<GraphResponse>, AppError> {
     let config = config.lock().await;
     let project_root = config.project_dir.clone();
-    let metadata = Metadata::load(&project_root).await.unwrap_or_default();
+    let metadata = Metadata::load(&project_root).await?;
+    let graph = metadata.graph();
 
     Ok(Json(GraphResponse {
-        nodes: metadata.nodes,
+        nodes: graph
+            .nodes
+            .into_iter()
+            .map(|node| GraphNode {
+                last_updated_at: metadata
+                    .get_node(&node.name)
+                    .and_then(|n| n.last_updated_at),
+                name: node.name,
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .into_iter()
+            .map(|edge| GraphEdge {
+                from: edge.from,
+                to: edge.to,
+            })
+            .collect(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let (items, page, per_page) = paginate((0..25).collect(), 2, 10);
+        assert_eq!(items, (10..20).collect::<Vec<_>>());
+        assert_eq!((page, per_page), (2, 10));
+
+        let (items, page, _) = paginate((0..25).collect::<Vec<i32>>(), 0, 10);
+        assert_eq!(items.len(), 10);
+        assert_eq!(page, 1);
+    }
+
+    #[test]
+    fn test_paginate_large_page() {
+        let (items, page, per_page) = paginate((0..25).collect::<Vec<i32>>(), usize::MAX, 100);
+        assert!(items.is_empty());
+        assert_eq!((page, per_page), (usize::MAX, 100));
+    }
+}
@@ -1,22 +1,109 @@
-use crate::api::{AppError, app_error};
+use crate::api::{AppError, app_error, sql_guard::check_read_only};
+use crate::auth::Role;
 use crate::config::{Config, QueryConfig};
-use crate::pipeline::ducklake::DuckLake;
+use crate::pipeline::ducklake::{DuckLake, ExportFormat, QueryPage, SharedDuckLake};
 use anyhow::Result;
 use axum::{
     Extension, Router,
-    extract::Path as AxumPath,
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Path as AxumPath, Query},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_PAGE_SIZE: usize = 1_000;
+/// Upper bound on rows per request, so a `SELECT *` can't exhaust server or browser memory.
+/// Use the export endpoints for full results.
+const MAX_PAGE_SIZE: usize = 10_000;
+
+#[derive(Deserialize)]
+pub struct PageParams {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl PageParams {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
 
 #[derive(Deserialize)]
 pub struct QueryRequest {
     pub sql: String,
+    /// Client-chosen id that `POST /query/{id}/cancel` can refer to.
+    pub query_id: Option<String>,
+    /// Shortens the engine's `query_timeout_secs` for this request.
+    pub timeout_secs: Option<u64>,
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+/// Ad-hoc queries that are running and were given a `query_id`.
+#[derive(Default)]
+pub struct RunningQueries {
+    tokens: std::sync::Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl RunningQueries {
+    /// Returns `None` if a query with the same id is already running.
+    fn register(&self, query_id: &str) -> Option<RunningQuery<'_>> {
+        let mut tokens = self.tokens.lock().unwrap();
+        if tokens.contains_key(query_id) {
+            return None;
+        }
+
+        let token = CancellationToken::new();
+        tokens.insert(query_id.to_string(), token.clone());
+        Some(RunningQuery {
+            queries: self,
+            query_id: query_id.to_string(),
+            token,
+        })
+    }
+
+    fn cancel(&self, query_id: &str) -> bool {
+        match self.tokens.lock().unwrap().get(query_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Removes the query from [`RunningQueries`] when it finishes.
+struct RunningQuery<'a> {
+    queries: &'a RunningQueries,
+    query_id: String,
+    token: CancellationToken,
+}
+
+impl Drop for RunningQuery<'_> {
+    fn drop(&mut self) {
+        self.queries.tokens.lock().unwrap().remove(&self.query_id);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportRequest {
+    pub sql: String,
+    pub format: ExportFormat,
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    pub format: ExportFormat,
 }
 
 #[derive(Deserialize)]
@@ -32,12 +119,6 @@ pub struct UpdateQueryRequest {
     pub description: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct QueryResponse {
-    pub results: Vec<Vec<String>>,
-    pub column_count: usize,
-}
-
 #[derive(Serialize)]
 pub struct QueryListResponse {
     pub queries: HashMap<String, QueryConfig>,
@@ -51,6 +132,8 @@ pub struct SaveQueryResponse {
 pub fn routes() -> Router {
     Router::new()
         .route("/query", post(execute_query_handler))
+        .route("/query/export", post(export_query_handler))
+        .route("/query/{query_id}/cancel", post(cancel_query_handler))
         .route(
             "/queries",
             get(list_queries_handler).post(save_query_handler),
@@ -62,33 +145,141 @@ pub fn routes() -> Router {
                 .delete(delete_query_handler),
         )
         .route("/queries/{name}/run", post(run_query_handler))
+        .route("/queries/{name}/export", get(export_saved_query_handler))
+}
+
+/// Returns the shared `DuckLake` and the engine's query timeout, releasing the config
+/// lock so that long queries don't block other requests.
+async fn query_engine(
+    config: &Mutex<Config>,
+    shared_ducklake: &SharedDuckLake,
+) -> Result<(Arc<DuckLake>, Duration)> {
+    let config = config.lock().await;
+    let ducklake = shared_ducklake.get(&config).await?;
+    let timeout = config
+        .project
+        .engine
+        .clone()
+        .unwrap_or_default()
+        .query_timeout();
+    Ok((ducklake, timeout))
+}
+
+async fn run_query<T, F>(
+    ducklake: &DuckLake,
+    timeout: Duration,
+    cancel: &CancellationToken,
+    work: F,
+) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&DuckLake) -> Result<T> + Send + 'static,
+{
+    ducklake
+        .run_interruptible(timeout, cancel, work)
+        .await
+        .map_err(AppError::sql)
 }
 
 async fn execute_query_handler(
     Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(running_queries): Extension<Arc<RunningQueries>>,
+    Extension(role): Extension<Role>,
     Json(payload): Json<QueryRequest>,
-) -> Result<Json<QueryResponse>, AppError> {
-    let config = config.lock().await;
-    match execute_query_internal(&config, &payload.sql).await {
-        Ok((results, column_count)) => Ok(Json(QueryResponse {
-            results,
-            column_count,
-        })),
-        Err(_e) => app_error(StatusCode::BAD_REQUEST),
+) -> Result<Json<QueryPage>, AppError> {
+    check_read_only(&payload.sql, role)?;
+
+    let (ducklake, mut timeout) = query_engine(&config, &shared_ducklake).await?;
+    if let Some(secs) = payload.timeout_secs {
+        timeout = timeout.min(Duration::from_secs(secs));
     }
-}
 
-async fn execute_query_internal(config: &Config, sql: &str) -> Result<(Vec<Vec<String>>, usize)> {
-    let ducklake = DuckLake::from_config(config).await?;
-    let results = ducklake.query(sql)?;
+    let running = match &payload.query_id {
+        Some(query_id) => match running_queries.register(query_id) {
+            Some(running) => Some(running),
+            None => {
+                return Err(AppError::new(
+                    StatusCode::CONFLICT,
+                    "query_id_in_use",
+                    format!("A query with id '{query_id}' is already running"),
+                ));
+            }
+        },
+        None => None,
+    };
+    let cancel = running
+        .as_ref()
+        .map(|running| running.token.clone())
+        .unwrap_or_default();
+
+    let (offset, limit) = (payload.page.offset, payload.page.limit());
+    let sql = payload.sql;
+    let page = run_query(&ducklake, timeout, &cancel, move |ducklake| {
+        ducklake.query_page(&sql, offset, limit)
+    })
+    .await?;
+    Ok(Json(page))
+}
 
-    let column_count = if results.is_empty() {
-        0
+async fn cancel_query_handler(
+    Extension(running_queries): Extension<Arc<RunningQueries>>,
+    AxumPath(query_id): AxumPath<String>,
+) -> Result<StatusCode, AppError> {
+    if running_queries.cancel(&query_id) {
+        Ok(StatusCode::NO_CONTENT)
     } else {
-        results[0].len()
-    };
+        app_error(StatusCode::NOT_FOUND)
+    }
+}
 
-    Ok((results, column_count))
+async fn export_query_handler(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(role): Extension<Role>,
+    Json(payload): Json<ExportRequest>,
+) -> Result<Response, AppError> {
+    check_read_only(&payload.sql, role)?;
+
+    let (ducklake, timeout) = query_engine(&config, &shared_ducklake).await?;
+    export_response(&ducklake, timeout, payload.sql, payload.format, "query").await
+}
+
+/// Writes the query result to a temporary file and streams it back as a download.
+async fn export_response(
+    ducklake: &DuckLake,
+    timeout: Duration,
+    sql: String,
+    format: ExportFormat,
+    file_name: &str,
+) -> Result<Response, AppError> {
+    let file = tempfile::Builder::new()
+        .suffix(&format!(".{}", format.extension()))
+        .tempfile()?;
+    let path = file.path().to_path_buf();
+    run_query(
+        ducklake,
+        timeout,
+        &CancellationToken::new(),
+        move |ducklake| ducklake.export_query(&sql, format, &path),
+    )
+    .await?;
+
+    // The temporary path is removed when `file` is dropped; the reopened handle stays
+    // readable until the download completes.
+    let reader = tokio::fs::File::from_std(file.reopen()?);
+    let headers = [
+        (header::CONTENT_TYPE, format.content_type().to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{file_name}.{}\"",
+                format.extension()
+            ),
+        ),
+    ];
+
+    Ok((headers, Body::from_stream(ReaderStream::new(reader))).into_response())
 }
 
 async fn list_queries_handler(
@@ -179,21 +370,47 @@ async fn delete_query_handler(
 
 async fn run_query_handler(
     Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(role): Extension<Role>,
     AxumPath(name): AxumPath<String>,
-) -> Result<Json<QueryResponse>, AppError> {
-    let config_guard = config.lock().await;
-    let sql = match config_guard.queries.get(&name) {
-        Some(query) => query.sql.clone(),
-        None => return app_error(StatusCode::NOT_FOUND),
+    Query(page): Query<PageParams>,
+) -> Result<Json<QueryPage>, AppError> {
+    let Some(sql) = saved_query_sql(&config, &name).await else {
+        return app_error(StatusCode::NOT_FOUND);
     };
+    check_read_only(&sql, role)?;
 
-    match execute_query_internal(&config_guard, &sql).await {
-        Ok((results, column_count)) => Ok(Json(QueryResponse {
-            results,
-            column_count,
-        })),
-        Err(_e) => app_error(StatusCode::BAD_REQUEST),
-    }
+    let (ducklake, timeout) = query_engine(&config, &shared_ducklake).await?;
+    let (offset, limit) = (page.offset, page.limit());
+    let page = run_query(
+        &ducklake,
+        timeout,
+        &CancellationToken::new(),
+        move |ducklake| ducklake.query_page(&sql, offset, limit),
+    )
+    .await?;
+    Ok(Json(page))
+}
+
+async fn saved_query_sql(config: &Mutex<Config>, name: &str) -> Option<String> {
+    let config = config.lock().await;
+    config.queries.get(name).map(|query| query.sql.clone())
+}
+
+async fn export_saved_query_handler(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(role): Extension<Role>,
+    AxumPath(name): AxumPath<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, AppError> {
+    let Some(sql) = saved_query_sql(&config, &name).await else {
+        return app_error(StatusCode::NOT_FOUND);
+    };
+    check_read_only(&sql, role)?;
+
+    let (ducklake, timeout) = query_engine(&config, &shared_ducklake).await?;
+    export_response(&ducklake, timeout, sql, params.format, &name).await
 }
 
 // #[cfg(test)]
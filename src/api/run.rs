@@ -1,14 +1,29 @@
 use crate::{
-    api::AppError,
+    api::{AppError, app_error},
     config::Config,
-    dependency::Graph,
-    pipeline::{build::Pipeline, ducklake::DuckLake},
+    dependency::{Graph, GraphError, affected_subgraph},
+    pipeline::{
+        build::{Pipeline, create_execution_subgraph},
+        ducklake::{DuckLake, SharedDuckLake},
+        execution::ExecutionOptions,
+        file_processor::TimeWindow,
+    },
+    redact::redact,
+    status::{PipelineStatus, StatusManager},
 };
 use anyhow::Result;
-use axum::{Extension, Router, extract::Path as AxumPath, response::Json, routing::post};
+use axum::{
+    Extension, Router,
+    extract::{Path as AxumPath, Query},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing::error;
 
 #[derive(Serialize, Deserialize)]
@@ -16,130 +31,296 @@ pub struct RunRequest {
     pub project_path: String,
 }
 
+#[derive(Default, Deserialize)]
+pub struct RunQuery {
+    #[serde(default)]
+    pub full_refresh: bool,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl RunQuery {
+    fn options(&self) -> Result<ExecutionOptions> {
+        Ok(ExecutionOptions {
+            full_refresh: self.full_refresh,
+            window: TimeWindow::new(self.from, self.to)?,
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RunResponse {
     pub success: bool,
     pub message: String,
-    pub pipeline_id: Option<i32>,
+    pub run_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RunStatusResponse {
+    pub run_id: String,
+    pub active: bool,
+    pub pipeline: PipelineStatus,
+}
+
+#[derive(Default)]
+pub struct RunRegistry {
+    active: Option<ActiveRun>,
+}
+
+struct ActiveRun {
+    run_id: String,
+    cancellation_token: CancellationToken,
+}
+
+impl RunRegistry {
+    pub fn is_active(&self, run_id: &str) -> bool {
+        self.active.as_ref().is_some_and(|run| run.run_id == run_id)
+    }
 }
 
-async fn execute_run_internal(config: &Config, target_node: Option<String>) -> Result<i32> {
+struct PlannedRun {
+    current_graph: Graph,
+    pipeline: Pipeline,
+    options: ExecutionOptions,
+}
+
+async fn plan_run(
+    config: &Config,
+    target_node: Option<String>,
+    mut options: ExecutionOptions,
+) -> Result<Option<PlannedRun>> {
     if config.adapters.is_empty() && config.models.is_empty() {
         return Err(anyhow::anyhow!("No adapters or models found"));
     }
 
     let current_graph = Graph::from_config(config)?;
-    let ducklake = DuckLake::from_config(config).await?;
 
-    let pipeline = if let Some(target) = target_node {
-        let execution_graph = create_execution_subgraph(&current_graph, &target)?;
-        Pipeline::from_graph(&execution_graph)
-    } else {
-        Pipeline::from_graph(&current_graph)
+    let execution_graph = match target_node {
+        Some(target) => create_execution_subgraph(&current_graph, &target)?,
+        None if options.full_refresh => current_graph.clone(),
+        None => {
+            let (graph, conditional) =
+                affected_subgraph(&config.project_dir, &current_graph, config).await?;
+            options.conditional = conditional;
+            graph
+        }
     };
+    if execution_graph.nodes.is_empty() {
+        return Ok(None);
+    }
+    let pipeline = Pipeline::from_graph(&execution_graph)?;
+
+    Ok(Some(PlannedRun {
+        current_graph,
+        pipeline,
+        options,
+    }))
+}
+
+async fn spawn_run(
+    config: Config,
+    ducklake: Arc<DuckLake>,
+    registry: Arc<Mutex<RunRegistry>>,
+    plan: PlannedRun,
+) -> Result<ActiveRun> {
+    let PlannedRun {
+        current_graph,
+        pipeline,
+        options,
+    } = plan;
 
     crate::dependency::save_graph(&config.project_dir, &current_graph).await?;
 
-    pipeline
-        .execute(&current_graph, config, &ducklake, &config.project_dir)
-        .await?;
+    let mut status_manager = StatusManager::new(&config.project_dir);
+    let table_names: Vec<String> = pipeline
+        .all_actions()
+        .iter()
+        .map(|action| action.table_name.clone())
+        .collect();
+    status_manager.start(Utc::now(), &table_names).await?;
+
+    let run_id = status_manager.run_id();
+    let cancellation_token = options.cancellation_token.clone();
+    let task_run_id = run_id.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = pipeline
+            .execute_with_status(
+                &current_graph,
+                &config,
+                &ducklake,
+                &config.project_dir,
+                &mut status_manager,
+                options,
+            )
+            .await
+        {
+            error!(
+                "Pipeline run {} failed: {}",
+                task_run_id,
+                redact(&e.to_string())
+            );
+        }
+
+        if let Some(history) = &config.project.history
+            && let Err(e) = StatusManager::prune(&config.project_dir, history).await
+        {
+            error!("Failed to prune run history: {}", e);
+        }
+
+        let mut registry = registry.lock().await;
+        if registry.is_active(&task_run_id) {
+            registry.active = None;
+        }
+    });
 
-    Ok(1)
+    Ok(ActiveRun {
+        run_id,
+        cancellation_token,
+    })
 }
 
-fn create_execution_subgraph(graph: &Graph, target_node: &str) -> Result<Graph> {
-    use std::collections::{HashSet, VecDeque};
+fn start_error(label: &str, error: anyhow::Error) -> AppError {
+    error!("{} failed to start: {}", label, redact(&error.to_string()));
+    match error.downcast::<GraphError>() {
+        Ok(graph_error) => graph_error.into(),
+        Err(error) => AppError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "run_failed_to_start",
+            format!("{label} failed to start: {}", redact(&error.to_string())),
+        ),
+    }
+}
 
-    if !graph.nodes.iter().any(|n| n.name == target_node) {
-        return Err(anyhow::anyhow!(
-            "Target node '{}' not found in graph",
-            target_node
-        ));
+async fn start_run(
+    config: Config,
+    shared_ducklake: Arc<SharedDuckLake>,
+    registry: Arc<Mutex<RunRegistry>>,
+    target_node: Option<String>,
+    options: ExecutionOptions,
+) -> Result<Json<RunResponse>, AppError> {
+    if registry.lock().await.active.is_some() {
+        return app_error(StatusCode::CONFLICT);
     }
 
-    let mut included_nodes = HashSet::new();
-    let mut queue = VecDeque::new();
-    queue.push_back(target_node);
-
-    while let Some(current_node) = queue.pop_front() {
-        if included_nodes.insert(current_node.to_string()) {
-            for edge in &graph.edges {
-                if edge.to == current_node && !included_nodes.contains(&edge.from) {
-                    queue.push_back(&edge.from);
-                }
-            }
+    let label = match &target_node {
+        Some(target) => format!("Pipeline run for target '{target}'"),
+        None => "Pipeline run".to_string(),
+    };
+
+    let plan = match plan_run(&config, target_node, options).await {
+        Ok(Some(plan)) => plan,
+        Ok(None) => {
+            return Ok(Json(RunResponse {
+                success: true,
+                message: "No changes detected, nothing to run".to_string(),
+                run_id: None,
+            }));
         }
+        Err(e) => return Err(start_error(&label, e)),
+    };
+
+    let ducklake = shared_ducklake
+        .get(&config)
+        .await
+        .map_err(|e| start_error(&label, e))?;
+
+    // Held until the handle is inserted, so a run that finishes at once cannot
+    // clear the registry before it is set.
+    let mut active_registry = registry.lock().await;
+    if active_registry.active.is_some() {
+        return app_error(StatusCode::CONFLICT);
     }
 
-    let filtered_nodes = graph
-        .nodes
-        .iter()
-        .filter(|node| included_nodes.contains(&node.name))
-        .cloned()
-        .collect();
+    let run = spawn_run(config, ducklake, Arc::clone(&registry), plan)
+        .await
+        .map_err(|e| start_error(&label, e))?;
+    let run_id = run.run_id.clone();
+    active_registry.active = Some(run);
 
-    let filtered_edges = graph
-        .edges
-        .iter()
-        .filter(|edge| included_nodes.contains(&edge.from) && included_nodes.contains(&edge.to))
-        .cloned()
-        .collect();
+    Ok(Json(RunResponse {
+        success: true,
+        message: format!("{label} started"),
+        run_id: Some(run_id),
+    }))
+}
 
-    Ok(Graph {
-        nodes: filtered_nodes,
-        edges: filtered_edges,
-    })
+fn invalid_options(error: anyhow::Error) -> AppError {
+    AppError::new(
+        StatusCode::BAD_REQUEST,
+        "invalid_options",
+        error.to_string(),
+    )
 }
 
 async fn handle_run(
     Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(registry): Extension<Arc<Mutex<RunRegistry>>>,
+    Query(query): Query<RunQuery>,
 ) -> Result<Json<RunResponse>, AppError> {
-    let config = config.lock().await;
-    match execute_run_internal(&config, None).await {
-        Ok(pipeline_id) => Ok(Json(RunResponse {
-            success: true,
-            message: "Pipeline execution completed successfully".to_string(),
-            pipeline_id: Some(pipeline_id),
-        })),
-        Err(e) => {
-            error!("Pipeline execution failed: {}", e);
-            Ok(Json(RunResponse {
-                success: false,
-                message: format!("Pipeline execution failed: {}", e),
-                pipeline_id: None,
-            }))
-        }
-    }
+    let options = query.options().map_err(invalid_options)?;
+    let config = config.lock().await.clone();
+    start_run(config, shared_ducklake, registry, None, options).await
 }
 
 async fn handle_run_target(
     Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(shared_ducklake): Extension<Arc<SharedDuckLake>>,
+    Extension(registry): Extension<Arc<Mutex<RunRegistry>>>,
     AxumPath(target_node): AxumPath<String>,
+    Query(query): Query<RunQuery>,
 ) -> Result<Json<RunResponse>, AppError> {
-    let config = config.lock().await;
-    match execute_run_internal(&config, Some(target_node.clone())).await {
-        Ok(pipeline_id) => Ok(Json(RunResponse {
-            success: true,
-            message: format!(
-                "Pipeline execution for target '{}' completed successfully",
-                target_node
-            ),
-            pipeline_id: Some(pipeline_id),
-        })),
-        Err(e) => {
-            error!(
-                "Pipeline execution for target '{}' failed: {}",
-                target_node, e
-            );
-            Ok(Json(RunResponse {
-                success: false,
-                message: format!(
-                    "Pipeline execution for target '{}' failed: {}",
-                    target_node, e
-                ),
-                pipeline_id: None,
-            }))
+    let options = query.options().map_err(invalid_options)?;
+    let config = config.lock().await.clone();
+    start_run(
+        config,
+        shared_ducklake,
+        registry,
+        Some(target_node),
+        options,
+    )
+    .await
+}
+
+async fn handle_get_run(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(registry): Extension<Arc<Mutex<RunRegistry>>>,
+    AxumPath(run_id): AxumPath<String>,
+) -> Result<Json<RunStatusResponse>, AppError> {
+    let project_dir = config.lock().await.project_dir.clone();
+
+    let Some(status_manager) = StatusManager::from_run_id(&project_dir, &run_id) else {
+        return app_error(StatusCode::NOT_FOUND);
+    };
+    let pipeline = status_manager.load().await?;
+    let active = registry.lock().await.is_active(&run_id);
+
+    Ok(Json(RunStatusResponse {
+        run_id,
+        active,
+        pipeline,
+    }))
+}
+
+async fn handle_cancel_run(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Extension(registry): Extension<Arc<Mutex<RunRegistry>>>,
+    AxumPath(run_id): AxumPath<String>,
+) -> Result<StatusCode, AppError> {
+    let project_dir = config.lock().await.project_dir.clone();
+    let registry = registry.lock().await;
+
+    match &registry.active {
+        Some(run) if run.run_id == run_id => {
+            run.cancellation_token.cancel();
+            Ok(StatusCode::ACCEPTED)
+        }
+        _ if StatusManager::from_run_id(&project_dir, &run_id).is_some() => {
+            app_error(StatusCode::CONFLICT)
         }
+        _ => app_error(StatusCode::NOT_FOUND),
     }
 }
 
@@ -147,4 +328,8 @@ pub fn routes() -> Router {
     Router::new()
         .route("/run", post(handle_run))
         .route("/run/{target_node}", post(handle_run_target))
+        .route(
+            "/runs/{run_id}",
+            get(handle_get_run).delete(handle_cancel_run),
+        )
 }
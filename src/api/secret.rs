@@ -1,286 +1,351 @@
-// use std::sync::Arc;
-//
-// use crate::api::{AppError, app_error};
-// use crate::config::Config;
-// use crate::secret::SecretManager;
-// use anyhow::Result;
-// use axum::Extension;
-// use axum::extract::Path;
-// use axum::http::StatusCode;
-// use axum::response::Json;
-// use axum::{
-//     Router,
-//     routing::{get, post},
-// };
-// use serde::{Deserialize, Serialize};
-// use tokio::sync::Mutex;
-//
-// #[derive(Serialize, Deserialize)]
-// pub struct SecretSummary {
-//     pub key: String,
-//     pub masked_value: String,
-// }
-//
-// #[derive(Deserialize)]
-// pub struct CreateSecretRequest {
-//     pub key: String,
-//     pub value: String,
-// }
-//
-// #[derive(Deserialize)]
-// pub struct UpdateSecretRequest {
-//     pub value: String,
-// }
-//
-// #[derive(Deserialize)]
-// pub struct GenerateSecretKeyRequest {
-//     pub connection_name: String,
-//     pub connection_type: String,
-//     pub field_type: String,
-// }
-//
-// #[derive(Serialize)]
-// pub struct GenerateSecretKeyResponse {
-//     pub key: String,
-// }
-//
-// pub fn routes() -> Router {
-//     Router::new()
-//         .route("/secrets", get(list_secrets).post(create_secret))
-//         .route(
-//             "/secrets/{key}",
-//             get(get_secret_info)
-//                 .put(update_secret)
-//                 .delete(delete_secret),
-//         )
-//         .route("/secrets/generate-key", post(generate_unique_secret_key))
-// }
-//
-// async fn list_secrets(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-// ) -> Result<Json<Vec<SecretSummary>>, AppError> {
-//     let config = config.lock().await;
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     let secrets = manager.get_all_secrets()?;
-//
-//     let mut summaries = Vec::new();
-//     for (key, value) in secrets {
-//         let masked_value = mask_secret_value(&value);
-//         summaries.push(SecretSummary { key, masked_value });
-//     }
-//
-//     summaries.sort_by(|a, b| a.key.cmp(&b.key));
-//     Ok(Json(summaries))
-// }
-//
-// async fn get_secret_info(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-//     Path(key): Path<String>,
-// ) -> Result<Json<SecretSummary>, AppError> {
-//     let config = config.lock().await;
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     match manager.get_secret(&key)? {
-//         Some(value) => {
-//             let masked_value = mask_secret_value(&value);
-//             Ok(Json(SecretSummary { key, masked_value }))
-//         }
-//         None => app_error(StatusCode::NOT_FOUND),
-//     }
-// }
-//
-// async fn create_secret(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-//     Json(req): Json<CreateSecretRequest>,
-// ) -> Result<StatusCode, AppError> {
-//     let config = config.lock().await;
-//     if !is_valid_secret_key(&req.key) {
-//         return app_error(StatusCode::BAD_REQUEST);
-//     }
-//
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     if manager.get_secret(&req.key)?.is_some() {
-//         return app_error(StatusCode::CONFLICT);
-//     }
-//
-//     manager.set_secret(&req.key, &req.value)?;
-//
-//     Ok(StatusCode::CREATED)
-// }
-//
-// async fn update_secret(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-//     Path(key): Path<String>,
-//     Json(req): Json<UpdateSecretRequest>,
-// ) -> Result<StatusCode, AppError> {
-//     let config = config.lock().await;
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     if manager.get_secret(&key)?.is_none() {
-//         return app_error(StatusCode::NOT_FOUND);
-//     }
-//
-//     manager.set_secret(&key, &req.value)?;
-//
-//     Ok(StatusCode::OK)
-// }
-//
-// async fn delete_secret(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-//     Path(key): Path<String>,
-// ) -> Result<StatusCode, AppError> {
-//     let config = config.lock().await;
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     let removed = manager.delete_secret(&key)?;
-//
-//     if removed {
-//         Ok(StatusCode::NO_CONTENT)
-//     } else {
-//         app_error(StatusCode::NOT_FOUND)
-//     }
-// }
-//
-// async fn generate_unique_secret_key(
-//     Extension(config): Extension<Arc<Mutex<Config>>>,
-//     Json(req): Json<GenerateSecretKeyRequest>,
-// ) -> Result<Json<GenerateSecretKeyResponse>, AppError> {
-//     let config = config.lock().await;
-//     let manager = SecretManager::new(&config.project_dir)?;
-//
-//     let base_key = generate_secret_key_for_connection(
-//         &req.connection_name,
-//         &req.connection_type,
-//         &req.field_type,
-//     );
-//
-//     let unique_key = find_unique_secret_key(&manager, &base_key)?;
-//
-//     Ok(Json(GenerateSecretKeyResponse { key: unique_key }))
-// }
-//
-// fn mask_secret_value(value: &str) -> String {
-//     if value.len() <= 2 {
-//         "*".repeat(value.len())
-//     } else {
-//         let visible_chars = 2;
-//         let masked_part = "*".repeat(value.len() - visible_chars);
-//         format!("{}{}", masked_part, &value[value.len() - visible_chars..])
-//     }
-// }
-//
-// fn is_valid_secret_key(key: &str) -> bool {
-//     if key.is_empty() || key.len() > 64 {
-//         return false;
-//     }
-//
-//     let first_char = key.chars().next().unwrap();
-//     if !first_char.is_ascii_alphabetic() {
-//         return false;
-//     }
-//
-//     key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
-// }
-//
-// pub fn generate_secret_key_for_connection(
-//     connection_name: &str,
-//     connection_type: &str,
-//     field_type: &str,
-// ) -> String {
-//     let sanitized_name = sanitize_connection_name(connection_name);
-//     let upper_type = connection_type.to_uppercase();
-//     let upper_field = field_type.to_uppercase();
-//     format!("{}_{}_{}", sanitized_name, upper_type, upper_field)
-// }
-//
-// pub fn find_unique_secret_key(
-//     manager: &SecretManager,
-//     base_key: &str,
-// ) -> Result<String, anyhow::Error> {
-//     if manager.get_secret(base_key)?.is_none() {
-//         return Ok(base_key.to_string());
-//     }
-//
-//     for i in 2..=999 {
-//         let candidate = format!("{}_{}", base_key, i);
-//         if manager.get_secret(&candidate)?.is_none() {
-//             return Ok(candidate);
-//         }
-//     }
-//
-//     Err(anyhow::anyhow!(
-//         "Unable to find unique secret key for: {}",
-//         base_key
-//     ))
-// }
-//
-// fn sanitize_connection_name(name: &str) -> String {
-//     name.chars()
-//         .map(|c| {
-//             if c.is_ascii_alphanumeric() {
-//                 c.to_ascii_uppercase()
-//             } else {
-//                 '_'
-//             }
-//         })
-//         .collect()
-// }
-//
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//
-//     #[test]
-//     fn test_mask_secret_value() {
-//         assert_eq!(mask_secret_value(""), "");
-//         assert_eq!(mask_secret_value("a"), "*");
-//         assert_eq!(mask_secret_value("ab"), "**");
-//         assert_eq!(mask_secret_value("abc"), "*bc");
-//         assert_eq!(mask_secret_value("password123"), "*********23");
-//         assert_eq!(mask_secret_value("verylongpassword"), "**************rd");
-//     }
-//
-//     #[test]
-//     fn test_is_valid_secret_key() {
-//         assert!(is_valid_secret_key("VALID_KEY"));
-//         assert!(is_valid_secret_key("test123"));
-//         assert!(is_valid_secret_key("a"));
-//         assert!(is_valid_secret_key("My_Secret_123"));
-//
-//         assert!(!is_valid_secret_key(""));
-//         assert!(!is_valid_secret_key("123invalid"));
-//         assert!(!is_valid_secret_key("_invalid"));
-//         assert!(!is_valid_secret_key("invalid-key"));
-//         assert!(!is_valid_secret_key("invalid key"));
-//         assert!(!is_valid_secret_key(&"a".repeat(65)));
-//     }
-//
-//     #[test]
-//     fn test_generate_secret_key_for_connection() {
-//         let key = generate_secret_key_for_connection("my_db", "mysql", "password");
-//         assert_eq!(key, "MY_DB_MYSQL_PASSWORD");
-//
-//         let key = generate_secret_key_for_connection("test-connection", "postgresql", "password");
-//         assert_eq!(key, "TEST_CONNECTION_POSTGRESQL_PASSWORD");
-//
-//         let key = generate_secret_key_for_connection("s3 storage", "s3", "secret_access_key");
-//         assert_eq!(key, "S3_STORAGE_S3_SECRET_ACCESS_KEY");
-//     }
-//
-//     #[test]
-//     fn test_sanitize_connection_name() {
-//         assert_eq!(sanitize_connection_name("my_db"), "MY_DB");
-//         assert_eq!(
-//             sanitize_connection_name("test-connection"),
-//             "TEST_CONNECTION"
-//         );
-//         assert_eq!(sanitize_connection_name("s3 storage"), "S3_STORAGE");
-//         assert_eq!(sanitize_connection_name("123abc"), "123ABC");
-//         assert_eq!(
-//             sanitize_connection_name("special!@#chars"),
-//             "SPECIAL___CHARS"
-//         );
-//     }
-// }
+use crate::api::{AppError, app_error};
+use crate::config::Config;
+use crate::redact::MASK;
+use crate::secret::{SecretManager, is_valid_secret_name};
+use anyhow::Result;
+use axum::extract::Path;
+use axum::response::Json;
+use axum::{
+    Extension, Router,
+    http::StatusCode,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Serialize, Deserialize)]
+pub struct SecretSummary {
+    pub key: String,
+    pub masked_value: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSecretRequest {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSecretRequest {
+    pub value: String,
+}
+
+#[derive(Deserialize)]
+pub struct GenerateSecretKeyRequest {
+    pub connection_name: String,
+    pub connection_type: String,
+    pub field_type: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GenerateSecretKeyResponse {
+    pub key: String,
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/secrets", get(list_secrets).post(create_secret))
+        .route(
+            "/secrets/{key}",
+            get(get_secret_info)
+                .put(update_secret)
+                .delete(delete_secret),
+        )
+        .route("/secrets/generate-key", post(generate_unique_secret_key))
+}
+
+async fn secret_manager(config: &Arc<Mutex<Config>>) -> Result<SecretManager, AppError> {
+    let manager = SecretManager::new(&config.lock().await.project_dir);
+    if !manager.key_exists() {
+        return Err(AppError::new(
+            StatusCode::PRECONDITION_FAILED,
+            "secret_key_missing",
+            "No secret key found, run `featherbox secret gen-key` first",
+        ));
+    }
+    Ok(manager)
+}
+
+async fn list_secrets(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+) -> Result<Json<Vec<SecretSummary>>, AppError> {
+    let manager = secret_manager(&config).await?;
+
+    let summaries = manager
+        .get_all_secrets()?
+        .into_keys()
+        .map(|key| SecretSummary {
+            key,
+            masked_value: MASK.to_string(),
+        })
+        .collect();
+
+    Ok(Json(summaries))
+}
+
+async fn get_secret_info(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Path(key): Path<String>,
+) -> Result<Json<SecretSummary>, AppError> {
+    let manager = secret_manager(&config).await?;
+
+    match manager.get_secret(&key)? {
+        Some(_) => Ok(Json(SecretSummary {
+            key,
+            masked_value: MASK.to_string(),
+        })),
+        None => app_error(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn create_secret(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Json(req): Json<CreateSecretRequest>,
+) -> Result<StatusCode, AppError> {
+    if !is_valid_secret_name(&req.key) {
+        return Err(AppError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_secret_name",
+            format!("Invalid secret name '{}'", req.key),
+        ));
+    }
+
+    let manager = secret_manager(&config).await?;
+
+    if manager.get_secret(&req.key)?.is_some() {
+        return app_error(StatusCode::CONFLICT);
+    }
+
+    manager.set_secret(&req.key, &req.value)?;
+    config.lock().await.reload_project()?;
+
+    Ok(StatusCode::CREATED)
+}
+
+async fn update_secret(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Path(key): Path<String>,
+    Json(req): Json<UpdateSecretRequest>,
+) -> Result<StatusCode, AppError> {
+    let manager = secret_manager(&config).await?;
+
+    if manager.get_secret(&key)?.is_none() {
+        return app_error(StatusCode::NOT_FOUND);
+    }
+
+    manager.set_secret(&key, &req.value)?;
+    config.lock().await.reload_project()?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn delete_secret(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let manager = secret_manager(&config).await?;
+
+    let Some(value) = manager.get_secret(&key)? else {
+        return app_error(StatusCode::NOT_FOUND);
+    };
+    manager.delete_secret(&key)?;
+
+    if let Err(e) = config.lock().await.reload_project() {
+        manager.set_secret(&key, &value)?;
+        return Err(AppError::new(
+            StatusCode::CONFLICT,
+            "secret_in_use",
+            format!("Secret '{key}' cannot be deleted: {e:#}"),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn generate_unique_secret_key(
+    Extension(config): Extension<Arc<Mutex<Config>>>,
+    Json(req): Json<GenerateSecretKeyRequest>,
+) -> Result<Json<GenerateSecretKeyResponse>, AppError> {
+    let manager = secret_manager(&config).await?;
+
+    let base_key = generate_secret_key_for_connection(
+        &req.connection_name,
+        &req.connection_type,
+        &req.field_type,
+    );
+
+    let unique_key = find_unique_secret_key(&manager, &base_key)?;
+
+    Ok(Json(GenerateSecretKeyResponse { key: unique_key }))
+}
+
+fn generate_secret_key_for_connection(
+    connection_name: &str,
+    connection_type: &str,
+    field_type: &str,
+) -> String {
+    let key = [connection_name, connection_type, field_type]
+        .iter()
+        .map(|part| sanitize_key_part(part))
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if key.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        key
+    } else {
+        format!("SECRET_{key}")
+    }
+}
+
+fn find_unique_secret_key(manager: &SecretManager, base_key: &str) -> Result<String> {
+    let existing = manager.list_secrets()?;
+    if !existing.iter().any(|key| key == base_key) {
+        return Ok(base_key.to_string());
+    }
+
+    (2..=999)
+        .map(|i| format!("{base_key}_{i}"))
+        .find(|candidate| !existing.contains(candidate))
+        .ok_or_else(|| anyhow::anyhow!("Unable to find unique secret key for: {base_key}"))
+}
+
+fn sanitize_key_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::new::create_secret_key;
+    use crate::config::project::{ConnectionConfig, ProjectConfig};
+    use crate::test_helpers::TestManager;
+    use serde_json::json;
+
+    #[test]
+    fn test_generate_secret_key_for_connection() {
+        assert_eq!(
+            generate_secret_key_for_connection("my_db", "mysql", "password"),
+            "MY_DB_MYSQL_PASSWORD"
+        );
+        assert_eq!(
+            generate_secret_key_for_connection("s3 storage", "s3", "secret_access_key"),
+            "S3_STORAGE_S3_SECRET_ACCESS_KEY"
+        );
+        assert_eq!(
+            generate_secret_key_for_connection("1st-db", "postgresql", "password"),
+            "SECRET_1ST_DB_POSTGRESQL_PASSWORD"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secret_routes() -> Result<()> {
+        let test = TestManager::new();
+        let server = test.setup_project(routes);
+
+        let response = server.get("/secrets").await;
+        response.assert_status(StatusCode::PRECONDITION_FAILED);
+
+        create_secret_key(test.directory())?;
+
+        let response = server
+            .post("/secrets")
+            .json(&json!({"key": "DB_PASSWORD", "value": "hunter22"}))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response = server
+            .post("/secrets")
+            .json(&json!({"key": "DB_PASSWORD", "value": "other"}))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+
+        let response = server
+            .post("/secrets")
+            .json(&json!({"key": "invalid-key", "value": "value"}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let response = server.get("/secrets/DB_PASSWORD").await;
+        response.assert_status_ok();
+        let summary: SecretSummary = response.json();
+        assert_eq!(summary.masked_value, MASK);
+
+        let mut project = ProjectConfig::default();
+        project.connections.insert(
+            "db".to_string(),
+            ConnectionConfig::PostgreSql {
+                host: "localhost".to_string(),
+                port: 5432,
+                database: "app".to_string(),
+                username: "app".to_string(),
+                password: "${secret:DB_PASSWORD}".to_string(),
+            },
+        );
+        test.config().await.add_project_setting(&project)?.save()?;
+
+        let response = server
+            .put("/secrets/DB_PASSWORD")
+            .json(&json!({"value": "changed"}))
+            .await;
+        response.assert_status_ok();
+        let manager = SecretManager::new(test.directory());
+        assert_eq!(
+            manager.get_secret("DB_PASSWORD")?,
+            Some("changed".to_string())
+        );
+        let resolved = test.config().await.project.connections["db"].clone();
+        assert!(
+            matches!(resolved, ConnectionConfig::PostgreSql { password, .. } if password == "changed")
+        );
+
+        let response = server.delete("/secrets/DB_PASSWORD").await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_eq!(
+            manager.get_secret("DB_PASSWORD")?,
+            Some("changed".to_string())
+        );
+        test.config()
+            .await
+            .add_project_setting(&ProjectConfig::default())?
+            .save()?;
+
+        let response = server
+            .post("/secrets/generate-key")
+            .json(&json!({
+                "connection_name": "db",
+                "connection_type": "mysql",
+                "field_type": "password"
+            }))
+            .await;
+        let generated: GenerateSecretKeyResponse = response.json();
+        assert_eq!(generated.key, "DB_MYSQL_PASSWORD");
+
+        manager.set_secret("DB_MYSQL_PASSWORD", "value")?;
+        let response = server
+            .post("/secrets/generate-key")
+            .json(&json!({
+                "connection_name": "db",
+                "connection_type": "mysql",
+                "field_type": "password"
+            }))
+            .await;
+        let generated: GenerateSecretKeyResponse = response.json();
+        assert_eq!(generated.key, "DB_MYSQL_PASSWORD_2");
+
+        let response = server.delete("/secrets/DB_PASSWORD").await;
+        response.assert_status(StatusCode::NO_CONTENT);
+        let response = server.get("/secrets/DB_PASSWORD").await;
+        response.assert_status_not_found();
+
+        Ok(())
+    }
+}
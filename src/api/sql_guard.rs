@@ -0,0 +1,294 @@
+use crate::api::AppError;
+use crate::auth::Role;
+use axum::http::StatusCode;
+use serde::Serialize;
+use sqlparser::{
+    ast::{ObjectName, Query, SetExpr, Statement, TableFactor, Visit, Visitor},
+    dialect::DuckDbDialect,
+    parser::Parser,
+};
+use std::ops::ControlFlow;
+
+/// Table functions that only generate rows and can't reach files or other databases.
+const SAFE_TABLE_FUNCTIONS: &[&str] = &["range", "generate_series", "unnest"];
+
+/// The DuckLake catalog. Other attached catalogs are only reachable by admins.
+const DUCKLAKE_CATALOG: &str = "db";
+/// The DuckLake catalog's schema, which two-part names such as `main.users` may start with.
+const DUCKLAKE_SCHEMA: &str = "main";
+
+/// Why ad-hoc SQL was not run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum SqlRejection {
+    ParseError { message: String },
+    Empty,
+    MultipleStatements { count: usize },
+    NotReadOnly { statement: String },
+    FileAccess { function: String },
+    OtherCatalog { catalog: String },
+}
+
+impl SqlRejection {
+    pub fn message(&self) -> String {
+        match self {
+            SqlRejection::ParseError { message } => format!("SQL could not be parsed: {message}"),
+            SqlRejection::Empty => "SQL is empty".to_string(),
+            SqlRejection::MultipleStatements { count } => {
+                format!("Only one statement can be run at a time, got {count}")
+            }
+            SqlRejection::NotReadOnly { statement } => {
+                format!("{statement} statements are not allowed, only queries can be run")
+            }
+            SqlRejection::FileAccess { function } => {
+                format!("'{function}' can read files or other databases and needs an admin token")
+            }
+            SqlRejection::OtherCatalog { catalog } => {
+                format!("Catalog '{catalog}' is not the DuckLake catalog and needs an admin token")
+            }
+        }
+    }
+}
+
+impl From<SqlRejection> for AppError {
+    fn from(rejection: SqlRejection) -> Self {
+        let status = match rejection {
+            SqlRejection::ParseError { .. } | SqlRejection::Empty => StatusCode::BAD_REQUEST,
+            _ => StatusCode::FORBIDDEN,
+        };
+        let mut details = serde_json::to_value(&rejection).unwrap_or_default();
+        let code = details
+            .as_object_mut()
+            .and_then(|fields| fields.remove("code"))
+            .and_then(|code| code.as_str().map(str::to_string))
+            .unwrap_or_default();
+
+        let error = AppError::new(status, &code, rejection.message());
+        match details.as_object() {
+            Some(fields) if !fields.is_empty() => error.with_details(details),
+            _ => error,
+        }
+    }
+}
+
+/// Checks that ad-hoc SQL only reads DuckLake tables. Admins may also run several
+/// statements and use table functions that read files or other databases.
+pub fn check_read_only(sql: &str, role: Role) -> Result<(), SqlRejection> {
+    let statements =
+        Parser::parse_sql(&DuckDbDialect, sql).map_err(|e| SqlRejection::ParseError {
+            message: e.to_string(),
+        })?;
+
+    match statements.len() {
+        0 => return Err(SqlRejection::Empty),
+        1 => {}
+        count if role != Role::Admin => {
+            return Err(SqlRejection::MultipleStatements { count });
+        }
+        _ => {}
+    }
+
+    let mut guard = ReadOnlyGuard {
+        allow_external: role == Role::Admin,
+    };
+    match statements.visit(&mut guard) {
+        ControlFlow::Break(rejection) => Err(rejection),
+        ControlFlow::Continue(()) => Ok(()),
+    }
+}
+
+struct ReadOnlyGuard {
+    allow_external: bool,
+}
+
+impl Visitor for ReadOnlyGuard {
+    type Break = SqlRejection;
+
+    fn pre_visit_statement(&mut self, statement: &Statement) -> ControlFlow<SqlRejection> {
+        match statement {
+            Statement::Query(_)
+            | Statement::Explain { .. }
+            | Statement::ExplainTable { .. }
+            | Statement::ShowTables { .. }
+            | Statement::ShowColumns { .. } => ControlFlow::Continue(()),
+            _ => ControlFlow::Break(SqlRejection::NotReadOnly {
+                statement: statement_keyword(statement),
+            }),
+        }
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<SqlRejection> {
+        if let SetExpr::Select(select) = query.body.as_ref()
+            && select.into.is_some()
+        {
+            return ControlFlow::Break(SqlRejection::NotReadOnly {
+                statement: "SELECT INTO".to_string(),
+            });
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<SqlRejection> {
+        if self.allow_external {
+            return ControlFlow::Continue(());
+        }
+
+        let rejection = match table_factor {
+            TableFactor::Table {
+                name,
+                args: Some(_),
+                ..
+            }
+            | TableFactor::Function { name, .. } => {
+                let function = last_part(name);
+                (!SAFE_TABLE_FUNCTIONS.contains(&function.as_str()))
+                    .then_some(SqlRejection::FileAccess { function })
+            }
+            // DuckDB scans files named directly in FROM, e.g. FROM "data.csv".
+            TableFactor::Table { name, .. } if is_file_path(name) => {
+                Some(SqlRejection::FileAccess {
+                    function: name.to_string(),
+                })
+            }
+            // A two-part name may be `catalog.table` as well as `schema.table`.
+            TableFactor::Table { name, .. } if name.0.len() > 1 => {
+                let catalog = name.0[0].to_string();
+                let allowed = catalog.eq_ignore_ascii_case(DUCKLAKE_CATALOG)
+                    || (name.0.len() == 2 && catalog.eq_ignore_ascii_case(DUCKLAKE_SCHEMA));
+                (!allowed).then_some(SqlRejection::OtherCatalog { catalog })
+            }
+            TableFactor::TableFunction { expr, .. } => Some(SqlRejection::FileAccess {
+                function: expr.to_string(),
+            }),
+            _ => None,
+        };
+
+        match rejection {
+            Some(rejection) => ControlFlow::Break(rejection),
+            None => ControlFlow::Continue(()),
+        }
+    }
+}
+
+fn statement_keyword(statement: &Statement) -> String {
+    statement
+        .to_string()
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase()
+}
+
+fn last_part(name: &ObjectName) -> String {
+    name.0
+        .last()
+        .and_then(|part| part.as_ident())
+        .map(|ident| ident.value.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_file_path(name: &ObjectName) -> bool {
+    name.0
+        .iter()
+        .filter_map(|part| part.as_ident())
+        .any(|ident| {
+            ident.quote_style.is_some() && (ident.value.contains('/') || ident.value.contains('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_reads() {
+        for sql in [
+            "SELECT * FROM users",
+            "SELECT * FROM db.main.users u JOIN orders o ON u.id = o.user_id;",
+            "SELECT * FROM main.users JOIN db.orders USING (id)",
+            "WITH recent AS (SELECT * FROM orders) SELECT count(*) FROM recent",
+            "SELECT * FROM users WHERE id IN (SELECT user_id FROM orders)",
+            "SELECT * FROM range(10) t(i)",
+            "EXPLAIN SELECT 1",
+        ] {
+            assert_eq!(check_read_only(sql, Role::ReadOnly), Ok(()), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_writes() {
+        for (sql, statement) in [
+            ("DROP TABLE users", "DROP"),
+            ("INSERT INTO users VALUES (1)", "INSERT"),
+            ("ATTACH '/tmp/other.db' AS other", "ATTACH"),
+            ("COPY users TO '/tmp/users.csv'", "COPY"),
+            ("INSTALL httpfs", "INSTALL"),
+        ] {
+            assert_eq!(
+                check_read_only(sql, Role::Admin),
+                Err(SqlRejection::NotReadOnly {
+                    statement: statement.to_string()
+                }),
+                "{sql}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_external_access_needs_admin() {
+        let cases = [
+            (
+                "SELECT * FROM read_csv('/etc/passwd')",
+                SqlRejection::FileAccess {
+                    function: "read_csv".to_string(),
+                },
+            ),
+            (
+                "SELECT * FROM users WHERE id IN (SELECT id FROM read_parquet('s3://bucket/*.parquet'))",
+                SqlRejection::FileAccess {
+                    function: "read_parquet".to_string(),
+                },
+            ),
+            (
+                "SELECT * FROM \"data/users.csv\"",
+                SqlRejection::FileAccess {
+                    function: "\"data/users.csv\"".to_string(),
+                },
+            ),
+            (
+                "SELECT * FROM sqlite_db.main.orders",
+                SqlRejection::OtherCatalog {
+                    catalog: "sqlite_db".to_string(),
+                },
+            ),
+            (
+                "SELECT * FROM sqlite_db.orders",
+                SqlRejection::OtherCatalog {
+                    catalog: "sqlite_db".to_string(),
+                },
+            ),
+            (
+                "SELECT 1; SELECT 2",
+                SqlRejection::MultipleStatements { count: 2 },
+            ),
+        ];
+
+        for (sql, rejection) in cases {
+            assert_eq!(
+                check_read_only(sql, Role::ReadOnly),
+                Err(rejection),
+                "{sql}"
+            );
+            assert_eq!(check_read_only(sql, Role::Admin), Ok(()), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_unparsable_sql() {
+        assert!(matches!(
+            check_read_only("SELEC 1", Role::Admin),
+            Err(SqlRejection::ParseError { .. })
+        ));
+        assert_eq!(check_read_only("", Role::Admin), Err(SqlRejection::Empty));
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ring::digest::{SHA256, digest};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TOKEN_PREFIX: &str = "fbx_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Can read everything except connections and secrets, and run queries
+    ReadOnly,
+    /// Full access, including running pipelines and editing configuration
+    Admin,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    hash: String,
+}
+
+/// API tokens of a project. Only SHA-256 hashes are stored, in `.data/tokens.json`.
+pub struct TokenStore {
+    path: PathBuf,
+}
+
+impl TokenStore {
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            path: project_dir.join(".data").join("tokens.json"),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<ApiToken>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read tokens file: {}", self.path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse tokens file")
+    }
+
+    fn save(&self, tokens: &[ApiToken]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&self.path, serde_json::to_string_pretty(tokens)?)
+            .with_context(|| format!("Failed to write tokens file: {}", self.path.display()))
+    }
+
+    /// Creates a token and returns its plaintext value, which is not stored anywhere.
+    pub fn create(&self, name: &str, role: Role) -> Result<String> {
+        let mut tokens = self.list()?;
+        if tokens.iter().any(|token| token.name == name) {
+            return Err(anyhow::anyhow!("Token '{name}' already exists"));
+        }
+
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow::anyhow!("Failed to generate token"))?;
+        let value = format!("{TOKEN_PREFIX}{}", to_hex(&bytes));
+
+        tokens.push(ApiToken {
+            name: name.to_string(),
+            role,
+            created_at: Utc::now(),
+            hash: hash_token(&value),
+        });
+        self.save(&tokens)?;
+
+        Ok(value)
+    }
+
+    pub fn revoke(&self, name: &str) -> Result<bool> {
+        let mut tokens = self.list()?;
+        let count = tokens.len();
+        tokens.retain(|token| token.name != name);
+
+        if tokens.len() == count {
+            return Ok(false);
+        }
+        self.save(&tokens)?;
+        Ok(true)
+    }
+
+    pub fn authenticate(&self, value: &str) -> Result<Option<ApiToken>> {
+        if !value.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+
+        let hash = hash_token(value);
+        Ok(self.list()?.into_iter().find(|token| token.hash == hash))
+    }
+}
+
+fn hash_token(value: &str) -> String {
+    to_hex(digest(&SHA256, value.as_bytes()).as_ref())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_store() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let store = TokenStore::new(temp_dir.path());
+
+        let admin = store.create("ci", Role::Admin)?;
+        let reader = store.create("dashboard", Role::ReadOnly)?;
+        assert!(store.create("ci", Role::ReadOnly).is_err());
+
+        let content = fs::read_to_string(temp_dir.path().join(".data/tokens.json"))?;
+        assert!(!content.contains(&admin));
+        assert!(!content.contains(&reader));
+
+        assert_eq!(store.authenticate(&admin)?.unwrap().role, Role::Admin);
+        assert_eq!(store.authenticate(&reader)?.unwrap().name, "dashboard");
+        assert!(store.authenticate("fbx_unknown")?.is_none());
+        assert!(store.authenticate("")?.is_none());
+
+        assert!(store.revoke("ci")?);
+        assert!(!store.revoke("ci")?);
+        assert!(store.authenticate(&admin)?.is_none());
+        assert_eq!(store.list()?.len(), 1);
+
+        Ok(())
+    }
+}
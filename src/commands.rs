@@ -1,3 +1,7 @@
 pub mod new;
+pub mod run;
 pub mod samples;
+pub mod secret;
 pub mod start;
+pub mod token;
+pub mod validate;
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::{
+    config::Config,
+    dependency::{Graph, affected_subgraph, save_graph},
+    pipeline::{
+        build::{Pipeline, create_execution_subgraph},
+        ducklake::DuckLake,
+        execution::ExecutionOptions,
+    },
+    status::{Phase, StatusManager},
+};
+
+pub async fn execute_run(
+    mut config: Config,
+    target: Option<String>,
+    mut options: ExecutionOptions,
+) -> Result<()> {
+    if !config.project_dir.join("project.yml").exists() {
+        return Err(anyhow::anyhow!(
+            "The directory is not a valid Featherbox project (missing project.yml)",
+        ));
+    }
+
+    config.load()?;
+
+    if config.adapters.is_empty() && config.models.is_empty() {
+        return Err(anyhow::anyhow!("No adapters or models found"));
+    }
+
+    let current_graph = Graph::from_config(&config)?;
+    let execution_graph = match &target {
+        Some(target) => create_execution_subgraph(&current_graph, target)?,
+        None if options.full_refresh => current_graph.clone(),
+        None => {
+            let (graph, conditional) =
+                affected_subgraph(&config.project_dir, &current_graph, &config).await?;
+            options.conditional = conditional;
+            graph
+        }
+    };
+
+    if execution_graph.nodes.is_empty() {
+        println!("✓ No changes detected, nothing to run");
+        return Ok(());
+    }
+
+    let pipeline = Pipeline::from_graph(&execution_graph)?;
+
+    let ducklake = DuckLake::from_config(&config).await?;
+    save_graph(&config.project_dir, &current_graph).await?;
+
+    match &target {
+        Some(target) => println!("Running pipeline for target '{target}' ..."),
+        None => println!("Running pipeline ..."),
+    }
+
+    let ctrl_c_token = options.cancellation_token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            println!("\nCancelling pipeline ...");
+            ctrl_c_token.cancel();
+        }
+    });
+
+    let mut status_manager = StatusManager::new(&config.project_dir);
+    let result = pipeline
+        .execute_with_status(
+            &current_graph,
+            &config,
+            &ducklake,
+            &config.project_dir,
+            &mut status_manager,
+            options,
+        )
+        .await;
+
+    if let Ok(status) = status_manager.load().await {
+        for action in pipeline.all_actions() {
+            let Some(task) = status.tasks.get(&action.table_name) else {
+                continue;
+            };
+            let duration = task
+                .duration_ms()
+                .map(|ms| format!(" ({ms}ms)"))
+                .unwrap_or_default();
+
+            match task.phase {
+                Phase::Completed => println!("  ✓ {}{duration}", action.table_name),
+                Phase::Failed => {
+                    let message = task
+                        .error
+                        .as_ref()
+                        .map(|error| error.message.as_str())
+                        .unwrap_or("unknown error");
+                    println!("  ✗ {}: {message}", action.table_name);
+                }
+                Phase::Cancelled => println!("  - {} (cancelled)", action.table_name),
+                _ => println!("  - {} (skipped)", action.table_name),
+            }
+        }
+    }
+
+    if let Some(history) = &config.project.history
+        && let Err(e) = StatusManager::prune(&config.project_dir, history).await
+    {
+        eprintln!("⚠ Failed to prune run history: {e}");
+    }
+
+    result?;
+
+    println!("✓ Pipeline completed successfully");
+    Ok(())
+}
+
+/// Parses a `--from`/`--to` bound given as RFC 3339, `YYYY-MM-DDTHH:MM:SS` or `YYYY-MM-DD` (UTC).
+pub fn parse_time_bound(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(datetime.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid date '{value}', expected YYYY-MM-DD or RFC 3339"))
+}
@@ -2,8 +2,9 @@ use std::path::Path;
 
 use crate::config::{
     AdapterConfig, Config, DashboardConfig, ModelConfig, QueryConfig,
-    adapter::{AdapterSource, FileConfig, FormatConfig},
+    adapter::{AdapterSource, FileConfig, FormatConfig, ImportMode},
     dashboard::{ChartConfig, ChartType},
+    model::Materialization,
 };
 use anyhow::Result;
 use rusqlite::Connection;
@@ -144,12 +145,16 @@ fn create_sample_adapters(config: &mut Config) -> Result<()> {
     // Users CSV adapter
     let users_config = AdapterConfig {
         connection: "local_files".to_string(),
+        mode: ImportMode::Full,
+        tests: vec![],
         description: Some("User data from CSV file".to_string()),
         source: AdapterSource::File {
             file: FileConfig {
                 path: "users.csv".to_string(),
                 compression: None,
                 max_batch_size: None,
+                lookback_days: None,
+                partition_column: None,
             },
             format: FormatConfig {
                 ty: "csv".to_string(),
@@ -165,12 +170,16 @@ fn create_sample_adapters(config: &mut Config) -> Result<()> {
     // App logs JSON adapter
     let app_logs_config = AdapterConfig {
         connection: "local_files".to_string(),
+        mode: ImportMode::Full,
+        tests: vec![],
         description: Some("Application logs from JSON files".to_string()),
         source: AdapterSource::File {
             file: FileConfig {
                 path: "app_log_*.json".to_string(),
                 compression: None,
                 max_batch_size: None,
+                lookback_days: None,
+                partition_column: None,
             },
             format: FormatConfig {
                 ty: "json".to_string(),
@@ -188,9 +197,14 @@ fn create_sample_adapters(config: &mut Config) -> Result<()> {
     // Products database adapter
     let products_config = AdapterConfig {
         connection: "sample_db".to_string(),
+        mode: ImportMode::Full,
+        tests: vec![],
         description: Some("Product data from database".to_string()),
         source: AdapterSource::Database {
-            table_name: "products".to_string(),
+            table_name: Some("products".to_string()),
+            query: None,
+            cursor_column: None,
+            primary_key: vec![],
         },
         columns: vec![],
     };
@@ -199,9 +213,14 @@ fn create_sample_adapters(config: &mut Config) -> Result<()> {
     // Orders database adapter
     let orders_config = AdapterConfig {
         connection: "sample_db".to_string(),
+        mode: ImportMode::Full,
+        tests: vec![],
         description: Some("Order data from database".to_string()),
         source: AdapterSource::Database {
-            table_name: "orders".to_string(),
+            table_name: Some("orders".to_string()),
+            query: None,
+            cursor_column: None,
+            primary_key: vec![],
         },
         columns: vec![],
     };
@@ -214,6 +233,9 @@ fn create_sample_models(config: &mut Config) -> Result<()> {
     // Staging: app_logs model
     let app_logs_config = ModelConfig {
         description: Some("Cleaned application logs".to_string()),
+        materialization: Materialization::Table,
+        unique_key: vec![],
+        tests: vec![],
         sql: "SELECT
     timestamp::TIMESTAMP as event_time,
     user_id,
@@ -232,6 +254,9 @@ WHERE duration > 0"
     // Marts: user_activity_summary model
     let user_activity_config = ModelConfig {
         description: Some("User activity summary".to_string()),
+        materialization: Materialization::Table,
+        unique_key: vec![],
+        tests: vec![],
         sql: "SELECT
     u.user_id,
     u.name,
@@ -252,6 +277,9 @@ GROUP BY u.user_id, u.name, u.email"
     // Marts: product_performance model
     let product_performance_config = ModelConfig {
         description: Some("Product performance metrics".to_string()),
+        materialization: Materialization::Table,
+        unique_key: vec![],
+        tests: vec![],
         sql: "SELECT
     p.product_id,
     p.name as product_name,
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::{io::BufRead, path::Path};
+
+use crate::{commands::new::create_secret_key, secret::SecretManager};
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Create the project's secret key
+    GenKey,
+    /// Add or replace a secret; the value is read from stdin when omitted
+    Set { name: String, value: Option<String> },
+    /// List secret names
+    List,
+    /// Delete a secret
+    Delete { name: String },
+}
+
+pub fn execute_secret(project_dir: &Path, command: &SecretCommands) -> Result<()> {
+    if !project_dir.join("project.yml").exists() {
+        return Err(anyhow::anyhow!(
+            "The directory is not a valid Featherbox project (missing project.yml)",
+        ));
+    }
+
+    let manager = SecretManager::new(project_dir);
+
+    match command {
+        SecretCommands::GenKey => {
+            if manager.key_exists() {
+                return Err(anyhow::anyhow!(
+                    "Secret key already exists. Existing secrets can't be decrypted with a new key."
+                ));
+            }
+            create_secret_key(project_dir)?;
+            println!("✓ Secret key created at .secret.key");
+        }
+        SecretCommands::Set { name, value } => {
+            let value = match value {
+                Some(value) => value.clone(),
+                None => read_value_from_stdin()?,
+            };
+            manager.set_secret(name, &value)?;
+            println!("✓ Secret '{name}' saved. Reference it as ${{secret:{name}}}");
+        }
+        SecretCommands::List => {
+            for name in manager.list_secrets()? {
+                println!("{name}");
+            }
+        }
+        SecretCommands::Delete { name } => {
+            if !manager.delete_secret(name)? {
+                return Err(anyhow::anyhow!("Secret '{name}' not found"));
+            }
+            println!("✓ Secret '{name}' deleted");
+        }
+    }
+
+    Ok(())
+}
+
+fn read_value_from_stdin() -> Result<String> {
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -2,54 +2,71 @@ use anyhow::Result;
 use std::process::Command;
 use tokio::task::JoinHandle;
 
+use crate::api::ServerConfig;
 use crate::config::Config;
 
-pub async fn execute_start(config: Config, port: u16) -> Result<()> {
+pub async fn execute_start(mut config: Config, server: ServerConfig) -> Result<()> {
     if !config.project_dir.join("project.yml").exists() {
         return Err(anyhow::anyhow!(
             "The directory is not a valid Featherbox project (missing project.yml)",
         ));
     }
 
+    config.load()?;
+    crate::api::check_exposure(&config, &server).await?;
+
     println!("Starting Featherbox for project ...");
 
+    let api_server = server.clone();
     let api_handle: JoinHandle<Result<()>> =
-        tokio::spawn(async move { crate::api::main(config).await });
+        tokio::spawn(async move { crate::api::main(config, api_server).await });
 
-    // Wait a moment for the API server to start
+    // Wait a moment for the servers to start
     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-    println!("✓ API server started on port {port}");
 
-    // Start the UI server
-    let ui_handle: JoinHandle<Result<()>> =
-        tokio::spawn(async move { crate::ui::start_ui_server().await });
+    let ui_url = server.ui_url();
+    if server.open_browser {
+        open_browser(&ui_url);
+    }
 
-    // Wait a moment for the UI server to start
-    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    println!("\n🚀 Featherbox is running!");
+    println!("   API: {}", server.api_url());
+    println!("   UI: {ui_url}");
+    println!("\nPress Ctrl+C to stop");
 
-    println!("✓ Opening browser at http://localhost:8015");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {
+            println!("\nShutting down...");
+        }
+        api_result = api_handle => {
+            match api_result {
+                Ok(Ok(())) => println!("Server stopped"),
+                Ok(Err(e)) => eprintln!("Server error: {e}"),
+                Err(e) => eprintln!("Server task error: {e}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn open_browser(url: &str) {
+    println!("✓ Opening browser at {url}");
 
-    // Try to open the browser
     let browser_result = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(["/c", "start", "http://localhost:8015"])
-            .spawn()
+        Command::new("cmd").args(["/c", "start", url]).spawn()
     } else if cfg!(target_os = "macos") {
-        Command::new("open").arg("http://localhost:8015").spawn()
+        Command::new("open").arg(url).spawn()
     } else {
         // Linux and other Unix-like systems
         Command::new("xdg-open")
-            .arg("http://localhost:8015")
+            .arg(url)
             .spawn()
             .or_else(|_| {
                 // Fallback options for Linux
-                Command::new("firefox").arg("http://localhost:8015").spawn()
-            })
-            .or_else(|_| {
-                Command::new("chromium")
-                    .arg("http://localhost:8015")
-                    .spawn()
+                Command::new("firefox").arg(url).spawn()
             })
+            .or_else(|_| Command::new("chromium").arg(url).spawn())
     };
 
     match browser_result {
@@ -58,34 +75,7 @@ pub async fn execute_start(config: Config, port: u16) -> Result<()> {
         }
         Err(e) => {
             println!("⚠ Could not open browser automatically: {e}");
-            println!("  Please manually open: http://localhost:8015");
+            println!("  Please manually open: {url}");
         }
     }
-
-    println!("\n🚀 Featherbox is running!");
-    println!("   API: http://localhost:{port}");
-    println!("   UI: http://localhost:8015");
-    println!("\nPress Ctrl+C to stop");
-
-    tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            println!("\nShutting down...");
-        }
-        api_result = api_handle => {
-            match api_result {
-                Ok(Ok(())) => println!("API server stopped"),
-                Ok(Err(e)) => eprintln!("API server error: {e}"),
-                Err(e) => eprintln!("API server task error: {e}"),
-            }
-        }
-        ui_result = ui_handle => {
-            match ui_result {
-                Ok(Ok(())) => println!("UI server stopped"),
-                Ok(Err(e)) => eprintln!("UI server error: {e}"),
-                Err(e) => eprintln!("UI server task error: {e}"),
-            }
-        }
-    }
-
-    Ok(())
 }
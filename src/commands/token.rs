@@ -0,0 +1,55 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::auth::{Role, TokenStore};
+
+#[derive(Subcommand)]
+pub enum TokenCommands {
+    /// Create an API token; its value is only shown once
+    Create {
+        name: String,
+        #[arg(long, value_enum, default_value = "read-only")]
+        role: Role,
+    },
+    /// List API tokens
+    List,
+    /// Revoke an API token
+    Revoke { name: String },
+}
+
+pub fn execute_token(project_dir: &Path, command: &TokenCommands) -> Result<()> {
+    if !project_dir.join("project.yml").exists() {
+        return Err(anyhow::anyhow!(
+            "The directory is not a valid Featherbox project (missing project.yml)",
+        ));
+    }
+
+    let store = TokenStore::new(project_dir);
+
+    match command {
+        TokenCommands::Create { name, role } => {
+            let token = store.create(name, *role)?;
+            println!("✓ Token '{name}' created. Store it now, it will not be shown again:");
+            println!("{token}");
+        }
+        TokenCommands::List => {
+            for token in store.list()? {
+                println!(
+                    "{}\t{:?}\t{}",
+                    token.name,
+                    token.role,
+                    token.created_at.format("%Y-%m-%d %H:%M:%S")
+                );
+            }
+        }
+        TokenCommands::Revoke { name } => {
+            if !store.revoke(name)? {
+                return Err(anyhow::anyhow!("Token '{name}' not found"));
+            }
+            println!("✓ Token '{name}' revoked");
+        }
+    }
+
+    Ok(())
+}
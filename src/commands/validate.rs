@@ -0,0 +1,28 @@
+use anyhow::Result;
+
+use crate::{config::Config, dependency::Graph};
+
+pub fn execute_validate(mut config: Config) -> Result<()> {
+    if !config.project_dir.join("project.yml").exists() {
+        return Err(anyhow::anyhow!(
+            "The directory is not a valid Featherbox project (missing project.yml)",
+        ));
+    }
+
+    config.load()?;
+
+    let errors = Graph::validate(&config);
+    if errors.is_empty() {
+        println!(
+            "✓ {} adapters and {} models are valid",
+            config.adapters.len(),
+            config.models.len()
+        );
+        return Ok(());
+    }
+
+    for error in &errors {
+        println!("✗ {error}");
+    }
+    Err(anyhow::anyhow!("Found {} problem(s)", errors.len()))
+}
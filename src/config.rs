@@ -1,3 +1,4 @@
+use crate::secret::SecretManager;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -6,6 +7,7 @@ use std::path::{Path, PathBuf};
 
 pub mod adapter;
 pub mod dashboard;
+pub mod data_test;
 pub mod model;
 pub mod project;
 pub mod query;
@@ -62,7 +64,7 @@ impl Config {
     }
 
     pub fn load(&mut self) -> Result<()> {
-        self.project = self.load_project_config()?;
+        self.reload_project()?;
         self.adapters = self.load_adapters()?;
         self.models = self.load_models()?;
         self.queries = self.load_queries()?;
@@ -71,11 +73,17 @@ impl Config {
         Ok(())
     }
 
+    /// Re-reads `project.yml`, resolving `${secret:NAME}` references with the current secrets.
+    pub fn reload_project(&mut self) -> Result<()> {
+        self.project = self.load_project_config()?;
+        Ok(())
+    }
+
     pub fn add_project_setting<'a>(
         &mut self,
         config: &'a ProjectConfig,
     ) -> Result<UpsertFileHandle<'a, ProjectConfig>> {
-        self.project = config.clone();
+        self.project = config.resolve_secrets(&SecretManager::new(&self.project_dir))?;
 
         Ok(UpsertFileHandle {
             config,
@@ -218,6 +226,13 @@ impl Config {
     }
 
     fn load_project_config(&self) -> Result<ProjectConfig> {
+        self.project_source()?
+            .resolve_secrets(&SecretManager::new(&self.project_dir))
+    }
+
+    /// The project settings as written in `project.yml`, with `${secret:NAME}` references
+    /// left in place. Use this when the settings are going to be shown or saved back.
+    pub fn project_source(&self) -> Result<ProjectConfig> {
         let project_yml_path = self.project_config_file()?;
         if !project_yml_path.exists() {
             return Err(anyhow::anyhow!(
@@ -1,3 +1,4 @@
+use crate::config::data_test::DataTestConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -6,6 +7,10 @@ pub struct AdapterConfig {
     pub description: Option<String>,
     pub source: AdapterSource,
     pub columns: Vec<ColumnConfig>,
+    #[serde(default, skip_serializing_if = "ImportMode::is_full")]
+    pub mode: ImportMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<DataTestConfig>,
 }
 
 impl AdapterConfig {
@@ -13,6 +18,21 @@ impl AdapterConfig {
         self.connection != other.connection
             || self.source != other.source
             || self.columns != other.columns
+            || self.mode != other.mode
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    #[default]
+    Full,
+    Incremental,
+}
+
+impl ImportMode {
+    pub fn is_full(&self) -> bool {
+        *self == ImportMode::Full
     }
 }
 
@@ -25,7 +45,18 @@ pub enum AdapterSource {
         format: FormatConfig,
     },
     #[serde(rename = "database")]
-    Database { table_name: String },
+    Database {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        table_name: Option<String>,
+        /// SQL run against the source database instead of reading `table_name`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+        /// Increasing column used as the high-water mark in incremental mode.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cursor_column: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        primary_key: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,6 +64,8 @@ pub struct FileConfig {
     pub path: String,
     pub compression: Option<String>,
     pub max_batch_size: Option<String>,
+    pub lookback_days: Option<u32>,
+    pub partition_column: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -52,6 +85,38 @@ pub struct ColumnConfig {
     pub description: Option<String>,
 }
 
+impl ColumnConfig {
+    pub fn sql_type(&self) -> anyhow::Result<String> {
+        let normalized = self.ty.trim().to_uppercase();
+        let mapped = match normalized.as_str() {
+            "STRING" | "TEXT" | "VARCHAR" => "VARCHAR",
+            "INT" | "INTEGER" => "INTEGER",
+            "LONG" | "BIGINT" => "BIGINT",
+            "FLOAT" | "DOUBLE" | "NUMBER" => "DOUBLE",
+            "BOOL" | "BOOLEAN" => "BOOLEAN",
+            "DATE" => "DATE",
+            "TIME" => "TIME",
+            "DATETIME" | "TIMESTAMP" => "TIMESTAMP",
+            "TIMESTAMPTZ" => "TIMESTAMPTZ",
+            "JSON" => "JSON",
+            _ => {
+                let re =
+                    regex::Regex::new(r"^[A-Z][A-Z0-9_ ]*(\(\s*\d+\s*(,\s*\d+\s*)?\))?$").unwrap();
+                if !re.is_match(&normalized) {
+                    return Err(anyhow::anyhow!(
+                        "Unsupported type '{}' for column '{}'",
+                        self.ty,
+                        self.name
+                    ));
+                }
+                return Ok(normalized);
+            }
+        };
+
+        Ok(mapped.to_string())
+    }
+}
+
 pub fn parse_adapter_config(yaml_str: &str) -> anyhow::Result<AdapterConfig> {
     serde_yml::from_str(yaml_str)
         .map_err(|e| anyhow::anyhow!("Failed to parse adapter config: {}", e))
@@ -85,8 +150,16 @@ mod tests {
         );
 
         match &config.source {
-            AdapterSource::Database { table_name } => {
-                assert_eq!(table_name, "users");
+            AdapterSource::Database {
+                table_name,
+                query,
+                cursor_column,
+                primary_key,
+            } => {
+                assert_eq!(table_name.as_deref(), Some("users"));
+                assert_eq!(query, &None);
+                assert_eq!(cursor_column, &None);
+                assert!(primary_key.is_empty());
             }
             _ => panic!("Expected Database source"),
         }
@@ -96,6 +169,7 @@ mod tests {
         assert_eq!(config.columns[0].ty, "INTEGER");
         assert_eq!(config.columns[1].name, "name");
         assert_eq!(config.columns[1].ty, "STRING");
+        assert_eq!(config.mode, ImportMode::Full);
     }
 
     #[test]
@@ -109,8 +183,11 @@ mod tests {
                 path: data/logs.json
                 compression: gzip
                 max_batch_size: 100MB
+                lookback_days: 7
+                partition_column: log_date
               format:
                 type: json
+            mode: incremental
             columns:
               - name: timestamp
                 type: DATETIME
@@ -131,6 +208,8 @@ mod tests {
                 assert_eq!(file.path, "data/logs.json");
                 assert_eq!(file.compression, Some("gzip".to_string()));
                 assert_eq!(file.max_batch_size, Some("100MB".to_string()));
+                assert_eq!(file.lookback_days, Some(7));
+                assert_eq!(file.partition_column, Some("log_date".to_string()));
                 assert_eq!(format.ty, "json");
             }
             _ => panic!("Expected File source"),
@@ -141,6 +220,34 @@ mod tests {
         assert_eq!(config.columns[0].ty, "DATETIME");
         assert_eq!(config.columns[1].name, "message");
         assert_eq!(config.columns[1].ty, "STRING");
+        assert_eq!(config.mode, ImportMode::Incremental);
+    }
+
+    #[test]
+    fn test_parse_adapter_config_incremental_database_query() {
+        let yaml_str = r#"
+            connection: test_db
+            mode: incremental
+            source:
+              type: database
+              query: SELECT * FROM orders WHERE status <> 'draft'
+              cursor_column: updated_at
+              primary_key: [id]
+            columns: []
+        "#;
+
+        let config = parse_adapter_config(yaml_str).unwrap();
+
+        assert_eq!(config.mode, ImportMode::Incremental);
+        assert_eq!(
+            config.source,
+            AdapterSource::Database {
+                table_name: None,
+                query: Some("SELECT * FROM orders WHERE status <> 'draft'".to_string()),
+                cursor_column: Some("updated_at".to_string()),
+                primary_key: vec!["id".to_string()],
+            }
+        );
     }
 
     #[test]
@@ -159,9 +266,14 @@ mod tests {
     fn test_adapter_config_has_changed() {
         let config1 = AdapterConfig {
             connection: "test_db".to_string(),
+            mode: ImportMode::Full,
+            tests: vec![],
             description: Some("Test adapter".to_string()),
             source: AdapterSource::Database {
-                table_name: "users".to_string(),
+                table_name: Some("users".to_string()),
+                query: None,
+                cursor_column: None,
+                primary_key: vec![],
             },
             columns: vec![ColumnConfig {
                 name: "id".to_string(),
@@ -183,7 +295,10 @@ mod tests {
 
         let mut config5 = config1.clone();
         config5.source = AdapterSource::Database {
-            table_name: "products".to_string(),
+            table_name: Some("products".to_string()),
+            query: None,
+            cursor_column: None,
+            primary_key: vec![],
         };
         assert!(config1.has_changed(&config5));
 
@@ -202,5 +317,29 @@ mod tests {
         let mut config8 = config1.clone();
         config8.columns[0].description = Some("Primary key".to_string());
         assert!(config1.has_changed(&config8));
+
+        let mut config9 = config1.clone();
+        config9.mode = ImportMode::Incremental;
+        assert!(config1.has_changed(&config9));
+    }
+
+    #[test]
+    fn test_column_sql_type() {
+        let column = |ty: &str| ColumnConfig {
+            name: "value".to_string(),
+            ty: ty.to_string(),
+            description: None,
+        };
+
+        assert_eq!(column("STRING").sql_type().unwrap(), "VARCHAR");
+        assert_eq!(column("datetime").sql_type().unwrap(), "TIMESTAMP");
+        assert_eq!(column("Integer").sql_type().unwrap(), "INTEGER");
+        assert_eq!(column("bool").sql_type().unwrap(), "BOOLEAN");
+        assert_eq!(
+            column("decimal(10, 2)").sql_type().unwrap(),
+            "DECIMAL(10, 2)"
+        );
+        assert_eq!(column("UUID").sql_type().unwrap(), "UUID");
+        assert!(column("INTEGER); DROP TABLE users; --").sql_type().is_err());
     }
 }
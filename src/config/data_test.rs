@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataTestConfig {
+    #[serde(flatten)]
+    pub check: DataCheck,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DataCheck {
+    NotNull {
+        column: String,
+    },
+    Unique {
+        columns: Vec<String>,
+    },
+    AcceptedValues {
+        column: String,
+        values: Vec<String>,
+    },
+    Relationships {
+        column: String,
+        to: String,
+        field: String,
+    },
+    RowCount {
+        min: Option<u64>,
+        max: Option<u64>,
+    },
+    /// A query returning the rows that violate the expectation.
+    Sql {
+        sql: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warn,
+    #[default]
+    Error,
+}
+
+impl DataCheck {
+    pub fn describe(&self) -> String {
+        match self {
+            DataCheck::NotNull { column } => format!("not_null({column})"),
+            DataCheck::Unique { columns } => format!("unique({})", columns.join(", ")),
+            DataCheck::AcceptedValues { column, .. } => format!("accepted_values({column})"),
+            DataCheck::Relationships { column, to, field } => {
+                format!("relationships({column} -> {to}.{field})")
+            }
+            DataCheck::RowCount { min, max } => {
+                let bound = |value: &Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+                format!("row_count({}..{})", bound(min), bound(max))
+            }
+            DataCheck::Sql { .. } => "sql".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data_tests() {
+        let yaml = r#"
+            - type: not_null
+              column: id
+            - type: unique
+              columns: [id]
+            - type: accepted_values
+              column: status
+              values: [active, inactive]
+              severity: warn
+            - type: relationships
+              column: user_id
+              to: users
+              field: id
+            - type: row_count
+              min: 1
+            - type: sql
+              sql: SELECT * FROM orders WHERE amount < 0
+        "#;
+
+        let tests: Vec<DataTestConfig> = serde_yml::from_str(yaml).unwrap();
+        assert_eq!(tests.len(), 6);
+        assert_eq!(
+            tests[0].check,
+            DataCheck::NotNull {
+                column: "id".to_string()
+            }
+        );
+        assert_eq!(tests[0].severity, Severity::Error);
+        assert_eq!(tests[2].severity, Severity::Warn);
+        assert_eq!(
+            tests[4].check,
+            DataCheck::RowCount {
+                min: Some(1),
+                max: None
+            }
+        );
+        assert_eq!(
+            tests[3].check.describe(),
+            "relationships(user_id -> users.id)"
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_data_test_type() {
+        let yaml = r#"
+            - type: positive
+              column: amount
+        "#;
+
+        assert!(serde_yml::from_str::<Vec<DataTestConfig>>(yaml).is_err());
+    }
+}
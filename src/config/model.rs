@@ -1,14 +1,111 @@
+use crate::config::data_test::DataTestConfig;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModelConfig {
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Materialization::is_table")]
+    pub materialization: Materialization,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique_key: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<DataTestConfig>,
     pub sql: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Materialization {
+    #[default]
+    Table,
+    View,
+    IncrementalAppend,
+    IncrementalMerge,
+}
+
+impl Materialization {
+    pub fn is_table(&self) -> bool {
+        *self == Materialization::Table
+    }
+
+    pub fn is_incremental(&self) -> bool {
+        matches!(
+            self,
+            Materialization::IncrementalAppend | Materialization::IncrementalMerge
+        )
+    }
+}
+
 impl ModelConfig {
     pub fn has_changed(&self, other: &Self) -> bool {
         self.sql != other.sql
+            || self.materialization != other.materialization
+            || self.unique_key != other.unique_key
+    }
+
+    /// Renders the model's SQL template. `{{ this }}` becomes the model's own table and
+    /// `{% if is_incremental() %} ... {% else %} ... {% endif %}` blocks are kept or
+    /// dropped depending on whether an incremental run is appending to an existing table.
+    pub fn render_sql(&self, this: &str, is_incremental: bool) -> anyhow::Result<String> {
+        let tag_re = Regex::new(r"(?s)\{\{(.*?)\}\}|\{%(.*?)%\}").unwrap();
+
+        let mut rendered = String::new();
+        let mut conditions: Vec<(bool, bool)> = Vec::new();
+        let mut last = 0;
+
+        for captures in tag_re.captures_iter(&self.sql) {
+            let tag = captures.get(0).unwrap();
+            let emitting = conditions.iter().all(|(condition, _)| *condition);
+            if emitting {
+                rendered.push_str(&self.sql[last..tag.start()]);
+            }
+            last = tag.end();
+
+            if let Some(expression) = captures.get(1) {
+                match expression.as_str().trim() {
+                    "this" => {
+                        if emitting {
+                            rendered.push_str(this);
+                        }
+                    }
+                    other => {
+                        return Err(anyhow::anyhow!("Unsupported template expression '{other}'"));
+                    }
+                }
+                continue;
+            }
+
+            let statement = captures.get(2).unwrap().as_str().trim();
+            match statement.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["if", "is_incremental()"] => conditions.push((is_incremental, false)),
+                ["if", "not", "is_incremental()"] => conditions.push((!is_incremental, false)),
+                ["else"] => match conditions.last_mut() {
+                    Some((condition, in_else)) if !*in_else => {
+                        *condition = !*condition;
+                        *in_else = true;
+                    }
+                    _ => return Err(anyhow::anyhow!("Unexpected {{% else %}} in model SQL")),
+                },
+                ["endif"] => {
+                    if conditions.pop().is_none() {
+                        return Err(anyhow::anyhow!("Unexpected {{% endif %}} in model SQL"));
+                    }
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Unsupported template statement '{{% {statement} %}}'"
+                    ));
+                }
+            }
+        }
+
+        if !conditions.is_empty() {
+            return Err(anyhow::anyhow!("Missing {{% endif %}} in model SQL"));
+        }
+        rendered.push_str(&self.sql[last..]);
+
+        Ok(rendered)
     }
 }
 
@@ -106,6 +203,9 @@ mod tests {
     fn test_model_config_has_changed() {
         let config1 = ModelConfig {
             description: Some("Test model".to_string()),
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
             sql: "SELECT * FROM users".to_string(),
         };
 
@@ -126,15 +226,95 @@ mod tests {
 
         let config6 = ModelConfig {
             description: None,
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
             sql: "SELECT * FROM users".to_string(),
         };
         assert!(!config1.has_changed(&config6));
+
+        let mut config7 = config1.clone();
+        config7.materialization = Materialization::IncrementalMerge;
+        assert!(config1.has_changed(&config7));
+
+        let mut config8 = config7.clone();
+        config8.unique_key = vec!["id".to_string()];
+        assert!(config7.has_changed(&config8));
+    }
+
+    #[test]
+    fn test_parse_model_config_materialization() {
+        let yaml_str = r#"
+            materialization: incremental_merge
+            unique_key: [event_id]
+            sql: SELECT * FROM events
+        "#;
+
+        let config = parse_model_config(yaml_str).unwrap();
+        assert_eq!(config.materialization, Materialization::IncrementalMerge);
+        assert_eq!(config.unique_key, vec!["event_id"]);
+
+        let config = parse_model_config("sql: SELECT 1").unwrap();
+        assert_eq!(config.materialization, Materialization::Table);
+        assert!(
+            !serde_yml::to_string(&config)
+                .unwrap()
+                .contains("materialization")
+        );
+    }
+
+    #[test]
+    fn test_render_sql() {
+        let config = parse_model_config(
+            r#"
+            sql: |
+              SELECT * FROM events
+              {% if is_incremental() %}
+              WHERE ts > (SELECT MAX(ts) FROM {{ this }})
+              {% else %}
+              WHERE ts > '2020-01-01'
+              {% endif %}
+            "#,
+        )
+        .unwrap();
+
+        let incremental = config.render_sql("daily_events", true).unwrap();
+        assert!(incremental.contains("SELECT MAX(ts) FROM daily_events"));
+        assert!(!incremental.contains("2020-01-01"));
+
+        let full = config.render_sql("daily_events", false).unwrap();
+        assert!(full.contains("WHERE ts > '2020-01-01'"));
+        assert!(!full.contains("daily_events"));
+        assert!(!full.contains("{%"));
+    }
+
+    #[test]
+    fn test_render_sql_errors() {
+        let render = |sql: &str| {
+            ModelConfig {
+                description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
+                sql: sql.to_string(),
+            }
+            .render_sql("model", true)
+        };
+
+        assert!(render("SELECT 1 {% if is_incremental() %}").is_err());
+        assert!(render("SELECT 1 {% endif %}").is_err());
+        assert!(render("SELECT {{ unknown }}").is_err());
+        assert!(render("SELECT 1 {% for x in y %}").is_err());
+        assert_eq!(render("SELECT 1").unwrap(), "SELECT 1");
     }
 
     #[test]
     fn test_model_config_serde() {
         let config = ModelConfig {
             description: Some("Test model for analysis".to_string()),
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
             sql: r#"SELECT 
                 id,
                 name,
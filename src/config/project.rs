@@ -1,18 +1,58 @@
-use anyhow::Result;
+use crate::redact::MASK;
+use crate::secret::SecretManager;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub storage: StorageConfig,
     pub database: DatabaseConfig,
     pub connections: HashMap<String, ConnectionConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api: Option<ApiConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineConfig>,
 }
 
 impl ProjectConfig {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns a copy with `${secret:NAME}` references in the storage, database and
+    /// connection settings replaced by their decrypted values.
+    pub fn resolve_secrets(&self, secrets: &SecretManager) -> Result<Self> {
+        let mut resolved = self.clone();
+
+        if let StorageConfig::S3(s3) = &mut resolved.storage {
+            s3.resolve_secrets(secrets)?;
+        }
+        resolved.database.resolve_secrets(secrets)?;
+        for (name, connection) in &mut resolved.connections {
+            connection
+                .resolve_secrets(secrets)
+                .with_context(|| format!("Failed to resolve secrets of connection '{name}'"))?;
+        }
+
+        Ok(resolved)
+    }
+}
+
+fn resolve_secret(value: &mut String, secrets: &SecretManager) -> Result<()> {
+    *value = secrets.expand(value)?;
+    Ok(())
+}
+
+fn resolve_optional_secret(value: &mut Option<String>, secrets: &SecretManager) -> Result<()> {
+    if let Some(value) = value {
+        resolve_secret(value, secrets)?;
+    }
+    Ok(())
 }
 
 impl Default for ProjectConfig {
@@ -31,10 +71,78 @@ impl Default for ProjectConfig {
                 password: None,
             },
             connections: HashMap::new(),
+            history: None,
+            api: None,
+            engine: None,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runs: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ApiConfig {
+    /// Require an API token (`featherbox token create`) on every request.
+    #[serde(default)]
+    pub auth: bool,
+    /// Other origins allowed to call the API from a browser. None are allowed when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cors_origins: Vec<String>,
+    /// Let callers without a token run several statements and read files or other
+    /// databases through the query API while `auth` is off, like admins can.
+    #[serde(default)]
+    pub unrestricted_queries: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Maximum number of pooled DuckDB connections. Defaults to the number of CPUs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<u32>,
+    /// DuckDB `memory_limit`, e.g. `4GB`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<String>,
+    /// DuckDB `threads`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<u32>,
+    /// DuckDB `temp_directory`, where larger-than-memory operations spill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_directory: Option<String>,
+    /// Longest an API query may run. Defaults to 300 seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_timeout_secs: Option<u64>,
+    /// Longest a single adapter or model may run during a pipeline run. Unlimited by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_timeout_secs: Option<u64>,
+    /// Most adapters and models a pipeline run executes at once. Unlimited by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<NonZeroUsize>,
+    /// Most adapters reading from each named connection at once, e.g. `warehouse: 2`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub connection_concurrency: HashMap<String, NonZeroUsize>,
+}
+
+impl EngineConfig {
+    const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 300;
+
+    pub fn query_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.query_timeout_secs
+                .unwrap_or(Self::DEFAULT_QUERY_TIMEOUT_SECS),
+        )
+    }
+
+    pub fn node_timeout(&self) -> Option<Duration> {
+        self.node_timeout_secs.map(Duration::from_secs)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum StorageConfig {
@@ -56,6 +164,16 @@ pub struct S3Config {
     pub path_style_access: bool,
 }
 
+impl S3Config {
+    fn resolve_secrets(&mut self, secrets: &SecretManager) -> Result<()> {
+        resolve_secret(&mut self.bucket, secrets)?;
+        resolve_optional_secret(&mut self.endpoint_url, secrets)?;
+        resolve_secret(&mut self.access_key_id, secrets)?;
+        resolve_secret(&mut self.secret_access_key, secrets)?;
+        resolve_optional_secret(&mut self.session_token, secrets)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     #[serde(rename = "type")]
@@ -74,6 +192,16 @@ pub struct DatabaseConfig {
     pub password: Option<String>,
 }
 
+impl DatabaseConfig {
+    fn resolve_secrets(&mut self, secrets: &SecretManager) -> Result<()> {
+        resolve_optional_secret(&mut self.path, secrets)?;
+        resolve_optional_secret(&mut self.host, secrets)?;
+        resolve_optional_secret(&mut self.database, secrets)?;
+        resolve_optional_secret(&mut self.username, secrets)?;
+        resolve_optional_secret(&mut self.password, secrets)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum S3AuthMethod {
     #[serde(rename = "credential_chain")]
@@ -130,6 +258,83 @@ pub enum ConnectionConfig {
 }
 
 impl ConnectionConfig {
+    fn resolve_secrets(&mut self, secrets: &SecretManager) -> Result<()> {
+        match self {
+            ConnectionConfig::LocalFile { base_path } => resolve_secret(base_path, secrets),
+            ConnectionConfig::S3(config) => config.resolve_secrets(secrets),
+            ConnectionConfig::Sqlite { path } => resolve_secret(path, secrets),
+            ConnectionConfig::MySql {
+                host,
+                database,
+                username,
+                password,
+                ..
+            }
+            | ConnectionConfig::PostgreSql {
+                host,
+                database,
+                username,
+                password,
+                ..
+            } => {
+                resolve_secret(host, secrets)?;
+                resolve_secret(database, secrets)?;
+                resolve_secret(username, secrets)?;
+                resolve_secret(password, secrets)
+            }
+        }
+    }
+
+    fn credentials_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            ConnectionConfig::S3(config) => {
+                let mut fields = vec![&mut config.secret_access_key];
+                fields.extend(config.session_token.as_mut());
+                fields
+            }
+            ConnectionConfig::MySql { password, .. }
+            | ConnectionConfig::PostgreSql { password, .. } => vec![password],
+            ConnectionConfig::LocalFile { .. } | ConnectionConfig::Sqlite { .. } => vec![],
+        }
+    }
+
+    /// Returns a copy with passwords and secret keys replaced by [`MASK`]. `${secret:NAME}`
+    /// references are kept, since they don't reveal the value.
+    pub fn masked(&self) -> Self {
+        let mut masked = self.clone();
+        for value in masked.credentials_mut() {
+            if !value.is_empty() && !is_secret_reference(value) {
+                *value = MASK.to_string();
+            }
+        }
+        masked
+    }
+
+    /// Replaces credentials that a client sent back as [`MASK`] with the stored ones, so
+    /// clients can edit a connection without knowing its secrets.
+    pub fn restore_masked(&mut self, stored: Option<&ConnectionConfig>) -> Result<()> {
+        let mut stored = stored
+            .filter(|stored| std::mem::discriminant(*stored) == std::mem::discriminant(self))
+            .cloned();
+        let stored_values = stored
+            .as_mut()
+            .map(|stored| stored.credentials_mut())
+            .unwrap_or_default();
+        let mut stored_values = stored_values.into_iter();
+
+        for value in self.credentials_mut() {
+            let stored_value = stored_values.next();
+            if value != MASK {
+                continue;
+            }
+            match stored_value {
+                Some(stored_value) => *value = stored_value.clone(),
+                None => return Err(anyhow::anyhow!("Masked credential has no stored value")),
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_full_endpoint_url(&self) -> Option<String> {
         match self {
             ConnectionConfig::S3(config) => config.endpoint_url.clone(),
@@ -160,6 +365,12 @@ impl ConnectionConfig {
     }
 }
 
+fn is_secret_reference(value: &str) -> bool {
+    value
+        .strip_prefix("${secret:")
+        .is_some_and(|rest| rest.ends_with('}') && !rest.contains('{'))
+}
+
 fn expand_env_vars(value: &str) -> Result<String, anyhow::Error> {
     let re = regex::Regex::new(r"\$\{([^}]+)\}").unwrap();
     let mut result = value.to_string();
@@ -168,6 +379,11 @@ fn expand_env_vars(value: &str) -> Result<String, anyhow::Error> {
         let env_var_with_braces = &cap[0];
         let env_var = &cap[1];
 
+        // Secret references are resolved after parsing, by `ProjectConfig::resolve_secrets`.
+        if env_var.starts_with("secret:") {
+            continue;
+        }
+
         match std::env::var(env_var) {
             Ok(env_value) => {
                 result = result.replace(env_var_with_braces, &env_value);
@@ -181,7 +397,7 @@ fn expand_env_vars(value: &str) -> Result<String, anyhow::Error> {
         }
     }
 
-    if result.contains("${") {
+    if re.replace_all(&result, "").contains("${") {
         return Err(anyhow::anyhow!(
             "Unclosed environment variable reference in: {}",
             value
@@ -196,3 +412,99 @@ pub fn parse_project_config(yaml_str: &str) -> anyhow::Result<ProjectConfig> {
     serde_yml::from_str(&expanded_yaml)
         .map_err(|e| anyhow::anyhow!("Failed to parse project config: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::new::create_secret_key;
+
+    #[test]
+    fn test_resolve_secrets() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        create_secret_key(temp_dir.path())?;
+        let secrets = SecretManager::new(temp_dir.path());
+        secrets.set_secret("PG_PASSWORD", "p@ss: #word")?;
+
+        let config = parse_project_config(
+            r#"
+            storage:
+              type: local
+              path: ./storage
+            database:
+              type: sqlite
+              path: ./database.db
+            connections:
+              warehouse:
+                type: postgresql
+                host: localhost
+                port: 5432
+                database: analytics
+                username: reader
+                password: ${secret:PG_PASSWORD}
+            "#,
+        )?;
+
+        let ConnectionConfig::PostgreSql { password, .. } = &config.connections["warehouse"] else {
+            panic!("expected a postgresql connection");
+        };
+        assert_eq!(password, "${secret:PG_PASSWORD}");
+
+        let resolved = config.resolve_secrets(&secrets)?;
+        let ConnectionConfig::PostgreSql { password, .. } = &resolved.connections["warehouse"]
+        else {
+            panic!("expected a postgresql connection");
+        };
+        assert_eq!(password, "p@ss: #word");
+
+        let mut missing = config.clone();
+        missing.database.password = Some("${secret:MISSING}".to_string());
+        assert!(missing.resolve_secrets(&secrets).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_and_restore_credentials() -> Result<()> {
+        let stored = ConnectionConfig::MySql {
+            host: "localhost".to_string(),
+            port: 3306,
+            database: "shop".to_string(),
+            username: "app".to_string(),
+            password: "hunter2".to_string(),
+        };
+
+        let masked = stored.masked();
+        let ConnectionConfig::MySql { password, host, .. } = &masked else {
+            panic!("expected a mysql connection");
+        };
+        assert_eq!(password, MASK);
+        assert_eq!(host, "localhost");
+
+        let mut edited = masked.clone();
+        edited.restore_masked(Some(&stored))?;
+        assert_eq!(edited, stored);
+        assert!(masked.clone().restore_masked(None).is_err());
+
+        let reference = ConnectionConfig::PostgreSql {
+            host: "localhost".to_string(),
+            port: 5432,
+            database: "analytics".to_string(),
+            username: "reader".to_string(),
+            password: "${secret:PG_PASSWORD}".to_string(),
+        };
+        assert_eq!(reference.masked(), reference);
+        assert!(masked.clone().restore_masked(Some(&reference)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_env_vars_keeps_secret_references() {
+        assert_eq!(
+            expand_env_vars("password: ${secret:DB_PASSWORD}").unwrap(),
+            "password: ${secret:DB_PASSWORD}"
+        );
+        assert!(expand_env_vars("password: ${FEATHERBOX_UNSET_TEST_VAR}").is_err());
+        assert!(expand_env_vars("password: ${UNCLOSED").is_err());
+    }
+}
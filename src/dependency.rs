@@ -1,10 +1,12 @@
 pub mod graph;
+pub mod references;
 
-use crate::config::{AdapterConfig, Config, ModelConfig};
-use crate::metadata::Metadata;
+use crate::config::{AdapterConfig, Config, ModelConfig, adapter::ImportMode};
+use crate::metadata::{ImportState, Metadata};
 use anyhow::Result;
 pub use graph::*;
-use std::collections::{HashMap, HashSet};
+pub use references::*;
+use std::collections::HashSet;
 use std::path::Path;
 
 pub async fn detect_changes(
@@ -30,27 +32,17 @@ pub async fn detect_changes(
 
     let last_nodes_with_config: Vec<(String, Option<String>)> = metadata
         .nodes
-        .keys()
-        .map(|name| {
-            let config_json = if let Some(adapter) = config.adapters.get(name) {
-                serde_json::to_string(adapter).ok()
-            } else if let Some(model) = config.models.get(name) {
-                serde_json::to_string(model).ok()
-            } else {
-                None
-            };
-            (name.clone(), config_json)
-        })
+        .iter()
+        .map(|(name, node)| (name.clone(), node.config.clone()))
         .collect();
 
-    let last_nodes: HashSet<String> = metadata.nodes.keys().cloned().collect();
-
-    let mut last_edges: HashSet<(String, String)> = HashSet::new();
-    for (from_node, node_data) in &metadata.nodes {
-        for to_node in &node_data.referenced {
-            last_edges.insert((from_node.clone(), to_node.clone()));
-        }
-    }
+    let last_graph = metadata.graph();
+    let last_nodes: HashSet<String> = last_graph.nodes.into_iter().map(|n| n.name).collect();
+    let last_edges: HashSet<(String, String)> = last_graph
+        .edges
+        .into_iter()
+        .map(|e| (e.from, e.to))
+        .collect();
 
     let current_nodes: HashSet<String> =
         current_graph.nodes.iter().map(|n| n.name.clone()).collect();
@@ -71,14 +63,7 @@ pub async fn detect_changes(
 
     for (node_name, last_config_json) in last_nodes_with_config {
         if current_nodes.contains(&node_name) {
-            let current_config_json = if let Some(adapter_config) = config.adapters.get(&node_name)
-            {
-                Some(serde_json::to_string(adapter_config)?)
-            } else if let Some(model_config) = config.models.get(&node_name) {
-                Some(serde_json::to_string(model_config)?)
-            } else {
-                None
-            };
+            let current_config_json = node_config_json(config, &node_name)?;
 
             match (last_config_json.as_ref(), current_config_json.as_ref()) {
                 (Some(last_json), Some(current_json)) => {
@@ -92,8 +77,11 @@ pub async fn detect_changes(
                     } else if let (Ok(last_model), Ok(current_model)) = (
                         serde_json::from_str::<ModelConfig>(last_json),
                         serde_json::from_str::<ModelConfig>(current_json),
-                    ) && last_model.has_changed(&current_model)
-                    {
+                    ) {
+                        if last_model.has_changed(&current_model) {
+                            config_changed_nodes.push(node_name.clone());
+                        }
+                    } else if last_json != current_json {
                         config_changed_nodes.push(node_name.clone());
                     }
                 }
@@ -120,21 +108,70 @@ pub async fn detect_changes(
     }
 }
 
-pub async fn save_graph(project_dir: &Path, current_graph: &graph::Graph) -> Result<()> {
-    let mut metadata = Metadata::load(project_dir).await.unwrap_or_default();
+fn node_config_json(config: &Config, node_name: &str) -> Result<Option<String>> {
+    if let Some(adapter_config) = config.adapters.get(node_name) {
+        Ok(Some(serde_json::to_string(adapter_config)?))
+    } else if let Some(model_config) = config.models.get(node_name) {
+        Ok(Some(serde_json::to_string(model_config)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The nodes a run has to build, along with the ones among them that only need to run when
+/// an incremental adapter upstream imports new data. Incremental adapters always run.
+pub async fn affected_subgraph(
+    project_dir: &Path,
+    current_graph: &graph::Graph,
+    config: &Config,
+) -> Result<(graph::Graph, HashSet<String>)> {
+    let metadata = Metadata::load(project_dir).await?;
+
+    let mut affected: Vec<String> = match detect_changes(project_dir, current_graph, config).await?
+    {
+        Some(changes) => calculate_affected_nodes(current_graph, &changes),
+        None => Vec::new(),
+    };
+    let mut incremental = Vec::new();
 
-    let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
     for node in &current_graph.nodes {
-        dependencies.entry(node.name.clone()).or_default();
-    }
-    for edge in &current_graph.edges {
-        dependencies
-            .entry(edge.to.clone())
-            .or_default()
-            .push(edge.from.clone());
+        let never_built = metadata
+            .get_node(&node.name)
+            .and_then(|n| n.last_updated_at)
+            .is_none();
+        if never_built {
+            affected.push(node.name.clone());
+        } else if config
+            .adapters
+            .get(&node.name)
+            .is_some_and(|adapter| adapter.mode == ImportMode::Incremental)
+        {
+            incremental.push(node.name.clone());
+        }
     }
 
-    metadata.set_dependencies(dependencies);
+    let required: HashSet<String> =
+        crate::pipeline::build::create_subgraph(current_graph, &affected)
+            .nodes
+            .into_iter()
+            .map(|node| node.name)
+            .collect();
+    affected.extend(incremental.iter().cloned());
+    let subgraph = crate::pipeline::build::create_subgraph(current_graph, &affected);
+    let conditional = subgraph
+        .nodes
+        .iter()
+        .map(|node| &node.name)
+        .filter(|name| !required.contains(*name) && !incremental.contains(name))
+        .cloned()
+        .collect();
+
+    Ok((subgraph, conditional))
+}
+
+pub async fn save_graph(project_dir: &Path, current_graph: &graph::Graph) -> Result<()> {
+    let mut metadata = Metadata::load(project_dir).await?;
+    metadata.set_graph(current_graph);
     metadata.save(project_dir).await?;
 
     Ok(())
@@ -151,52 +188,84 @@ pub async fn update_node_timestamp(
     Ok(())
 }
 
-pub async fn get_oldest_dependency_timestamp(
+pub async fn update_node_config(
     project_dir: &Path,
     table_name: &str,
-    graph: &Graph,
-) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
-    let metadata = Metadata::load(project_dir).await?;
-
-    let dependencies: Vec<String> = graph
-        .edges
-        .iter()
-        .filter(|edge| edge.to == table_name)
-        .map(|edge| edge.from.clone())
-        .collect();
+    config: &Config,
+) -> Result<()> {
+    let Some(config_json) = node_config_json(config, table_name)? else {
+        return Ok(());
+    };
 
-    let oldest = metadata.get_oldest_dependency_timestamp(&dependencies);
-    Ok(oldest)
+    let mut metadata = Metadata::load(project_dir).await?;
+    metadata.update_node_config(table_name, config_json);
+    metadata.save(project_dir).await?;
+    Ok(())
 }
 
-pub fn calculate_affected_nodes(
-    graph: &graph::Graph,
-    changes: &graph::GraphChanges,
-) -> Vec<String> {
-    let mut affected = HashSet::new();
+/// Whether `model_config` differs from the config the model was last built with.
+pub async fn model_config_changed(
+    project_dir: &Path,
+    table_name: &str,
+    model_config: &ModelConfig,
+) -> Result<bool> {
+    let metadata = Metadata::load(project_dir).await?;
+    let Some(last_json) = metadata
+        .get_node(table_name)
+        .and_then(|node| node.config.as_ref())
+    else {
+        return Ok(false);
+    };
 
-    affected.extend(changes.added_nodes.iter().cloned());
-    affected.extend(changes.config_changed_nodes.iter().cloned());
+    let last_model: ModelConfig = serde_json::from_str(last_json)?;
+    Ok(last_model.has_changed(model_config))
+}
 
-    for (_, to) in &changes.added_edges {
-        affected.insert(to.clone());
-    }
-    for (_, to) in &changes.removed_edges {
-        affected.insert(to.clone());
-    }
+pub async fn clear_node_timestamp(project_dir: &Path, table_name: &str) -> Result<()> {
+    let mut metadata = Metadata::load(project_dir).await?;
+    metadata.clear_node_timestamp(table_name);
+    metadata.save(project_dir).await?;
+    Ok(())
+}
 
-    let mut queue: Vec<String> = affected.iter().cloned().collect();
+pub async fn get_import_state(project_dir: &Path, table_name: &str) -> Result<ImportState> {
+    let metadata = Metadata::load(project_dir).await?;
+    Ok(metadata
+        .get_node(table_name)
+        .map(|node| node.import_state.clone())
+        .unwrap_or_default())
+}
 
-    while let Some(node) = queue.pop() {
-        for edge in &graph.edges {
-            if edge.from == node && !affected.contains(&edge.to) {
-                affected.insert(edge.to.clone());
-                queue.push(edge.to.clone());
-            }
-        }
-    }
+pub async fn update_import_state(
+    project_dir: &Path,
+    table_name: &str,
+    import_state: ImportState,
+) -> Result<()> {
+    let mut metadata = Metadata::load(project_dir).await?;
+    metadata.update_import_state(table_name, import_state);
+    metadata.save(project_dir).await?;
+    Ok(())
+}
 
-    affected.into_iter().collect()
+pub async fn record_appended_files(
+    project_dir: &Path,
+    table_name: &str,
+    import_state: &ImportState,
+) -> Result<()> {
+    let mut metadata = Metadata::load(project_dir).await?;
+    metadata.record_appended_files(table_name, import_state);
+    metadata.save(project_dir).await?;
+    Ok(())
+}
+
+pub async fn get_oldest_dependency_timestamp(
+    project_dir: &Path,
+    table_name: &str,
+    graph: &Graph,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let metadata = Metadata::load(project_dir).await?;
+    let oldest = metadata.get_oldest_dependency_timestamp(&graph.dependencies(table_name));
+    Ok(oldest)
 }
 
 pub async fn save_graph_with_changes(
@@ -210,6 +279,7 @@ pub async fn save_graph_with_changes(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::model::Materialization;
     use crate::dependency::graph::Node;
     use std::collections::HashMap;
     use tempfile;
@@ -230,12 +300,15 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters: HashMap::new(),
             models: HashMap::new(),
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         }
     }
 
@@ -284,4 +357,123 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_detect_changes_model_sql_changed() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path();
+
+        let graph = graph::Graph {
+            nodes: vec![Node {
+                name: "user_stats".to_string(),
+            }],
+            edges: vec![],
+        };
+
+        let mut config = create_test_config();
+        config.models.insert(
+            "user_stats".to_string(),
+            ModelConfig {
+                description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
+                sql: "SELECT 1".to_string(),
+            },
+        );
+
+        save_graph(project_dir, &graph).await?;
+        update_node_config(project_dir, "user_stats", &config).await?;
+        assert!(
+            detect_changes(project_dir, &graph, &config)
+                .await?
+                .is_none()
+        );
+
+        assert!(
+            !model_config_changed(project_dir, "user_stats", &config.models["user_stats"]).await?
+        );
+
+        config.models.get_mut("user_stats").unwrap().sql = "SELECT 2".to_string();
+        let changes = detect_changes(project_dir, &graph, &config).await?.unwrap();
+        assert_eq!(changes.config_changed_nodes, vec!["user_stats"]);
+        assert!(
+            model_config_changed(project_dir, "user_stats", &config.models["user_stats"]).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_affected_subgraph_skips_unchanged_nodes() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path();
+
+        let graph = graph::Graph {
+            nodes: vec![
+                Node {
+                    name: "users".to_string(),
+                },
+                Node {
+                    name: "orders".to_string(),
+                },
+                Node {
+                    name: "user_stats".to_string(),
+                },
+            ],
+            edges: vec![graph::Edge {
+                from: "users".to_string(),
+                to: "user_stats".to_string(),
+            }],
+        };
+
+        let config = create_test_config();
+        save_graph(project_dir, &graph).await?;
+
+        let (subgraph, conditional) = affected_subgraph(project_dir, &graph, &config).await?;
+        assert_eq!(subgraph.nodes.len(), 3);
+        assert!(conditional.is_empty());
+
+        for table in ["users", "orders", "user_stats"] {
+            update_node_timestamp(project_dir, table, chrono::Utc::now()).await?;
+        }
+        save_graph(project_dir, &graph).await?;
+
+        let (subgraph, _) = affected_subgraph(project_dir, &graph, &config).await?;
+        assert!(subgraph.nodes.is_empty());
+
+        clear_node_timestamp(project_dir, "users").await?;
+        let (subgraph, conditional) = affected_subgraph(project_dir, &graph, &config).await?;
+        let mut names: Vec<&str> = subgraph.nodes.iter().map(|n| n.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["user_stats", "users"]);
+        assert!(conditional.is_empty());
+
+        update_node_timestamp(project_dir, "users", chrono::Utc::now()).await?;
+        let mut config = config;
+        config.adapters.insert(
+            "users".to_string(),
+            crate::config::adapter::parse_adapter_config(
+                r#"
+                connection: data
+                mode: incremental
+                source:
+                  type: file
+                  file:
+                    path: users/*.csv
+                  format:
+                    type: csv
+                columns: []
+                "#,
+            )?,
+        );
+        update_node_config(project_dir, "users", &config).await?;
+        let (subgraph, conditional) = affected_subgraph(project_dir, &graph, &config).await?;
+        let mut names: Vec<&str> = subgraph.nodes.iter().map(|n| n.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["user_stats", "users"]);
+        assert_eq!(conditional, HashSet::from(["user_stats".to_string()]));
+
+        Ok(())
+    }
 }
@@ -1,8 +1,8 @@
-use crate::config::Config;
-use sqlparser::{
-    ast::{Statement, TableFactor},
-    dialect::DuckDbDialect,
-    parser::Parser,
+use super::references::dependent_tables;
+use crate::config::{
+    Config,
+    data_test::{DataCheck, DataTestConfig},
+    model::ModelConfig,
 };
 use std::{
     collections::{HashMap, HashSet, VecDeque},
@@ -26,7 +26,7 @@ pub struct Graph {
     pub edges: Vec<Edge>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum GraphError {
     CircularDependency {
         nodes: Vec<String>,
@@ -161,113 +161,113 @@ pub fn build_adjacency_map(graph: &Graph) -> HashMap<String, Vec<String>> {
 }
 
 impl Graph {
-    fn validate_circular_dependencies(&self) -> Result<(), GraphError> {
-        let mut in_degree: HashMap<String, usize> = HashMap::new();
-        let mut adjacency_map: HashMap<String, Vec<String>> = HashMap::new();
+    /// Tables `name` reads from directly.
+    pub fn dependencies(&self, name: &str) -> Vec<String> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.to == name)
+            .map(|edge| edge.from.clone())
+            .collect()
+    }
 
-        for node in &self.nodes {
-            in_degree.insert(node.name.clone(), 0);
-            adjacency_map.insert(node.name.clone(), Vec::new());
-        }
+    /// Every node `name` depends on, directly or through other nodes.
+    pub fn upstream(&self, name: &str) -> Vec<String> {
+        self.walk(name, |edge| (&edge.to, &edge.from))
+    }
 
-        for edge in &self.edges {
-            *in_degree.entry(edge.to.clone()).or_insert(0) += 1;
-            adjacency_map
-                .entry(edge.from.clone())
-                .or_default()
-                .push(edge.to.clone());
-        }
+    /// Every node that depends on `name`, directly or through other nodes.
+    pub fn downstream(&self, name: &str) -> Vec<String> {
+        self.walk(name, |edge| (&edge.from, &edge.to))
+    }
 
-        let mut queue: VecDeque<String> = VecDeque::new();
-        for (node, &degree) in &in_degree {
-            if degree == 0 {
-                queue.push_back(node.clone());
-            }
-        }
+    fn walk(&self, name: &str, direction: fn(&Edge) -> (&String, &String)) -> Vec<String> {
+        let mut visited = HashSet::new();
+        let mut found = Vec::new();
+        let mut queue = VecDeque::from([name.to_string()]);
 
-        let mut sorted_count = 0;
         while let Some(current) = queue.pop_front() {
-            sorted_count += 1;
-
-            if let Some(neighbors) = adjacency_map.get(&current) {
-                for neighbor in neighbors {
-                    if let Some(degree) = in_degree.get_mut(neighbor) {
-                        *degree -= 1;
-                        if *degree == 0 {
-                            queue.push_back(neighbor.clone());
-                        }
-                    }
+            for edge in &self.edges {
+                let (source, target) = direction(edge);
+                if *source == current && target != name && visited.insert(target.clone()) {
+                    found.push(target.clone());
+                    queue.push_back(target.clone());
                 }
             }
         }
 
-        if sorted_count != self.nodes.len() {
-            let remaining_nodes: Vec<String> = in_degree
-                .into_iter()
-                .filter(|(_, degree)| *degree > 0)
-                .map(|(node, _)| node)
-                .collect();
-
-            return Err(GraphError::CircularDependency {
-                nodes: remaining_nodes,
-            });
-        }
-
-        Ok(())
+        found
     }
 
-    fn validate_table_references(&self, config: &Config) -> Result<(), GraphError> {
-        let mut all_table_names = HashSet::new();
+    /// Every cycle in the graph as a path that starts and ends at the same node, e.g.
+    /// `["a", "b", "a"]`. Each cycle is reported once, from the first node that reaches it.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let adjacency = build_adjacency_map(self);
+        let mut cycles = Vec::new();
+        let mut path = Vec::new();
+        let mut finished = HashSet::new();
 
-        for adapter_name in config.adapters.keys() {
-            all_table_names.insert(adapter_name.clone());
-        }
-
-        for model_name in config.models.keys() {
-            all_table_names.insert(model_name.clone());
+        for node in &self.nodes {
+            collect_cycles(
+                &node.name,
+                &adjacency,
+                &mut path,
+                &mut finished,
+                &mut cycles,
+            );
         }
 
-        for (model_name, model_config) in &config.models {
-            let dependent_tables =
-                dependent_tables(&model_config.sql).map_err(|e| GraphError::SqlParseError {
-                    model_name: model_name.clone(),
-                    error: e,
-                })?;
+        cycles
+    }
 
-            for table in dependent_tables {
-                if !all_table_names.contains(&table) {
-                    return Err(GraphError::NonExistentTableReference {
-                        model_name: model_name.clone(),
-                        table_name: table,
-                    });
-                }
-            }
+    pub fn from_config(config: &Config) -> Result<Self, GraphError> {
+        let (graph, errors) = Self::build(config);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(graph),
         }
+    }
 
-        Ok(())
+    /// Every problem that keeps the config from forming a valid graph, instead of only the
+    /// first one as `from_config` reports.
+    pub fn validate(config: &Config) -> Vec<GraphError> {
+        Self::build(config).1
     }
 
-    pub fn from_config(config: &Config) -> Result<Self, GraphError> {
+    fn build(config: &Config) -> (Self, Vec<GraphError>) {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
+        let mut errors = Vec::new();
 
-        for adapter_name in config.adapters.keys() {
+        let mut adapter_names: Vec<&String> = config.adapters.keys().collect();
+        adapter_names.sort();
+        for adapter_name in adapter_names {
             nodes.push(Node {
                 name: adapter_name.to_string(),
             });
         }
 
-        for (model_name, model_config) in &config.models {
+        let mut models: Vec<(&String, &ModelConfig)> = config.models.iter().collect();
+        models.sort_by_key(|(model_name, _)| *model_name);
+        for (model_name, model_config) in models {
             nodes.push(Node {
                 name: model_name.to_string(),
             });
 
-            let dependent_tables =
-                dependent_tables(&model_config.sql).map_err(|e| GraphError::SqlParseError {
-                    model_name: model_name.clone(),
-                    error: e,
-                })?;
+            let dependent_tables = match model_dependencies(config, model_name, model_config) {
+                Ok(dependent_tables) => dependent_tables,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
             for table in dependent_tables {
+                if !config.adapters.contains_key(&table) && !config.models.contains_key(&table) {
+                    errors.push(GraphError::NonExistentTableReference {
+                        model_name: model_name.to_string(),
+                        table_name: table,
+                    });
+                    continue;
+                }
                 edges.push(Edge {
                     from: table,
                     to: model_name.to_string(),
@@ -275,63 +275,122 @@ impl Graph {
             }
         }
 
-        let graph = Self { nodes, edges };
+        // A `relationships` test reads its parent table, so the parent has to be built first.
+        let mut tested: Vec<(&String, &Vec<DataTestConfig>)> = config
+            .adapters
+            .iter()
+            .map(|(name, adapter)| (name, &adapter.tests))
+            .chain(
+                config
+                    .models
+                    .iter()
+                    .map(|(name, model)| (name, &model.tests)),
+            )
+            .collect();
+        tested.sort_by_key(|(name, _)| *name);
+        for (name, tests) in tested {
+            for test in tests {
+                let DataCheck::Relationships { to, .. } = &test.check else {
+                    continue;
+                };
+                let edge = Edge {
+                    from: to.to_string(),
+                    to: name.to_string(),
+                };
+                if to == name || edges.contains(&edge) {
+                    continue;
+                }
+                if !config.adapters.contains_key(to) && !config.models.contains_key(to) {
+                    errors.push(GraphError::NonExistentTableReference {
+                        model_name: name.to_string(),
+                        table_name: to.to_string(),
+                    });
+                    continue;
+                }
+                edges.push(edge);
+            }
+        }
 
-        graph.validate_table_references(config)?;
-        graph.validate_circular_dependencies()?;
+        let graph = Self { nodes, edges };
+        errors.extend(
+            graph
+                .cycles()
+                .into_iter()
+                .map(|nodes| GraphError::CircularDependency { nodes }),
+        );
 
-        Ok(graph)
+        (graph, errors)
     }
 }
 
-pub fn dependent_tables(sql: &str) -> Result<Vec<String>, String> {
-    let dialect = DuckDbDialect;
-    let ast = match Parser::parse_sql(&dialect, sql) {
-        Ok(ast) => ast,
-        Err(e) => return Err(e.to_string()),
-    };
-
-    if let Some(Statement::Query(query)) = ast.first()
-        && let sqlparser::ast::SetExpr::Select(select) = query.body.as_ref()
-    {
-        let mut tables = Vec::new();
-
-        for table in &select.from {
-            collect_table_names(&table.relation, &mut tables);
-
-            for join in &table.joins {
-                collect_table_names(&join.relation, &mut tables);
-            }
-        }
-
-        return Ok(tables);
+fn collect_cycles<'a>(
+    node: &'a str,
+    adjacency: &'a HashMap<String, Vec<String>>,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if finished.contains(node) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|visited| *visited == node) {
+        let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+        cycle.push(node.to_string());
+        cycles.push(cycle);
+        return;
     }
 
-    Ok(vec![])
+    path.push(node);
+    for next in adjacency.get(node).into_iter().flatten() {
+        collect_cycles(next, adjacency, path, finished, cycles);
+    }
+    path.pop();
+    finished.insert(node);
 }
 
-fn collect_table_names(table_factor: &TableFactor, tables: &mut Vec<String>) {
-    match table_factor {
-        TableFactor::Table { name, .. } => {
-            tables.push(name.to_string());
-        }
-        TableFactor::NestedJoin {
-            table_with_joins, ..
-        } => {
-            collect_table_names(&table_with_joins.relation, tables);
-            for join in &table_with_joins.joins {
-                collect_table_names(&join.relation, tables);
+/// Tables a model reads from. DuckDB resolves identifiers case-insensitively, so each
+/// table is matched to the adapter or model name it refers to, e.g. `FROM Users` to `users`.
+/// The SQL is rendered both as a full build and as an incremental run, so tables read only
+/// inside `{% if is_incremental() %}` blocks are dependencies too. An incremental run may
+/// read the model's own table, so that reference is not a dependency.
+pub fn model_dependencies(
+    config: &Config,
+    model_name: &str,
+    model_config: &ModelConfig,
+) -> Result<Vec<String>, GraphError> {
+    let sql_parse_error = |error: String| GraphError::SqlParseError {
+        model_name: model_name.to_string(),
+        error,
+    };
+
+    let mut dependencies: Vec<String> = Vec::new();
+    for is_incremental in [false, true] {
+        let sql = model_config
+            .render_sql(model_name, is_incremental)
+            .map_err(|e| sql_parse_error(e.to_string()))?;
+
+        for table in dependent_tables(&sql).map_err(sql_parse_error)? {
+            let table = config
+                .adapters
+                .keys()
+                .chain(config.models.keys())
+                .find(|name| name.eq_ignore_ascii_case(&table))
+                .cloned()
+                .unwrap_or(table);
+            let own_table = is_incremental && table.eq_ignore_ascii_case(model_name);
+            if !own_table && !dependencies.contains(&table) {
+                dependencies.push(table);
             }
         }
-        _ => {}
     }
+    Ok(dependencies)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{
-        model::ModelConfig,
+        model::{Materialization, ModelConfig},
         project::{ProjectConfig, StorageConfig},
     };
     use std::collections::HashMap;
@@ -343,12 +402,16 @@ mod tests {
             "raw_users".to_string(),
             crate::config::adapter::AdapterConfig {
                 connection: "default".to_string(),
+                mode: crate::config::adapter::ImportMode::Full,
+                tests: vec![],
                 description: None,
                 source: crate::config::adapter::AdapterSource::File {
                     file: crate::config::adapter::FileConfig {
                         path: "/tmp/raw_users.csv".to_string(),
                         compression: None,
                         max_batch_size: None,
+                        lookback_days: None,
+                        partition_column: None,
                     },
                     format: crate::config::adapter::FormatConfig {
                         ty: "csv".to_string(),
@@ -365,12 +428,23 @@ mod tests {
             "order_items".to_string(),
             crate::config::adapter::AdapterConfig {
                 connection: "default".to_string(),
+                mode: crate::config::adapter::ImportMode::Full,
+                tests: vec![DataTestConfig {
+                    check: DataCheck::Relationships {
+                        column: "user_id".to_string(),
+                        to: "users".to_string(),
+                        field: "id".to_string(),
+                    },
+                    severity: crate::config::data_test::Severity::Error,
+                }],
                 description: None,
                 source: crate::config::adapter::AdapterSource::File {
                     file: crate::config::adapter::FileConfig {
                         path: "/tmp/order_items.csv".to_string(),
                         compression: None,
                         max_batch_size: None,
+                        lookback_days: None,
+                        partition_column: None,
                     },
                     format: crate::config::adapter::FormatConfig {
                         ty: "csv".to_string(),
@@ -389,7 +463,12 @@ mod tests {
             "users".to_string(),
             ModelConfig {
                 description: None,
-                sql: "SELECT * FROM raw_users WHERE active = true".to_string(),
+                materialization: Materialization::IncrementalAppend,
+                unique_key: vec![],
+                tests: vec![],
+                sql: "SELECT * FROM raw_users WHERE active = true \
+                      {% if is_incremental() %}AND id > (SELECT MAX(id) FROM {{ this }}){% endif %}"
+                    .to_string(),
             },
         );
 
@@ -397,6 +476,9 @@ mod tests {
             "orders".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT o.*, u.name FROM order_items o JOIN users u ON o.user_id = u.id"
                     .to_string(),
             },
@@ -417,12 +499,15 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters,
             models,
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         };
 
         let graph = Graph::from_config(&config).unwrap();
@@ -434,12 +519,50 @@ mod tests {
         assert!(node_names.contains(&"users"));
         assert!(node_names.contains(&"orders"));
 
-        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.edges.len(), 4);
         let edge_exists =
             |from: &str, to: &str| graph.edges.iter().any(|e| e.from == from && e.to == to);
         assert!(edge_exists("raw_users", "users"));
         assert!(edge_exists("order_items", "orders"));
         assert!(edge_exists("users", "orders"));
+        assert!(edge_exists("users", "order_items"));
+    }
+
+    #[test]
+    fn test_incremental_branch_dependencies() {
+        let adapter = |path: &str| {
+            crate::config::adapter::parse_adapter_config(&format!(
+                "connection: default\ncolumns: []\nsource:\n  type: file\n  file:\n    path: {path}\n  format:\n    type: csv"
+            ))
+            .unwrap()
+        };
+        let mut config = Config::new(std::path::PathBuf::new());
+        config
+            .adapters
+            .insert("events".to_string(), adapter("/tmp/events.csv"));
+        config
+            .adapters
+            .insert("late_events".to_string(), adapter("/tmp/late_events.csv"));
+        config.models.insert(
+            "all_events".to_string(),
+            ModelConfig {
+                description: None,
+                materialization: Materialization::IncrementalAppend,
+                unique_key: vec![],
+                tests: vec![],
+                sql: "SELECT e.* FROM events e \
+                      {% if is_incremental() %}\
+                      JOIN late_events l ON e.id = l.id \
+                      WHERE e.id > (SELECT MAX(id) FROM {{ this }})\
+                      {% endif %}"
+                    .to_string(),
+            },
+        );
+
+        let graph = Graph::from_config(&config).unwrap();
+        let mut upstream = graph.dependencies("all_events");
+        upstream.sort();
+        assert_eq!(upstream, vec!["events", "late_events"]);
     }
 
     #[test]
@@ -451,6 +574,9 @@ mod tests {
             "model_a".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM model_b".to_string(),
             },
         );
@@ -459,6 +585,9 @@ mod tests {
             "model_b".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM model_c".to_string(),
             },
         );
@@ -467,6 +596,9 @@ mod tests {
             "model_c".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM model_a".to_string(),
             },
         );
@@ -475,6 +607,9 @@ mod tests {
             "self_reference_model".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM self_reference_model WHERE id > 10".to_string(),
             },
         );
@@ -494,25 +629,112 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters,
             models,
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         };
 
-        let result = Graph::from_config(&config);
-        assert!(result.is_err());
-        if let Err(GraphError::CircularDependency { nodes }) = result {
-            assert_eq!(nodes.len(), 4);
-            assert!(nodes.contains(&"model_a".to_string()));
-            assert!(nodes.contains(&"model_b".to_string()));
-            assert!(nodes.contains(&"model_c".to_string()));
-            assert!(nodes.contains(&"self_reference_model".to_string()));
-        } else {
-            panic!("Expected CircularDependency error");
-        }
+        // Cycles follow the edges, from each table to the models reading it.
+        let cycle = |nodes: &[&str]| GraphError::CircularDependency {
+            nodes: nodes.iter().map(|node| node.to_string()).collect(),
+        };
+        assert_eq!(
+            Graph::from_config(&config),
+            Err(cycle(&["model_a", "model_c", "model_b", "model_a"]))
+        );
+        assert_eq!(
+            Graph::validate(&config),
+            vec![
+                cycle(&["model_a", "model_c", "model_b", "model_a"]),
+                cycle(&["self_reference_model", "self_reference_model"]),
+            ]
+        );
+        assert_eq!(
+            GraphError::CircularDependency {
+                nodes: vec![
+                    "model_a".to_string(),
+                    "model_b".to_string(),
+                    "model_a".to_string()
+                ]
+            }
+            .to_string(),
+            "Circular dependency detected involving nodes: model_a -> model_b -> model_a"
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let model = |sql: &str| ModelConfig {
+            description: None,
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
+            sql: sql.to_string(),
+        };
+        let models = HashMap::from([
+            ("broken".to_string(), model("SELECT (1 FROM users")),
+            (
+                "orders".to_string(),
+                model("SELECT * FROM Users JOIN missing ON true"),
+            ),
+            ("users".to_string(), model("SELECT * FROM orders")),
+        ]);
+
+        let config = Config {
+            project: ProjectConfig {
+                storage: StorageConfig::LocalFile {
+                    path: "/tmp".to_string(),
+                },
+                database: crate::config::project::DatabaseConfig {
+                    ty: crate::config::project::DatabaseType::Sqlite,
+                    path: Some("/tmp/test.db".to_string()),
+                    host: None,
+                    port: None,
+                    database: None,
+                    password: None,
+                    username: None,
+                },
+                connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
+            },
+            adapters: HashMap::new(),
+            models,
+            queries: HashMap::new(),
+            dashboards: HashMap::new(),
+            project_dir: std::path::PathBuf::new(),
+        };
+
+        let errors = Graph::validate(&config);
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(
+            &errors[0],
+            GraphError::SqlParseError { model_name, .. } if model_name == "broken"
+        ));
+        assert_eq!(
+            errors[1],
+            GraphError::NonExistentTableReference {
+                model_name: "orders".to_string(),
+                table_name: "missing".to_string(),
+            }
+        );
+        assert_eq!(
+            errors[2],
+            GraphError::CircularDependency {
+                nodes: vec![
+                    "orders".to_string(),
+                    "users".to_string(),
+                    "orders".to_string()
+                ]
+            }
+        );
     }
 
     #[test]
@@ -524,6 +746,9 @@ mod tests {
             "model_a".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM non_existent_model".to_string(),
             },
         );
@@ -543,12 +768,15 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters,
             models,
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         };
 
         let result = Graph::from_config(&config);
@@ -568,12 +796,16 @@ mod tests {
             "existing_adapter".to_string(),
             crate::config::adapter::AdapterConfig {
                 connection: "default".to_string(),
+                mode: crate::config::adapter::ImportMode::Full,
+                tests: vec![],
                 description: None,
                 source: crate::config::adapter::AdapterSource::File {
                     file: crate::config::adapter::FileConfig {
                         path: "/tmp/data.csv".to_string(),
                         compression: None,
                         max_batch_size: None,
+                        lookback_days: None,
+                        partition_column: None,
                     },
                     format: crate::config::adapter::FormatConfig {
                         ty: "csv".to_string(),
@@ -591,6 +823,9 @@ mod tests {
             "model_a".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "SELECT * FROM non_existent_adapter".to_string(),
             },
         );
@@ -610,12 +845,15 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters,
             models,
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         };
 
         let result = Graph::from_config(&config);
@@ -637,6 +875,9 @@ mod tests {
             "invalid_model".to_string(),
             ModelConfig {
                 description: None,
+                materialization: Materialization::Table,
+                unique_key: vec![],
+                tests: vec![],
                 sql: "INVALID SQL SYNTAX HERE".to_string(),
             },
         );
@@ -656,12 +897,15 @@ mod tests {
                     username: None,
                 },
                 connections: HashMap::new(),
+                history: None,
+                api: None,
+                engine: None,
             },
             adapters,
             models,
             queries: HashMap::new(),
             dashboards: HashMap::new(),
-            project_dir: todo!(),
+            project_dir: std::path::PathBuf::new(),
         };
 
         let result = Graph::from_config(&config);
@@ -0,0 +1,259 @@
+use sqlparser::{
+    ast::{ObjectName, Query, TableFactor, Visit, Visitor},
+    dialect::DuckDbDialect,
+    parser::Parser,
+};
+use std::collections::{HashSet, VecDeque};
+use std::ops::ControlFlow;
+
+/// The DuckLake catalog and its default schema, which pipeline tables live in.
+const DUCKLAKE_CATALOG: &str = "db";
+const DEFAULT_SCHEMA: &str = "main";
+
+/// What a query reads from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SqlReferences {
+    /// Pipeline tables, with `db.main.` and `main.` prefixes removed.
+    pub tables: Vec<String>,
+    /// Tables in other catalogs or schemas, and files scanned directly, e.g. `FROM 'data.csv'`.
+    pub external_tables: Vec<String>,
+    /// Table functions such as `read_parquet`, in lowercase.
+    pub table_functions: Vec<String>,
+}
+
+/// Tables the SQL reads from, anywhere in the statement: joins, subqueries in
+/// expressions, set operations, CTE bodies and lateral joins. CTE names are not tables.
+pub fn dependent_tables(sql: &str) -> Result<Vec<String>, String> {
+    sql_references(sql).map(|references| references.tables)
+}
+
+pub fn sql_references(sql: &str) -> Result<SqlReferences, String> {
+    let statements = Parser::parse_sql(&DuckDbDialect, sql).map_err(|e| e.to_string())?;
+
+    let mut collector = ReferenceCollector::default();
+    let _ = statements.visit(&mut collector);
+    Ok(collector.references)
+}
+
+/// The CTE names visible inside one query. Non-recursive CTEs only become visible once
+/// their own body has been visited, so `WITH users AS (SELECT * FROM users)` still
+/// reads the `users` table.
+#[derive(Default)]
+struct CteScope {
+    visible: HashSet<String>,
+    pending: VecDeque<String>,
+}
+
+#[derive(Default)]
+struct ReferenceCollector {
+    scopes: Vec<CteScope>,
+    references: SqlReferences,
+}
+
+impl ReferenceCollector {
+    fn is_cte(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.scopes
+            .iter()
+            .any(|scope| scope.visible.contains(&name))
+    }
+
+    fn add_relation(&mut self, name: &ObjectName) {
+        let parts: Vec<&str> = name
+            .0
+            .iter()
+            .filter_map(|part| part.as_ident())
+            .map(|ident| ident.value.as_str())
+            .collect();
+        let table = match parts.as_slice() {
+            [table] if self.is_cte(table) => return,
+            [table] if is_file_path(table) => None,
+            [table] => Some(*table),
+            [prefix, table]
+                if prefix.eq_ignore_ascii_case(DUCKLAKE_CATALOG)
+                    || prefix.eq_ignore_ascii_case(DEFAULT_SCHEMA) =>
+            {
+                Some(*table)
+            }
+            [catalog, schema, table]
+                if catalog.eq_ignore_ascii_case(DUCKLAKE_CATALOG)
+                    && schema.eq_ignore_ascii_case(DEFAULT_SCHEMA) =>
+            {
+                Some(*table)
+            }
+            _ => None,
+        };
+
+        match table {
+            Some(table) => push_unique(&mut self.references.tables, table.to_string()),
+            None => push_unique(&mut self.references.external_tables, name.to_string()),
+        }
+    }
+
+    fn add_table_function(&mut self, name: &ObjectName) {
+        let function = name
+            .0
+            .last()
+            .and_then(|part| part.as_ident())
+            .map(|ident| ident.value.to_lowercase())
+            .unwrap_or_else(|| name.to_string());
+        push_unique(&mut self.references.table_functions, function);
+    }
+}
+
+impl Visitor for ReferenceCollector {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        let mut scope = CteScope::default();
+        if let Some(with) = &query.with {
+            let names = with
+                .cte_tables
+                .iter()
+                .map(|cte| cte.alias.name.value.to_lowercase());
+            if with.recursive {
+                scope.visible.extend(names);
+            } else {
+                scope.pending.extend(names);
+            }
+        }
+        self.scopes.push(scope);
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_query(&mut self, _query: &Query) -> ControlFlow<()> {
+        self.scopes.pop();
+
+        // CTE bodies are the first queries visited inside their parent, in order, so a
+        // finished query with pending CTEs in the parent is the next CTE's body.
+        if let Some(parent) = self.scopes.last_mut()
+            && let Some(name) = parent.pending.pop_front()
+        {
+            parent.visible.insert(name);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        match table_factor {
+            TableFactor::Table {
+                name, args: None, ..
+            } => self.add_relation(name),
+            TableFactor::Table { name, .. } | TableFactor::Function { name, .. } => {
+                self.add_table_function(name)
+            }
+            TableFactor::TableFunction { expr, .. } => {
+                push_unique(&mut self.references.table_functions, expr.to_string())
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// DuckDB scans a file named directly in `FROM`, e.g. `FROM 'data/users.csv'`.
+fn is_file_path(name: &str) -> bool {
+    name.contains('/') || name.contains('.')
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(sql: &str) -> Vec<String> {
+        dependent_tables(sql).unwrap()
+    }
+
+    #[test]
+    fn test_joins_and_subqueries() {
+        assert_eq!(
+            tables("SELECT * FROM users u JOIN orders o ON u.id = o.user_id JOIN users x ON true"),
+            vec!["users", "orders"]
+        );
+        assert_eq!(
+            tables(
+                "SELECT *, (SELECT MAX(amount) FROM payments) AS top FROM users \
+                 WHERE id IN (SELECT user_id FROM orders) \
+                 AND EXISTS (SELECT 1 FROM refunds r WHERE r.user_id = users.id)"
+            ),
+            vec!["payments", "users", "orders", "refunds"]
+        );
+        assert_eq!(
+            tables("SELECT id FROM users UNION ALL SELECT id FROM (SELECT id FROM admins) a"),
+            vec!["users", "admins"]
+        );
+        assert_eq!(
+            tables(
+                "SELECT * FROM users u, LATERAL (SELECT * FROM orders o WHERE o.user_id = u.id) t"
+            ),
+            vec!["users", "orders"]
+        );
+    }
+
+    #[test]
+    fn test_cte_scopes() {
+        assert_eq!(
+            tables(
+                "WITH recent AS (SELECT * FROM orders), \
+                 totals AS (SELECT user_id, SUM(amount) FROM recent GROUP BY user_id) \
+                 SELECT * FROM totals JOIN users ON users.id = totals.user_id"
+            ),
+            vec!["orders", "users"]
+        );
+
+        // A CTE can shadow a table, but not inside its own body.
+        assert_eq!(
+            tables("WITH users AS (SELECT * FROM users WHERE active) SELECT * FROM users"),
+            vec!["users"]
+        );
+
+        // CTE names only apply inside the query that defines them.
+        assert_eq!(
+            tables(
+                "SELECT * FROM (WITH t AS (SELECT 1 AS id) SELECT * FROM t) a \
+                 JOIN t ON a.id = t.id"
+            ),
+            vec!["t"]
+        );
+
+        assert_eq!(
+            tables(
+                "WITH RECURSIVE tree AS (SELECT id, parent_id FROM categories \
+                 UNION ALL SELECT c.id, c.parent_id FROM categories c JOIN tree ON c.parent_id = tree.id) \
+                 SELECT * FROM tree"
+            ),
+            vec!["categories"]
+        );
+    }
+
+    #[test]
+    fn test_qualified_names_and_table_functions() {
+        let references = sql_references(
+            "SELECT * FROM db.main.users \
+             JOIN main.orders ON true \
+             JOIN DB.payments ON true \
+             JOIN sqlite_db.main.customers ON true \
+             JOIN read_parquet('s3://bucket/*.parquet') p ON true \
+             JOIN \"data/events.csv\" e ON true",
+        )
+        .unwrap();
+
+        assert_eq!(references.tables, vec!["users", "orders", "payments"]);
+        assert_eq!(
+            references.external_tables,
+            vec!["sqlite_db.main.customers", "\"data/events.csv\""]
+        );
+        assert_eq!(references.table_functions, vec!["read_parquet"]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(dependent_tables("SELECT (1 FROM users").is_err());
+    }
+}
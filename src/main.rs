@@ -1,21 +1,26 @@
 use anyhow::Result;
+use api::{ServerConfig, TlsConfig};
 use clap::{Parser, Subcommand};
 use commands::{
     new::{create_gitignore, create_secret_key},
     samples::create_samples,
 };
 use config::{Config, ProjectConfig};
+use pipeline::{execution::ExecutionOptions, file_processor::TimeWindow};
+use std::path::PathBuf;
 
 pub mod api;
+pub mod auth;
 pub mod commands;
 pub mod config;
-pub mod core;
 pub mod dependency;
 pub mod error_handle;
 pub mod metadata;
 pub mod pipeline;
+pub mod redact;
 pub mod s3_client;
 pub mod secret;
+pub mod sql;
 pub mod status;
 #[cfg(test)]
 pub mod test_helpers;
@@ -35,8 +40,56 @@ enum Commands {
     },
     Start {
         project_name: String,
+        /// Address to bind, e.g. 0.0.0.0 inside a container
+        #[arg(long, default_value = "localhost")]
+        host: String,
         #[arg(short, long, default_value = "3015")]
         port: u16,
+        #[arg(long, default_value = "8015")]
+        ui_port: u16,
+        /// PEM certificate chain to serve HTTPS with
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        /// PEM private key for --tls-cert
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        /// Serve the UI and the API from the API port
+        #[arg(long)]
+        single_port: bool,
+        #[arg(long)]
+        no_browser: bool,
+        /// Listen on a non-loopback host even though `api.auth` is off
+        #[arg(long)]
+        allow_unauthenticated: bool,
+    },
+    Run {
+        #[arg(default_value = ".")]
+        project_name: String,
+        #[arg(short, long)]
+        target: Option<String>,
+        #[arg(long)]
+        full_refresh: bool,
+        #[arg(long, value_parser = commands::run::parse_time_bound)]
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(long, value_parser = commands::run::parse_time_bound)]
+        to: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    /// Check every model for SQL errors, unknown tables and dependency cycles
+    Validate {
+        #[arg(default_value = ".")]
+        project_name: String,
+    },
+    Secret {
+        #[arg(default_value = ".")]
+        project_name: String,
+        #[command(subcommand)]
+        command: commands::secret::SecretCommands,
+    },
+    Token {
+        #[arg(default_value = ".")]
+        project_name: String,
+        #[command(subcommand)]
+        command: commands::token::TokenCommands,
     },
 }
 
@@ -60,20 +113,81 @@ async fn main() -> Result<()> {
             println!("  Run 'featherbox start {project_name}' to open the project");
             Ok(())
         }
-        Commands::Start { project_name, port } => {
+        Commands::Start {
+            project_name,
+            host,
+            port,
+            ui_port,
+            tls_cert,
+            tls_key,
+            single_port,
+            no_browser,
+            allow_unauthenticated,
+        } => {
             let project_dir = std::env::current_dir()?.join(project_name);
             let config = Config::new(project_dir);
+            let tls = match (tls_cert, tls_key) {
+                (Some(cert_path), Some(key_path)) => Some(TlsConfig {
+                    cert_path: cert_path.clone(),
+                    key_path: key_path.clone(),
+                }),
+                _ => None,
+            };
+            let server = ServerConfig {
+                host: host.clone(),
+                port: *port,
+                ui_port: *ui_port,
+                tls,
+                single_listener: *single_port,
+                open_browser: !no_browser,
+                allow_unauthenticated: *allow_unauthenticated,
+            };
 
             tracing_subscriber::fmt()
                 .with_max_level(tracing::Level::DEBUG)
                 .init();
 
-            commands::start::execute_start(config, *port).await
+            commands::start::execute_start(config, server).await
+        }
+        Commands::Run {
+            project_name,
+            target,
+            full_refresh,
+            from,
+            to,
+        } => {
+            let project_dir = std::env::current_dir()?.join(project_name);
+            let config = Config::new(project_dir);
+            let options = ExecutionOptions {
+                full_refresh: *full_refresh,
+                window: TimeWindow::new(*from, *to)?,
+                ..Default::default()
+            };
+
+            commands::run::execute_run(config, target.clone(), options).await
+        }
+        Commands::Validate { project_name } => {
+            let project_dir = std::env::current_dir()?.join(project_name);
+            commands::validate::execute_validate(Config::new(project_dir))
+        }
+        Commands::Secret {
+            project_name,
+            command,
+        } => {
+            let project_dir = std::env::current_dir()?.join(project_name);
+            commands::secret::execute_secret(&project_dir, command)
+        }
+        Commands::Token {
+            project_name,
+            command,
+        } => {
+            let project_dir = std::env::current_dir()?.join(project_name);
+            commands::token::execute_token(&project_dir, command)
         }
     };
 
     if let Err(err) = result {
-        eprintln!("Error: {err}");
+        eprintln!("Error: {}", redact::redact(&err.to_string()));
         std::process::exit(1);
     }
 
@@ -1,26 +1,86 @@
-use anyhow::Result;
+use crate::dependency::graph::{Edge, Graph, Node as GraphNode};
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
+/// Version of the `.data/metadata.json` format written by [`Metadata::save`].
+///
+/// Files without a `version` were written by older releases, either as a map of
+/// `{last_updated_at, referenced, ...}` nodes or as a map of `{name, updated_at,
+/// dependencies}` nodes, and are migrated when loaded.
+pub const METADATA_VERSION: u32 = 2;
+
+/// Build state of the pipeline, and the dependency graph it was built from.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
-    #[serde(flatten)]
-    pub nodes: HashMap<String, Node>,
+    pub version: u32,
+    #[serde(default)]
+    pub nodes: BTreeMap<String, Node>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Node {
+    #[serde(default)]
     pub last_updated_at: Option<DateTime<Utc>>,
-    pub referenced: Vec<String>,
+    /// Tables this node reads from.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+    #[serde(flatten)]
+    pub import_state: ImportState,
+}
+
+/// What an adapter has already imported, used to load only new data on the next run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportState {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub loaded_files: LoadedFiles,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Files already in the table but not in `loaded_files`, because the run that appended
+    /// them failed afterwards, e.g. on a data test. The next run counts them as loaded
+    /// instead of appending them again.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub appended_files: BTreeSet<String>,
+}
+
+pub type LoadedFiles = BTreeMap<String, LoadedFile>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadedFile {
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+/// A node as written by releases before [`METADATA_VERSION`] 2, in either old shape.
+#[derive(Deserialize)]
+struct LegacyNode {
+    #[serde(default, alias = "updated_at")]
+    last_updated_at: Option<DateTime<Utc>>,
+    /// Downstream nodes.
+    #[serde(default)]
+    referenced: Vec<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    config: Option<String>,
+    #[serde(flatten)]
+    import_state: ImportState,
 }
 
 impl Metadata {
     pub fn new() -> Self {
         Self {
-            nodes: HashMap::new(),
+            version: METADATA_VERSION,
+            nodes: BTreeMap::new(),
         }
     }
 
@@ -32,7 +92,39 @@ impl Metadata {
         }
 
         let content = fs::read_to_string(&path).await?;
-        let metadata: Self = serde_json::from_str(&content)?;
+        Self::parse(&content).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+
+        match value.get("version").and_then(Value::as_u64) {
+            Some(version) if version > u64::from(METADATA_VERSION) => Err(anyhow::anyhow!(
+                "Metadata version {version} is newer than the supported version {METADATA_VERSION}, please upgrade featherbox"
+            )),
+            Some(_) => Ok(serde_json::from_value(value)?),
+            None => Self::migrate_legacy(value),
+        }
+    }
+
+    fn migrate_legacy(value: Value) -> Result<Self> {
+        let legacy: BTreeMap<String, LegacyNode> = serde_json::from_value(value)?;
+
+        let mut metadata = Self::new();
+        for (name, legacy_node) in legacy {
+            let node = metadata.get_node_mut(&name);
+            node.last_updated_at = legacy_node.last_updated_at;
+            node.config = legacy_node.config;
+            node.import_state = legacy_node.import_state;
+            for dependency in legacy_node.dependencies {
+                add_dependency(node, dependency);
+            }
+
+            for downstream in legacy_node.referenced {
+                add_dependency(metadata.get_node_mut(&downstream), name.clone());
+            }
+        }
+
         Ok(metadata)
     }
 
@@ -43,8 +135,11 @@ impl Metadata {
             fs::create_dir_all(parent).await?;
         }
 
+        // Write to a temporary file first so readers never see a half-written file.
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content).await?;
+        let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        fs::write(&temp_path, content).await?;
+        fs::rename(&temp_path, &path).await?;
         Ok(())
     }
 
@@ -57,12 +152,7 @@ impl Metadata {
     }
 
     pub fn get_node_mut(&mut self, table_name: &str) -> &mut Node {
-        self.nodes
-            .entry(table_name.to_string())
-            .or_insert_with(|| Node {
-                last_updated_at: None,
-                referenced: Vec::new(),
-            })
+        self.nodes.entry(table_name.to_string()).or_default()
     }
 
     pub fn update_node_timestamp(&mut self, table_name: &str, timestamp: DateTime<Utc>) {
@@ -70,61 +160,99 @@ impl Metadata {
         node.last_updated_at = Some(timestamp);
     }
 
-    pub fn set_dependencies(&mut self, dependencies: HashMap<String, Vec<String>>) {
-        self.nodes.clear();
+    pub fn update_node_config(&mut self, table_name: &str, config: String) {
+        let node = self.get_node_mut(table_name);
+        node.config = Some(config);
+    }
 
-        let mut reverse_deps: HashMap<String, Vec<String>> = HashMap::new();
+    pub fn update_import_state(&mut self, table_name: &str, import_state: ImportState) {
+        let node = self.get_node_mut(table_name);
+        node.import_state = import_state;
+    }
 
-        for (table, deps) in &dependencies {
-            for dep in deps {
-                reverse_deps
-                    .entry(dep.clone())
-                    .or_default()
-                    .push(table.clone());
-            }
+    /// Records the files a failed run left in `table_name`, keeping the import state of
+    /// the last successful run. `import_state` is what the failed run had imported.
+    pub fn record_appended_files(&mut self, table_name: &str, import_state: &ImportState) {
+        let state = &mut self.get_node_mut(table_name).import_state;
+        let appended: Vec<String> = import_state
+            .loaded_files
+            .keys()
+            .filter(|path| !state.loaded_files.contains_key(*path))
+            .cloned()
+            .collect();
+        state.appended_files = import_state.appended_files.clone();
+        state.appended_files.extend(appended);
+    }
+
+    pub fn clear_node_timestamp(&mut self, table_name: &str) {
+        if let Some(node) = self.nodes.get_mut(table_name) {
+            node.last_updated_at = None;
         }
+    }
 
-        for table in dependencies.keys() {
-            let referenced = reverse_deps.get(table).cloned().unwrap_or_default();
+    pub fn set_dependencies(&mut self, table_name: &str, dependencies: Vec<String>) {
+        let node = self.get_node_mut(table_name);
+        node.dependencies = dependencies;
+    }
 
-            self.nodes.insert(
-                table.clone(),
-                Node {
-                    last_updated_at: self.nodes.get(table).and_then(|n| n.last_updated_at),
-                    referenced,
-                },
-            );
-        }
+    pub fn remove_node(&mut self, table_name: &str) {
+        self.nodes.remove(table_name);
+    }
 
-        for (table, referenced_by) in reverse_deps {
-            self.nodes.entry(table).or_insert(Node {
-                last_updated_at: None,
-                referenced: referenced_by,
-            });
+    /// Clears the timestamps of `table_name` and everything downstream of it, so the
+    /// next run rebuilds them.
+    pub fn invalidate(&mut self, table_name: &str) {
+        self.clear_node_timestamp(table_name);
+        for downstream in self.graph().downstream(table_name) {
+            self.clear_node_timestamp(&downstream);
         }
     }
 
-    pub fn get_impacted_tables(&self, changed_tables: &[String]) -> Vec<String> {
-        let mut impacted = changed_tables.to_vec();
-        let mut visited = std::collections::HashSet::new();
-        let mut queue = changed_tables.to_vec();
+    /// The dependency graph of the saved nodes. Dependencies on tables that have no
+    /// node are left out.
+    pub fn graph(&self) -> Graph {
+        let nodes = self
+            .nodes
+            .keys()
+            .map(|name| GraphNode { name: name.clone() })
+            .collect();
+        let edges = self
+            .nodes
+            .iter()
+            .flat_map(|(name, node)| {
+                node.dependencies
+                    .iter()
+                    .filter(|dependency| self.nodes.contains_key(*dependency))
+                    .map(|dependency| Edge {
+                        from: dependency.clone(),
+                        to: name.clone(),
+                    })
+            })
+            .collect();
 
-        while let Some(table) = queue.pop() {
-            if !visited.insert(table.clone()) {
-                continue;
-            }
+        Graph { nodes, edges }
+    }
 
-            if let Some(node) = self.get_node(&table) {
-                for referenced in &node.referenced {
-                    if !visited.contains(referenced) {
-                        queue.push(referenced.clone());
-                        impacted.push(referenced.clone());
-                    }
-                }
-            }
+    /// Replaces the saved nodes with the nodes of `graph`, keeping the build state of
+    /// the ones that were already saved.
+    pub fn set_graph(&mut self, graph: &Graph) {
+        let mut previous = std::mem::take(&mut self.nodes);
+
+        let mut dependencies: HashMap<&str, Vec<String>> = HashMap::new();
+        for edge in &graph.edges {
+            dependencies
+                .entry(edge.to.as_str())
+                .or_default()
+                .push(edge.from.clone());
         }
 
-        impacted
+        for graph_node in &graph.nodes {
+            let mut node = previous.remove(&graph_node.name).unwrap_or_default();
+            node.dependencies = dependencies
+                .remove(graph_node.name.as_str())
+                .unwrap_or_default();
+            self.nodes.insert(graph_node.name.clone(), node);
+        }
     }
 
     pub fn get_oldest_dependency_timestamp(
@@ -144,3 +272,149 @@ impl Default for Metadata {
         Self::new()
     }
 }
+
+fn add_dependency(node: &mut Node, dependency: String) {
+    if !node.dependencies.contains(&dependency) {
+        node.dependencies.push(dependency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path();
+
+        let mut metadata = Metadata::load(project_dir).await?;
+        metadata.set_graph(&Graph {
+            nodes: ["users", "orders", "user_stats"]
+                .into_iter()
+                .map(|name| GraphNode {
+                    name: name.to_string(),
+                })
+                .collect(),
+            edges: vec![Edge {
+                from: "users".to_string(),
+                to: "user_stats".to_string(),
+            }],
+        });
+        metadata.update_node_timestamp("users", Utc::now());
+        metadata.save(project_dir).await?;
+
+        let content = std::fs::read_to_string(Metadata::get_path(project_dir))?;
+        let value: Value = serde_json::from_str(&content)?;
+        assert_eq!(value["version"], json!(METADATA_VERSION));
+        assert_eq!(
+            value["nodes"]["user_stats"]["dependencies"],
+            json!(["users"])
+        );
+
+        let loaded = Metadata::load(project_dir).await?;
+        assert_eq!(loaded.nodes, metadata.nodes);
+        assert!(loaded.get_node("users").unwrap().last_updated_at.is_some());
+        assert_eq!(loaded.graph().downstream("users"), vec!["user_stats"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_pipeline_metadata() -> Result<()> {
+        let content = json!({
+            "users": {
+                "last_updated_at": "2024-01-01T00:00:00Z",
+                "referenced": ["user_stats"],
+                "cursor": "42"
+            },
+            "user_stats": {
+                "last_updated_at": null,
+                "referenced": [],
+                "config": "{}"
+            }
+        });
+
+        let metadata = Metadata::parse(&content.to_string())?;
+        assert_eq!(metadata.version, METADATA_VERSION);
+
+        let users = metadata.get_node("users").unwrap();
+        assert!(users.last_updated_at.is_some());
+        assert!(users.dependencies.is_empty());
+        assert_eq!(users.import_state.cursor.as_deref(), Some("42"));
+
+        let user_stats = metadata.get_node("user_stats").unwrap();
+        assert_eq!(user_stats.dependencies, vec!["users"]);
+        assert_eq!(user_stats.config.as_deref(), Some("{}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_api_graph() -> Result<()> {
+        let content = json!({
+            "users": { "name": "users", "updated_at": "2024-01-01T00:00:00Z", "dependencies": [] },
+            "user_stats": { "name": "user_stats", "updated_at": null, "dependencies": ["users"] }
+        });
+
+        let metadata = Metadata::parse(&content.to_string())?;
+        assert!(
+            metadata
+                .get_node("users")
+                .unwrap()
+                .last_updated_at
+                .is_some()
+        );
+        assert_eq!(
+            metadata.get_node("user_stats").unwrap().dependencies,
+            vec!["users"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let content = json!({ "version": METADATA_VERSION + 1, "nodes": {} });
+        assert!(Metadata::parse(&content.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        // a-->b-->c
+        //     |
+        // e---+-->d
+        let mut metadata = Metadata::new();
+        for (name, dependencies) in [
+            ("a", vec![]),
+            ("e", vec![]),
+            ("b", vec!["a"]),
+            ("c", vec!["b"]),
+            ("d", vec!["b", "e"]),
+        ] {
+            metadata.set_dependencies(name, dependencies.into_iter().map(String::from).collect());
+            metadata.update_node_timestamp(name, Utc::now());
+        }
+
+        let graph = metadata.graph();
+        assert_eq!(sorted(graph.upstream("d")), vec!["a", "b", "e"]);
+        assert_eq!(sorted(graph.downstream("b")), vec!["c", "d"]);
+
+        metadata.invalidate("a");
+        for name in ["a", "b", "c", "d"] {
+            assert!(metadata.get_node(name).unwrap().last_updated_at.is_none());
+        }
+        assert!(metadata.get_node("e").unwrap().last_updated_at.is_some());
+
+        metadata.remove_node("b");
+        let graph = metadata.graph();
+        assert!(graph.upstream("c").is_empty());
+        assert_eq!(graph.upstream("d"), vec!["e"]);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::dependency::graph::Graph;
+use crate::dependency::graph::{Graph, GraphError};
+use anyhow::Result;
 use std::collections::{HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -12,9 +13,9 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    pub fn from_graph(graph: &Graph) -> Self {
-        let sorted_nodes = topological_sort(graph);
-        let level_map = calculate_execution_levels(graph);
+    pub fn from_graph(graph: &Graph) -> Result<Self, GraphError> {
+        let sorted_nodes = topological_sort(graph)?;
+        let level_map = execution_levels(graph, &sorted_nodes);
         let mut levels_actions: HashMap<usize, Vec<Action>> = HashMap::new();
 
         for node_name in sorted_nodes {
@@ -32,7 +33,7 @@ impl Pipeline {
             levels[level] = actions;
         }
 
-        Pipeline { levels }
+        Ok(Pipeline { levels })
     }
 
     pub fn all_actions(&self) -> Vec<&Action> {
@@ -40,7 +41,10 @@ impl Pipeline {
     }
 }
 
-pub fn topological_sort(graph: &Graph) -> Vec<String> {
+/// Orders the nodes so every table comes after the tables it reads from. Fails with the
+/// cycle path when the graph has a cycle, and when an edge starts at a node that is not in
+/// the graph.
+pub fn topological_sort(graph: &Graph) -> Result<Vec<String>, GraphError> {
     let mut in_degree = HashMap::new();
     let mut adjacency = HashMap::<String, Vec<String>>::new();
 
@@ -50,8 +54,17 @@ pub fn topological_sort(graph: &Graph) -> Vec<String> {
     }
 
     for edge in &graph.edges {
-        *in_degree.get_mut(&edge.to).unwrap() += 1;
-        adjacency.get_mut(&edge.from).unwrap().push(edge.to.clone());
+        let Some(degree) = in_degree.get_mut(&edge.to) else {
+            continue;
+        };
+        let Some(neighbors) = adjacency.get_mut(&edge.from) else {
+            return Err(GraphError::NonExistentTableReference {
+                model_name: edge.to.clone(),
+                table_name: edge.from.clone(),
+            });
+        };
+        *degree += 1;
+        neighbors.push(edge.to.clone());
     }
 
     let mut queue: VecDeque<String> = VecDeque::new();
@@ -77,47 +90,50 @@ pub fn topological_sort(graph: &Graph) -> Vec<String> {
     }
 
     if sorted_nodes.len() != graph.nodes.len() {
-        panic!(
-            "Circular dependency detected in graph. Expected {} nodes, got {}",
-            graph.nodes.len(),
-            sorted_nodes.len()
-        );
+        let nodes = graph.cycles().into_iter().next().unwrap_or_else(|| {
+            graph
+                .nodes
+                .iter()
+                .map(|node| node.name.clone())
+                .filter(|name| !sorted_nodes.contains(name))
+                .collect()
+        });
+        return Err(GraphError::CircularDependency { nodes });
     }
 
-    sorted_nodes
+    Ok(sorted_nodes)
+}
+
+pub fn calculate_execution_levels(graph: &Graph) -> Result<HashMap<String, usize>, GraphError> {
+    let sorted_nodes = topological_sort(graph)?;
+    Ok(execution_levels(graph, &sorted_nodes))
 }
 
-pub fn calculate_execution_levels(graph: &Graph) -> HashMap<String, usize> {
+fn execution_levels(graph: &Graph, sorted_nodes: &[String]) -> HashMap<String, usize> {
     let mut levels = HashMap::new();
-    let mut adjacency = HashMap::<String, Vec<String>>::new();
     let mut reverse_adjacency = HashMap::<String, Vec<String>>::new();
 
     for node in &graph.nodes {
         levels.insert(node.name.clone(), 0);
-        adjacency.insert(node.name.clone(), Vec::new());
         reverse_adjacency.insert(node.name.clone(), Vec::new());
     }
 
     for edge in &graph.edges {
-        adjacency.get_mut(&edge.from).unwrap().push(edge.to.clone());
-        reverse_adjacency
-            .get_mut(&edge.to)
-            .unwrap()
-            .push(edge.from.clone());
+        if let Some(predecessors) = reverse_adjacency.get_mut(&edge.to) {
+            predecessors.push(edge.from.clone());
+        }
     }
 
-    let sorted_nodes = topological_sort(graph);
-
     for node in sorted_nodes {
         let mut max_level = 0;
-        if let Some(predecessors) = reverse_adjacency.get(&node) {
+        if let Some(predecessors) = reverse_adjacency.get(node) {
             for predecessor in predecessors {
                 if let Some(&predecessor_level) = levels.get(predecessor) {
                     max_level = max_level.max(predecessor_level + 1);
                 }
             }
         }
-        levels.insert(node, max_level);
+        levels.insert(node.clone(), max_level);
     }
 
     levels
@@ -160,6 +176,48 @@ pub fn create_subgraph(graph: &Graph, affected_nodes: &[String]) -> Graph {
     }
 }
 
+pub fn create_execution_subgraph(graph: &Graph, target_node: &str) -> Result<Graph> {
+    if !graph.nodes.iter().any(|n| n.name == target_node) {
+        return Err(anyhow::anyhow!(
+            "Target node '{}' not found in graph",
+            target_node
+        ));
+    }
+
+    let mut included_nodes = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(target_node);
+
+    while let Some(current_node) = queue.pop_front() {
+        if included_nodes.insert(current_node.to_string()) {
+            for edge in &graph.edges {
+                if edge.to == current_node && !included_nodes.contains(&edge.from) {
+                    queue.push_back(&edge.from);
+                }
+            }
+        }
+    }
+
+    let filtered_nodes = graph
+        .nodes
+        .iter()
+        .filter(|node| included_nodes.contains(&node.name))
+        .cloned()
+        .collect();
+
+    let filtered_edges = graph
+        .edges
+        .iter()
+        .filter(|edge| included_nodes.contains(&edge.from) && included_nodes.contains(&edge.to))
+        .cloned()
+        .collect();
+
+    Ok(Graph {
+        nodes: filtered_nodes,
+        edges: filtered_edges,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,7 +249,7 @@ mod tests {
             ],
         };
 
-        let result = topological_sort(&graph);
+        let result = topological_sort(&graph).unwrap();
         assert_eq!(result, vec!["A", "B", "C"]);
     }
 
@@ -253,7 +311,7 @@ mod tests {
             ],
         };
 
-        let pipeline = Pipeline::from_graph(&graph);
+        let pipeline = Pipeline::from_graph(&graph).unwrap();
 
         assert_pipeline_has_levels!(pipeline, vec![2, 1]);
         assert_level_has_tables!(pipeline, 0, &["users", "orders"]);
@@ -344,11 +402,87 @@ mod tests {
             ],
         };
 
-        let levels = calculate_execution_levels(&graph);
+        let levels = calculate_execution_levels(&graph).unwrap();
 
         assert_eq!(levels.get("A"), Some(&0));
         assert_eq!(levels.get("B"), Some(&1));
         assert_eq!(levels.get("C"), Some(&1));
         assert_eq!(levels.get("D"), Some(&2));
     }
+
+    #[test]
+    fn test_cycle_is_an_error() {
+        let node = |name: &str| Node {
+            name: name.to_string(),
+        };
+        let edge = |from: &str, to: &str| Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        let graph = Graph {
+            nodes: vec![node("A"), node("B"), node("C")],
+            edges: vec![edge("A", "B"), edge("B", "C"), edge("C", "B")],
+        };
+
+        let cycle = GraphError::CircularDependency {
+            nodes: vec!["B".to_string(), "C".to_string(), "B".to_string()],
+        };
+        assert_eq!(topological_sort(&graph), Err(cycle.clone()));
+        assert_eq!(calculate_execution_levels(&graph), Err(cycle.clone()));
+        assert_eq!(Pipeline::from_graph(&graph).unwrap_err(), cycle);
+
+        let graph = Graph {
+            nodes: vec![node("B")],
+            edges: vec![edge("A", "B")],
+        };
+        assert_eq!(
+            topological_sort(&graph),
+            Err(GraphError::NonExistentTableReference {
+                model_name: "B".to_string(),
+                table_name: "A".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_execution_subgraph_includes_upstream_only() {
+        let graph = Graph {
+            nodes: vec![
+                Node {
+                    name: "users".to_string(),
+                },
+                Node {
+                    name: "orders".to_string(),
+                },
+                Node {
+                    name: "user_stats".to_string(),
+                },
+                Node {
+                    name: "report".to_string(),
+                },
+            ],
+            edges: vec![
+                Edge {
+                    from: "users".to_string(),
+                    to: "user_stats".to_string(),
+                },
+                Edge {
+                    from: "orders".to_string(),
+                    to: "report".to_string(),
+                },
+                Edge {
+                    from: "user_stats".to_string(),
+                    to: "report".to_string(),
+                },
+            ],
+        };
+
+        let subgraph = create_execution_subgraph(&graph, "user_stats").unwrap();
+        let mut names: Vec<&str> = subgraph.nodes.iter().map(|n| n.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["user_stats", "users"]);
+        assert_eq!(subgraph.edges.len(), 1);
+
+        assert!(create_execution_subgraph(&graph, "missing").is_err());
+    }
 }
@@ -0,0 +1,222 @@
+use crate::{
+    config::data_test::{DataCheck, DataTestConfig, Severity},
+    pipeline::ducklake::DuckLake,
+    sql::{quote_identifier, quote_literal},
+};
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTestFailure {
+    pub test: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Runs every test declared on `table_name` and returns the ones that failed.
+pub fn run_data_tests(
+    ducklake: &DuckLake,
+    table_name: &str,
+    tests: &[DataTestConfig],
+) -> Vec<DataTestFailure> {
+    tests
+        .iter()
+        .filter_map(|test| {
+            let message = match evaluate(ducklake, table_name, &test.check) {
+                Ok(None) => return None,
+                Ok(Some(message)) => message,
+                Err(e) => format!("{e:#}"),
+            };
+
+            Some(DataTestFailure {
+                test: test.check.describe(),
+                severity: test.severity,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn evaluate(ducklake: &DuckLake, table_name: &str, check: &DataCheck) -> Result<Option<String>> {
+    if let DataCheck::RowCount { min, max } = check {
+        let rows = count(
+            ducklake,
+            &format!("SELECT * FROM {}", quote_identifier(table_name)),
+        )?;
+        let too_few = min.is_some_and(|min| rows < min);
+        let too_many = max.is_some_and(|max| rows > max);
+
+        return Ok((too_few || too_many).then(|| format!("table has {rows} rows")));
+    }
+
+    let failing_rows = count(ducklake, &failing_rows_query(table_name, check))?;
+    Ok((failing_rows > 0).then(|| format!("{failing_rows} failing rows")))
+}
+
+fn failing_rows_query(table_name: &str, check: &DataCheck) -> String {
+    let table_name = quote_identifier(table_name);
+    match check {
+        DataCheck::NotNull { column } => {
+            let column = quote_identifier(column);
+            format!("SELECT * FROM {table_name} WHERE {column} IS NULL")
+        }
+        DataCheck::Unique { columns } => {
+            let columns = columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("SELECT {columns} FROM {table_name} GROUP BY {columns} HAVING COUNT(*) > 1")
+        }
+        DataCheck::AcceptedValues { column, values } => {
+            let column = quote_identifier(column);
+            let values = values
+                .iter()
+                .map(|value| quote_literal(value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "SELECT * FROM {table_name} WHERE {column} IS NOT NULL AND CAST({column} AS VARCHAR) NOT IN ({values})"
+            )
+        }
+        DataCheck::Relationships { column, to, field } => {
+            let column = quote_identifier(column);
+            let to = quote_identifier(to);
+            let field = quote_identifier(field);
+            format!(
+                "SELECT * FROM {table_name} AS child WHERE child.{column} IS NOT NULL \
+                 AND NOT EXISTS (SELECT 1 FROM {to} AS parent WHERE parent.{field} = child.{column})"
+            )
+        }
+        DataCheck::RowCount { .. } => format!("SELECT * FROM {table_name} WHERE false"),
+        DataCheck::Sql { sql } => sql.trim().trim_end_matches(';').to_string(),
+    }
+}
+
+fn count(ducklake: &DuckLake, query: &str) -> Result<u64> {
+    let results = ducklake.query(&format!("SELECT COUNT(*) FROM (\n{query}\n)"))?;
+    results
+        .first()
+        .and_then(|row| row.first())
+        .and_then(|count| count.parse().ok())
+        .with_context(|| format!("Failed to count rows of data test query: {query}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::project::StorageConfig, pipeline::ducklake::CatalogConfig};
+
+    fn test(check: DataCheck, severity: Severity) -> DataTestConfig {
+        DataTestConfig { check, severity }
+    }
+
+    #[tokio::test]
+    async fn test_run_data_tests() {
+        let test_dir = "/tmp/data_test_run";
+        std::fs::remove_dir_all(test_dir).ok();
+        std::fs::create_dir_all(test_dir).unwrap();
+
+        let catalog_config = CatalogConfig::Sqlite {
+            path: format!("{test_dir}/test_catalog.sqlite"),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: format!("{test_dir}/test_storage"),
+        };
+        let ducklake = DuckLake::new(catalog_config, storage_config).await.unwrap();
+
+        ducklake
+            .create_table_from_query("users", "SELECT * FROM (VALUES (1), (2)) AS t(id)")
+            .unwrap();
+        ducklake
+            .create_table_from_query(
+                "orders",
+                "SELECT * FROM (VALUES (1, 1, 'paid'), (2, 3, 'paid'), (2, NULL, 'lost')) AS t(id, user_id, status)",
+            )
+            .unwrap();
+
+        let tests = vec![
+            test(
+                DataCheck::NotNull {
+                    column: "id".to_string(),
+                },
+                Severity::Error,
+            ),
+            test(
+                DataCheck::NotNull {
+                    column: "user_id".to_string(),
+                },
+                Severity::Warn,
+            ),
+            test(
+                DataCheck::Unique {
+                    columns: vec!["id".to_string()],
+                },
+                Severity::Error,
+            ),
+            test(
+                DataCheck::AcceptedValues {
+                    column: "status".to_string(),
+                    values: vec!["paid".to_string(), "refunded".to_string()],
+                },
+                Severity::Error,
+            ),
+            test(
+                DataCheck::Relationships {
+                    column: "user_id".to_string(),
+                    to: "users".to_string(),
+                    field: "id".to_string(),
+                },
+                Severity::Error,
+            ),
+            test(
+                DataCheck::RowCount {
+                    min: Some(1),
+                    max: Some(2),
+                },
+                Severity::Error,
+            ),
+            test(
+                DataCheck::Sql {
+                    sql: "SELECT * FROM orders WHERE id < 0;".to_string(),
+                },
+                Severity::Error,
+            ),
+        ];
+
+        let failures = run_data_tests(&ducklake, "orders", &tests);
+        let failed: Vec<(&str, Severity)> = failures
+            .iter()
+            .map(|failure| (failure.test.as_str(), failure.severity))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                ("not_null(user_id)", Severity::Warn),
+                ("unique(id)", Severity::Error),
+                ("accepted_values(status)", Severity::Error),
+                ("relationships(user_id -> users.id)", Severity::Error),
+                ("row_count(1..2)", Severity::Error),
+            ]
+        );
+        assert_eq!(failures[4].message, "table has 3 rows");
+
+        let broken = vec![test(
+            DataCheck::Sql {
+                sql: "SELECT * FROM missing_table".to_string(),
+            },
+            Severity::Warn,
+        )];
+        assert_eq!(run_data_tests(&ducklake, "orders", &broken).len(), 1);
+
+        ducklake
+            .create_table_from_query("events", r#"SELECT * FROM (VALUES (1)) AS t("group")"#)
+            .unwrap();
+        let reserved = vec![test(
+            DataCheck::NotNull {
+                column: "group".to_string(),
+            },
+            Severity::Error,
+        )];
+        assert!(run_data_tests(&ducklake, "events", &reserved).is_empty());
+    }
+}
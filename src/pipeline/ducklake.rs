@@ -1,12 +1,27 @@
 use crate::config::Config;
 use crate::config::project::{
-    ConnectionConfig, DatabaseType, RemoteDatabaseConfig, S3AuthMethod, S3Config, StorageConfig,
+    ConnectionConfig, DatabaseConfig, DatabaseType, EngineConfig, RemoteDatabaseConfig,
+    S3AuthMethod, S3Config, StorageConfig,
 };
+use crate::redact::redact;
+use crate::sql::{connection_value, quote_identifier, quote_literal};
 use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, NaiveDate, NaiveTime};
 use duckdb::DuckdbConnectionManager;
-use r2d2::Pool;
+use duckdb::InterruptHandle;
+use duckdb::arrow::datatypes::DataType;
+use duckdb::types::{TimeUnit, Value};
+use r2d2::{CustomizeConnection, ManageConnection, Pool, PooledConnection};
+use serde::{Deserialize, Serialize};
+use sqlparser::{ast::Statement, dialect::DuckDbDialect, parser::Parser};
+use std::fmt;
+use std::ops::Deref;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Clone)]
 pub enum CatalogConfig {
@@ -19,29 +34,335 @@ pub enum CatalogConfig {
     },
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+/// A window of a query's result, with values converted to JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryPage {
+    pub columns: Vec<QueryColumn>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub offset: usize,
+    /// Offset of the next page, if there are more rows.
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    fn copy_options(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "FORMAT CSV, HEADER",
+            ExportFormat::Jsonl => "FORMAT JSON",
+            ExportFormat::Parquet => "FORMAT PARQUET",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// How often [`SharedDuckLake::get`] checks that the catalog still responds, unless a
+/// connection checkout fails first.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A `DuckLake` shared by API requests. It is created on first use and rebuilt when the
+/// project's storage, catalog or engine settings change, or when it stops responding.
+/// The lock is only held to read or swap the current instance, so a slow rebuild or
+/// health check doesn't hold up requests that can use it.
+#[derive(Default)]
+pub struct SharedDuckLake {
+    current: std::sync::Mutex<Option<CurrentDuckLake>>,
+}
+
+struct CurrentDuckLake {
+    settings: DuckLakeSettings,
+    ducklake: Arc<DuckLake>,
+    checked_at: Instant,
+}
+
+#[derive(PartialEq)]
+struct DuckLakeSettings {
+    storage: StorageConfig,
+    database: DatabaseConfig,
+    engine: Option<EngineConfig>,
+}
+
+impl DuckLakeSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            storage: config.project.storage.clone(),
+            database: config.project.database.clone(),
+            engine: config.project.engine.clone(),
+        }
+    }
+}
+
+impl SharedDuckLake {
+    pub async fn get(&self, config: &Config) -> Result<Arc<DuckLake>> {
+        let settings = DuckLakeSettings::from_config(config);
+        let current = self
+            .current
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|current| current.settings == settings)
+            .map(|current| (current.ducklake.clone(), current.checked_at));
+
+        if let Some((ducklake, checked_at)) = current {
+            if !ducklake.checkout_failed.load(Ordering::SeqCst)
+                && checked_at.elapsed() < HEALTH_CHECK_INTERVAL
+            {
+                return Ok(ducklake);
+            }
+            if Self::check_health(ducklake.clone()).await {
+                if let Some(current) = self.current.lock().unwrap().as_mut()
+                    && Arc::ptr_eq(&current.ducklake, &ducklake)
+                {
+                    current.checked_at = Instant::now();
+                }
+                return Ok(ducklake);
+            }
+        }
+
+        let ducklake = Arc::new(DuckLake::from_config(config).await?);
+        *self.current.lock().unwrap() = Some(CurrentDuckLake {
+            settings,
+            ducklake: ducklake.clone(),
+            checked_at: Instant::now(),
+        });
+        Ok(ducklake)
+    }
+
+    async fn check_health(ducklake: Arc<DuckLake>) -> bool {
+        let check = tokio::task::spawn_blocking(move || ducklake.is_healthy());
+        matches!(
+            tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check).await,
+            Ok(Ok(true))
+        )
+    }
+}
+
+/// Validates pooled connections with a query before handing them out, so a broken
+/// connection is replaced instead of failing the caller's statement.
+struct CheckedConnectionManager(DuckdbConnectionManager);
+
+impl ManageConnection for CheckedConnectionManager {
+    type Connection = duckdb::Connection;
+    type Error = duckdb::Error;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.0.connect()
+    }
+
+    fn is_valid(&self, connection: &mut Self::Connection) -> Result<(), Self::Error> {
+        connection.execute_batch("SELECT 1")
+    }
+
+    fn has_broken(&self, connection: &mut Self::Connection) -> bool {
+        self.0.has_broken(connection)
+    }
+}
+
+/// Applies the project's `engine` resource limits to every new pooled connection.
+#[derive(Debug)]
+struct ConnectionSettings(String);
+
+impl ConnectionSettings {
+    fn new(engine: &EngineConfig) -> Self {
+        let mut statements = Vec::new();
+        if let Some(memory_limit) = &engine.memory_limit {
+            statements.push(format!(
+                "SET memory_limit = {};",
+                quote_literal(memory_limit)
+            ));
+        }
+        if let Some(threads) = engine.threads {
+            statements.push(format!("SET threads = {threads};"));
+        }
+        if let Some(temp_directory) = &engine.temp_directory {
+            statements.push(format!(
+                "SET temp_directory = {};",
+                quote_literal(temp_directory)
+            ));
+        }
+
+        Self(statements.join(" "))
+    }
+}
+
+impl CustomizeConnection<duckdb::Connection, duckdb::Error> for ConnectionSettings {
+    fn on_acquire(&self, connection: &mut duckdb::Connection) -> Result<(), duckdb::Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        connection.execute_batch(&self.0)
+    }
+}
+
+/// Why a statement was interrupted by [`DuckLake::run_interruptible`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupted {
+    TimedOut(Duration),
+    Cancelled,
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interrupted::TimedOut(timeout) => {
+                write!(f, "Timed out after {} seconds", timeout.as_secs())
+            }
+            Interrupted::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Tracks the statements a scoped `DuckLake` is running, so they can all be interrupted
+/// when the work they belong to times out or is cancelled.
+#[derive(Default)]
+pub struct InterruptScope {
+    running: std::sync::Mutex<Vec<(u64, Arc<InterruptHandle>)>>,
+    next_id: AtomicU64,
+    interrupted: AtomicBool,
+}
+
+impl InterruptScope {
+    /// Interrupts running statements. Statements started later fail immediately.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        for (_, handle) in self.running.lock().unwrap().iter() {
+            handle.interrupt();
+        }
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    fn register(&self, handle: Arc<InterruptHandle>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.running.lock().unwrap().push((id, handle));
+        id
+    }
+
+    fn unregister(&self, id: u64) {
+        self.running
+            .lock()
+            .unwrap()
+            .retain(|(running_id, _)| *running_id != id);
+    }
+}
+
+/// A pooled connection, registered with the `DuckLake`'s interrupt scope while in use.
+struct ScopedConnection<'a> {
+    connection: CheckedOutConnection<'a>,
+    registration: Option<(&'a InterruptScope, u64)>,
+}
+
+enum CheckedOutConnection<'a> {
+    Pooled(Box<PooledConnection<CheckedConnectionManager>>),
+    Pinned(std::sync::MutexGuard<'a, PooledConnection<CheckedConnectionManager>>),
+}
+
+impl Deref for ScopedConnection<'_> {
+    type Target = duckdb::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.connection {
+            CheckedOutConnection::Pooled(connection) => connection,
+            CheckedOutConnection::Pinned(connection) => connection,
+        }
+    }
+}
+
+impl Drop for ScopedConnection<'_> {
+    fn drop(&mut self) {
+        if let Some((scope, id)) = self.registration {
+            scope.unregister(id);
+        }
+    }
+}
+
+/// Interrupts the scope when dropped, e.g. when the request waiting for it goes away.
+struct InterruptOnDrop(Arc<InterruptScope>);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        self.0.interrupt();
+    }
+}
+
 #[derive(Clone)]
 pub struct DuckLake {
     catalog_config: CatalogConfig,
     storage_config: StorageConfig,
-    pool: Arc<Pool<DuckdbConnectionManager>>,
+    pool: Arc<Pool<CheckedConnectionManager>>,
+    interrupt_scope: Option<Arc<InterruptScope>>,
+    pinned: Option<Arc<std::sync::Mutex<PooledConnection<CheckedConnectionManager>>>>,
+    checkout_failed: Arc<AtomicBool>,
     #[allow(dead_code)]
     temp_dir: Arc<tempfile::TempDir>,
 }
 
 impl DuckLake {
     pub async fn new(catalog_config: CatalogConfig, storage_config: StorageConfig) -> Result<Self> {
+        Self::with_engine_config(catalog_config, storage_config, &EngineConfig::default()).await
+    }
+
+    pub async fn with_engine_config(
+        catalog_config: CatalogConfig,
+        storage_config: StorageConfig,
+        engine: &EngineConfig,
+    ) -> Result<Self> {
+        let pool_size = engine.pool_size.unwrap_or_else(|| num_cpus::get() as u32);
+        if pool_size == 0 {
+            return Err(anyhow::anyhow!("DuckDB pool size must be at least 1"));
+        }
+
         let temp_dir = tempfile::tempdir()?;
         let temp_db_path = temp_dir.path().join("shared.db");
 
-        let manager = DuckdbConnectionManager::file(&temp_db_path)?;
+        let manager = CheckedConnectionManager(DuckdbConnectionManager::file(&temp_db_path)?);
         let pool = Pool::builder()
-            .max_size(num_cpus::get() as u32)
-            .build(manager)?;
+            .max_size(pool_size)
+            .test_on_check_out(true)
+            .connection_customizer(Box::new(ConnectionSettings::new(engine)))
+            .build(manager)
+            .context("Failed to create DuckDB connection pool")?;
 
         let instance = Self {
             catalog_config,
             storage_config,
             pool: Arc::new(pool),
+            interrupt_scope: None,
+            pinned: None,
+            checkout_failed: Arc::new(AtomicBool::new(false)),
             temp_dir: Arc::new(temp_dir),
         };
 
@@ -136,7 +457,106 @@ impl DuckLake {
             }
         };
 
-        DuckLake::new(catalog_config, config.project.storage.clone()).await
+        DuckLake::with_engine_config(
+            catalog_config,
+            config.project.storage.clone(),
+            &config.project.engine.clone().unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Returns a handle on the same database whose statements are tracked by `scope`.
+    pub fn with_interrupt_scope(&self, scope: Arc<InterruptScope>) -> Self {
+        Self {
+            interrupt_scope: Some(scope),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a handle on the same database whose statements all run on one pooled
+    /// connection, so they can see the `TEMP` tables created through it. The connection
+    /// goes back to the pool when the last clone of the handle is dropped.
+    pub fn pin_connection(&self) -> Result<Self> {
+        if self.pinned.is_some() {
+            return Ok(self.clone());
+        }
+
+        let connection = self.checkout()?;
+        Ok(Self {
+            pinned: Some(Arc::new(std::sync::Mutex::new(connection))),
+            ..self.clone()
+        })
+    }
+
+    fn checkout(&self) -> Result<PooledConnection<CheckedConnectionManager>> {
+        self.pool
+            .get()
+            .inspect_err(|_| self.checkout_failed.store(true, Ordering::SeqCst))
+            .context("Failed to get connection from pool")
+    }
+
+    fn connection(&self) -> Result<ScopedConnection<'_>> {
+        if let Some(scope) = &self.interrupt_scope
+            && scope.is_interrupted()
+        {
+            return Err(anyhow::anyhow!("Interrupted"));
+        }
+
+        let connection = match &self.pinned {
+            Some(pinned) => CheckedOutConnection::Pinned(pinned.lock().unwrap()),
+            None => CheckedOutConnection::Pooled(Box::new(self.checkout()?)),
+        };
+        let registration = self.interrupt_scope.as_deref().map(|scope| {
+            let handle = match &connection {
+                CheckedOutConnection::Pooled(connection) => connection.interrupt_handle(),
+                CheckedOutConnection::Pinned(connection) => connection.interrupt_handle(),
+            };
+            (scope, scope.register(handle))
+        });
+
+        Ok(ScopedConnection {
+            connection,
+            registration,
+        })
+    }
+
+    /// Runs blocking `work` on a separate thread. Its statements are interrupted when
+    /// `timeout` elapses, `cancel` fires or the returned future is dropped.
+    pub async fn run_interruptible<T, F>(
+        &self,
+        timeout: Duration,
+        cancel: &CancellationToken,
+        work: F,
+    ) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DuckLake) -> Result<T> + Send + 'static,
+    {
+        let scope = Arc::new(InterruptScope::default());
+        let _guard = InterruptOnDrop(Arc::clone(&scope));
+        let ducklake = self.with_interrupt_scope(Arc::clone(&scope));
+        let mut task = tokio::task::spawn_blocking(move || work(&ducklake));
+
+        let interrupted = tokio::select! {
+            result = &mut task => return result?,
+            _ = tokio::time::sleep(timeout) => Interrupted::TimedOut(timeout),
+            _ = cancel.cancelled() => Interrupted::Cancelled,
+        };
+
+        scope.interrupt();
+        let _ = task.await;
+        Err(interrupted.into())
+    }
+
+    /// Checks that a pooled connection can still reach the attached catalog.
+    pub fn is_healthy(&self) -> bool {
+        let healthy = self
+            .query("SELECT COUNT(*) FROM information_schema.schemata")
+            .is_ok();
+        if healthy {
+            self.checkout_failed.store(false, Ordering::SeqCst);
+        }
+        healthy
     }
 
     async fn initialize(&self) -> Result<()> {
@@ -151,72 +571,28 @@ impl DuckLake {
         Ok(())
     }
 
-    pub async fn configure_s3_connection(&self, connection: &ConnectionConfig) -> Result<()> {
-        if let ConnectionConfig::S3(s3_config) = connection {
-            let region = &s3_config.region;
-            let auth_method = &s3_config.auth_method;
-            let access_key_id = &s3_config.access_key_id;
-            let secret_access_key = &s3_config.secret_access_key;
-            let session_token = &s3_config.session_token;
-            let path_style_access = s3_config.path_style_access;
-            self.execute_batch("INSTALL httpfs; LOAD httpfs;")
-                .context("Failed to install and load httpfs extension for S3")?;
-
-            match auth_method {
-                S3AuthMethod::CredentialChain => {
-                    self.execute_batch("INSTALL aws; LOAD aws;")
-                        .context("Failed to install and load aws extension for credential chain")?;
-
-                    let mut create_secret_parts = vec![
-                        "CREATE OR REPLACE SECRET s3_secret (".to_string(),
-                        "    TYPE S3,".to_string(),
-                        format!("    REGION '{}'", region),
-                        ",    PROVIDER credential_chain".to_string(),
-                    ];
-
-                    if let Some(endpoint) = connection.get_full_endpoint_url() {
-                        create_secret_parts.push(format!(",    ENDPOINT '{endpoint}'"));
-                    }
-
-                    if path_style_access {
-                        create_secret_parts.push(",    URL_STYLE 'path'".to_string());
-                    }
-
-                    create_secret_parts.push(");".to_string());
-
-                    let create_secret_sql = create_secret_parts.join("\n");
-                    self.execute_batch(&create_secret_sql)
-                        .context("Failed to create S3 secret with credential chain")?;
-                }
-                S3AuthMethod::Explicit => {
-                    let mut s3_settings = vec![
-                        format!("SET s3_region = '{}';", region),
-                        format!("SET s3_access_key_id = '{}';", access_key_id),
-                        format!("SET s3_secret_access_key = '{}';", secret_access_key),
-                    ];
-
-                    if let Some(clean_endpoint) = connection.get_clean_endpoint_url() {
-                        s3_settings.push(format!("SET s3_endpoint = '{clean_endpoint}';"));
-                    }
-
-                    if path_style_access {
-                        s3_settings.push("SET s3_url_style = 'path';".to_string());
-                    }
-
-                    if !connection.uses_ssl() {
-                        s3_settings.push("SET s3_use_ssl = false;".to_string());
-                    }
-
-                    if let Some(token) = session_token {
-                        s3_settings.push(format!("SET s3_session_token = '{token}';"));
-                    }
+    /// Creates a secret for an S3 connection, scoped to its bucket so that imports
+    /// reading other buckets through other connections keep their own credentials.
+    pub async fn configure_s3_connection(
+        &self,
+        name: &str,
+        connection: &ConnectionConfig,
+    ) -> Result<()> {
+        let ConnectionConfig::S3(s3_config) = connection else {
+            return Ok(());
+        };
 
-                    let settings_sql = s3_settings.join(" ");
-                    self.execute_batch(&settings_sql)
-                        .context("Failed to configure S3 authentication settings")?;
-                }
-            }
+        self.execute_batch("INSTALL httpfs; LOAD httpfs;")
+            .context("Failed to install and load httpfs extension for S3")?;
+        if s3_config.auth_method == S3AuthMethod::CredentialChain {
+            self.execute_batch("INSTALL aws; LOAD aws;")
+                .context("Failed to install and load aws extension for credential chain")?;
         }
+
+        let secret_sql =
+            Self::build_s3_secret_sql(s3_config, &format!("s3_connection_{name}"), false);
+        self.execute_batch(&secret_sql)
+            .with_context(|| format!("Failed to create S3 secret for connection '{name}'"))?;
         Ok(())
     }
 
@@ -255,7 +631,9 @@ impl DuckLake {
 
                 let extension_sql = "INSTALL sqlite; LOAD sqlite;".to_string();
                 let attach_sql = format!(
-                    "ATTACH 'ducklake:sqlite:{path}' AS db (DATA_PATH '{data_path}'); USE db;"
+                    "ATTACH {} AS db (DATA_PATH {}); USE db;",
+                    quote_literal(&format!("ducklake:sqlite:{path}")),
+                    quote_literal(&data_path)
                 );
 
                 Ok((extension_sql, attach_sql))
@@ -268,11 +646,11 @@ impl DuckLake {
                         let extension = "mysql";
                         let conn_str = format!(
                             "ducklake:mysql:db={} host={} port={} user={} password={}",
-                            config.database,
-                            config.host,
+                            connection_value(&config.database),
+                            connection_value(&config.host),
                             config.port,
-                            config.username,
-                            config.password
+                            connection_value(&config.username),
+                            connection_value(&config.password)
                         );
                         (extension, conn_str)
                     }
@@ -280,11 +658,11 @@ impl DuckLake {
                         let extension = "postgres";
                         let conn_str = format!(
                             "ducklake:postgres:dbname={} host={} port={} user={} password={}",
-                            config.database,
-                            config.host,
+                            connection_value(&config.database),
+                            connection_value(&config.host),
                             config.port,
-                            config.username,
-                            config.password
+                            connection_value(&config.username),
+                            connection_value(&config.password)
                         );
                         (extension, conn_str)
                     }
@@ -295,8 +673,10 @@ impl DuckLake {
 
                 let extension_sql = format!("INSTALL {extension_name}; LOAD {extension_name};");
                 let attach_sql = format!(
-                    "ATTACH '{connection_string}' AS db (DATA_PATH '{data_path}', METADATA_SCHEMA '{}_metadata'); USE db;",
-                    config.database
+                    "ATTACH {} AS db (DATA_PATH {}, METADATA_SCHEMA {}); USE db;",
+                    quote_literal(&connection_string),
+                    quote_literal(&data_path),
+                    quote_literal(&format!("{}_metadata", config.database))
                 );
 
                 Ok((extension_sql, attach_sql))
@@ -305,23 +685,26 @@ impl DuckLake {
     }
 
     pub fn execute_batch(&self, sql: &str) -> Result<()> {
-        let connection = self
-            .pool
-            .get()
-            .context("Failed to get connection from pool")?;
+        let connection = self.connection()?;
 
         connection
             .execute_batch(sql)
-            .with_context(|| format!("Failed to execute batch SQL: {sql}"))
+            .with_context(|| format!("Failed to execute batch SQL: {}", redact(sql)))
     }
 
     pub fn query(&self, sql: &str) -> Result<Vec<Vec<String>>> {
-        let connection = self
-            .pool
-            .get()
-            .context("Failed to get connection from pool")?;
+        self.query_with_params(sql, [])
+    }
+
+    /// Runs `sql` with `params` bound to its `?` placeholders.
+    pub fn query_with_params(
+        &self,
+        sql: &str,
+        params: impl duckdb::Params,
+    ) -> Result<Vec<Vec<String>>> {
+        let connection = self.connection()?;
         let mut stmt = connection.prepare(sql)?;
-        let mut rows = stmt.query([])?;
+        let mut rows = stmt.query(params)?;
         let column_count = rows.as_ref().unwrap().column_count();
 
         let mut results = Vec::new();
@@ -368,12 +751,170 @@ impl DuckLake {
         Ok(results)
     }
 
+    /// Returns at most `limit` rows of `sql`, starting at `offset`, along with the result's
+    /// column names and DuckDB types. A plain query is limited by DuckDB, so only the
+    /// requested rows are read; other statements such as EXPLAIN run unchanged and are
+    /// paged here.
+    pub fn query_page(&self, sql: &str, offset: usize, limit: usize) -> Result<QueryPage> {
+        let sql = trim_statement(sql);
+        // One extra row tells whether there is a next page.
+        let (sql, skip) = if is_plain_query(sql) {
+            let sql = format!(
+                "SELECT * FROM (\n{sql}\n) LIMIT {} OFFSET {offset}",
+                limit + 1
+            );
+            (sql, 0)
+        } else {
+            (sql.to_string(), offset)
+        };
+
+        let connection = self.connection()?;
+        let mut stmt = connection.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+        let columns = {
+            let stmt = rows.as_ref().context("Query did not produce a result")?;
+            (0..stmt.column_count())
+                .map(|i| {
+                    Ok(QueryColumn {
+                        name: stmt.column_name(i)?.clone(),
+                        data_type: duckdb_type_name(&stmt.column_type(i)),
+                    })
+                })
+                .collect::<duckdb::Result<Vec<_>>>()?
+        };
+
+        let mut page = Vec::new();
+        let mut skipped = 0;
+        while let Some(row) = rows.next()? {
+            if skipped < skip {
+                skipped += 1;
+                continue;
+            }
+            let values = (0..columns.len())
+                .map(|i| row.get::<_, Value>(i).map(value_to_json))
+                .collect::<duckdb::Result<Vec<_>>>()?;
+            page.push(values);
+            if page.len() > limit {
+                break;
+            }
+        }
+
+        let next_offset = (page.len() > limit).then_some(offset + limit);
+        page.truncate(limit);
+
+        Ok(QueryPage {
+            columns,
+            rows: page,
+            offset,
+            next_offset,
+        })
+    }
+
+    /// Writes the full result of `sql` to `path` with DuckDB's `COPY`.
+    pub fn export_query(&self, sql: &str, format: ExportFormat, path: &Path) -> Result<()> {
+        self.execute_batch(&format!(
+            "COPY (\n{}\n) TO {} ({});",
+            trim_statement(sql),
+            quote_literal(&path.to_string_lossy()),
+            format.copy_options()
+        ))
+    }
+
     pub fn create_table_from_query(&self, table_name: &str, query: &str) -> Result<()> {
-        let sql = format!("CREATE OR REPLACE TABLE {table_name} AS ({query});");
+        let sql = format!(
+            "CREATE OR REPLACE TABLE {} AS ({query});",
+            quote_identifier(table_name)
+        );
         self.execute_batch(&sql)
             .with_context(|| format!("Failed to create table '{table_name}' from query: '{query}'"))
     }
 
+    /// Creates a `TEMP` table, which lives outside the DuckLake catalog and is only
+    /// visible to the connection that created it, see [`DuckLake::pin_connection`].
+    pub fn create_temp_table_from_query(&self, table_name: &str, query: &str) -> Result<()> {
+        let sql = format!(
+            "CREATE OR REPLACE TEMP TABLE {} AS ({query});",
+            quote_identifier(table_name)
+        );
+        self.execute_batch(&sql).with_context(|| {
+            format!("Failed to create temporary table '{table_name}' from query: '{query}'")
+        })
+    }
+
+    pub fn insert_from_query(&self, table_name: &str, query: &str) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} BY NAME {query};",
+            quote_identifier(table_name)
+        );
+        self.execute_batch(&sql).with_context(|| {
+            format!("Failed to insert into table '{table_name}' from query: '{query}'")
+        })
+    }
+
+    /// Replaces rows of `table_name` that share `keys` with rows from `query` and inserts
+    /// the rest, in a single transaction.
+    pub fn merge_from_query(&self, table_name: &str, query: &str, keys: &[String]) -> Result<()> {
+        if keys.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot merge into '{table_name}' without key columns"
+            ));
+        }
+
+        let target_table = quote_identifier(table_name);
+        let staging_table = quote_identifier(&Self::generate_temp_table_name(&format!(
+            "{table_name}_merge"
+        )));
+        let key_condition = keys
+            .iter()
+            .map(|key| {
+                let key = quote_identifier(key);
+                format!("{target_table}.{key} = {staging_table}.{key}")
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let sql = format!(
+            "BEGIN TRANSACTION; \
+             CREATE TEMP TABLE {staging_table} AS ({query}); \
+             DELETE FROM {target_table} WHERE EXISTS (SELECT 1 FROM {staging_table} WHERE {key_condition}); \
+             INSERT INTO {target_table} BY NAME SELECT * FROM {staging_table}; \
+             DROP TABLE {staging_table}; \
+             COMMIT;"
+        );
+
+        let connection = self.connection()?;
+        if let Err(e) = connection.execute_batch(&sql) {
+            let _ = connection.execute_batch("ROLLBACK;");
+            return Err(e).with_context(|| {
+                format!("Failed to merge into table '{table_name}' from query: '{query}'")
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the rows of `table_name` matching `condition` and inserts the rows of `query`
+    /// in their place, in a single transaction.
+    pub fn replace_rows(&self, table_name: &str, condition: &str, query: &str) -> Result<()> {
+        let target_table = quote_identifier(table_name);
+        let sql = format!(
+            "BEGIN TRANSACTION; \
+             DELETE FROM {target_table} WHERE {condition}; \
+             INSERT INTO {target_table} BY NAME {query}; \
+             COMMIT;"
+        );
+
+        let connection = self.connection()?;
+        if let Err(e) = connection.execute_batch(&sql) {
+            let _ = connection.execute_batch("ROLLBACK;");
+            return Err(e).with_context(|| {
+                format!("Failed to replace rows of table '{table_name}' from query: '{query}'")
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn create_table(&self, table_name: &str, columns: &[(String, String)]) -> Result<()> {
         if columns.is_empty() {
             return Err(anyhow::anyhow!(
@@ -383,11 +924,14 @@ impl DuckLake {
 
         let column_definitions: Vec<String> = columns
             .iter()
-            .map(|(name, data_type)| format!("{name} {data_type}"))
+            .map(|(name, data_type)| format!("{} {data_type}", quote_identifier(name)))
             .collect();
 
         let columns_sql = column_definitions.join(", ");
-        let sql = format!("CREATE OR REPLACE TABLE {table_name} ({columns_sql});");
+        let sql = format!(
+            "CREATE OR REPLACE TABLE {} ({columns_sql});",
+            quote_identifier(table_name)
+        );
 
         self.execute_batch(&sql)
             .with_context(|| format!("Failed to create empty table '{table_name}'"))
@@ -398,16 +942,16 @@ impl DuckLake {
     }
 
     pub fn drop_temp_table(&self, table_name: &str) -> Result<()> {
-        let drop_sql = format!("DROP TABLE IF EXISTS {table_name};");
+        let drop_sql = format!("DROP TABLE IF EXISTS {};", quote_identifier(table_name));
         self.execute_batch(&drop_sql)
             .with_context(|| format!("Failed to drop temporary table: {table_name}"))
     }
 
     pub fn table_exists(&self, table_name: &str) -> Result<bool> {
-        let sql = format!(
-            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = '{table_name}'"
-        );
-        let results = self.query(&sql)?;
+        let results = self.query_with_params(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = ?",
+            duckdb::params![table_name],
+        )?;
         let exists = if let Some(row) = results.first() {
             if let Some(count_str) = row.first() {
                 count_str.parse::<i64>().unwrap_or(0) > 0
@@ -420,11 +964,23 @@ impl DuckLake {
         Ok(exists)
     }
 
+    /// Returns `BASE TABLE` or `VIEW` for an existing relation, or `None` if it doesn't exist.
+    pub fn relation_type(&self, name: &str) -> Result<Option<String>> {
+        let results = self.query_with_params(
+            "SELECT table_type FROM information_schema.tables WHERE table_name = ?",
+            duckdb::params![name],
+        )?;
+        Ok(results
+            .into_iter()
+            .next()
+            .and_then(|row| row.into_iter().next()))
+    }
+
     pub fn table_schema(&self, table_name: &str) -> Result<Vec<(String, String)>> {
-        let sql = format!(
-            "SELECT column_name, data_type FROM information_schema.columns WHERE table_name = '{table_name}' ORDER BY ordinal_position"
-        );
-        let results = self.query(&sql)?;
+        let results = self.query_with_params(
+            "SELECT column_name, data_type FROM information_schema.columns WHERE table_name = ? ORDER BY ordinal_position",
+            duckdb::params![table_name],
+        )?;
         let columns = results
             .into_iter()
             .filter_map(|row| {
@@ -459,6 +1015,7 @@ impl DuckLake {
             .map(|url| url.contains("localhost") || url.contains("127.0.0.1"))
             .unwrap_or(false);
 
+        let secret_name = quote_identifier(secret_name);
         let create_clause = if if_not_exists {
             format!("CREATE SECRET IF NOT EXISTS {secret_name}")
         } else {
@@ -469,37 +1026,51 @@ impl DuckLake {
             S3AuthMethod::Explicit => format!(
                 "{create_clause} (
                     TYPE S3,
-                    KEY_ID '{}',
-                    SECRET '{}',
-                    REGION '{}'",
-                s3_config.access_key_id, s3_config.secret_access_key, s3_config.region
+                    KEY_ID {},
+                    SECRET {},
+                    REGION {}",
+                quote_literal(&s3_config.access_key_id),
+                quote_literal(&s3_config.secret_access_key),
+                quote_literal(&s3_config.region)
             ),
             S3AuthMethod::CredentialChain => format!(
                 "{create_clause} (
                     TYPE S3,
                     PROVIDER credential_chain,
-                    REGION '{}'",
-                s3_config.region
+                    REGION {}",
+                quote_literal(&s3_config.region)
             ),
         };
 
+        sql.push_str(&format!(
+            ",\n    SCOPE {}",
+            quote_literal(&format!("s3://{}", s3_config.bucket))
+        ));
+
         if let Some(endpoint) = &s3_config.endpoint_url {
             let clean_endpoint = endpoint
                 .strip_prefix("http://")
                 .or_else(|| endpoint.strip_prefix("https://"))
                 .unwrap_or(endpoint);
-            sql.push_str(&format!(",\n    ENDPOINT '{clean_endpoint}'"));
+            sql.push_str(&format!(
+                ",\n    ENDPOINT {}",
+                quote_literal(clean_endpoint)
+            ));
         }
 
         if let Some(token) = &s3_config.session_token {
-            sql.push_str(&format!(",\n    SESSION_TOKEN '{token}'"));
+            sql.push_str(&format!(",\n    SESSION_TOKEN {}", quote_literal(token)));
         }
 
         if s3_config.path_style_access || is_minio {
             sql.push_str(",\n    URL_STYLE 'path'");
         }
 
-        if is_minio {
+        let plain_http = s3_config
+            .endpoint_url
+            .as_ref()
+            .is_some_and(|url| url.starts_with("http://"));
+        if is_minio || plain_http {
             sql.push_str(",\n    USE_SSL false");
         }
 
@@ -515,6 +1086,137 @@ impl DuckLake {
     }
 }
 
+fn trim_statement(sql: &str) -> &str {
+    sql.trim().trim_end_matches(';').trim_end()
+}
+
+/// Whether `sql` is a single query that can be wrapped in a subquery, unlike e.g. EXPLAIN
+/// or SHOW TABLES.
+fn is_plain_query(sql: &str) -> bool {
+    matches!(
+        Parser::parse_sql(&DuckDbDialect {}, sql).as_deref(),
+        Ok([Statement::Query(_)])
+    )
+}
+
+/// DuckDB's name for a result column type, which the driver reports as an Arrow type.
+fn duckdb_type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "NULL".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 => "TINYINT".to_string(),
+        DataType::Int16 => "SMALLINT".to_string(),
+        DataType::Int32 => "INTEGER".to_string(),
+        DataType::Int64 => "BIGINT".to_string(),
+        DataType::UInt8 => "UTINYINT".to_string(),
+        DataType::UInt16 => "USMALLINT".to_string(),
+        DataType::UInt32 => "UINTEGER".to_string(),
+        DataType::UInt64 => "UBIGINT".to_string(),
+        DataType::Float16 | DataType::Float32 => "FLOAT".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "VARCHAR".to_string(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "BLOB".to_string(),
+        DataType::Date32 | DataType::Date64 => "DATE".to_string(),
+        DataType::Time32(_) | DataType::Time64(_) => "TIME".to_string(),
+        DataType::Timestamp(_, None) => "TIMESTAMP".to_string(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP WITH TIME ZONE".to_string(),
+        DataType::Interval(_) | DataType::Duration(_) => "INTERVAL".to_string(),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            format!("DECIMAL({precision},{scale})")
+        }
+        DataType::List(field) | DataType::LargeList(field) => {
+            format!("{}[]", duckdb_type_name(field.data_type()))
+        }
+        DataType::FixedSizeList(field, size) => {
+            format!("{}[{size}]", duckdb_type_name(field.data_type()))
+        }
+        DataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| format!("{} {}", field.name(), duckdb_type_name(field.data_type())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("STRUCT({fields})")
+        }
+        // ENUM columns arrive dictionary-encoded.
+        DataType::Dictionary(_, values) => duckdb_type_name(values),
+        other => other.to_string(),
+    }
+}
+
+fn value_to_json(value: Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    match value {
+        Value::Null => Json::Null,
+        Value::Boolean(b) => b.into(),
+        Value::TinyInt(i) => i.into(),
+        Value::SmallInt(i) => i.into(),
+        Value::Int(i) => i.into(),
+        Value::BigInt(i) => i.into(),
+        Value::UTinyInt(i) => i.into(),
+        Value::USmallInt(i) => i.into(),
+        Value::UInt(i) => i.into(),
+        Value::UBigInt(i) => i.into(),
+        Value::HugeInt(i) => i64::try_from(i)
+            .map(Json::from)
+            .unwrap_or_else(|_| Json::String(i.to_string())),
+        Value::Float(f) => float_to_json(f64::from(f)),
+        Value::Double(f) => float_to_json(f),
+        // Strings keep the exact decimal value, which JSON numbers may not.
+        Value::Decimal(d) => Json::String(d.to_string()),
+        Value::Text(s) | Value::Enum(s) => Json::String(s),
+        Value::Blob(b) => Json::String(BASE64.encode(b)),
+        Value::Date32(days) => NaiveDate::from_num_days_from_ce_opt(days + 719_163)
+            .map(|date| Json::String(date.to_string()))
+            .unwrap_or(Json::Null),
+        Value::Time64(unit, value) => {
+            let micros = to_micros(unit, value);
+            NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                (micros % 1_000_000) as u32 * 1_000,
+            )
+            .map(|time| Json::String(time.to_string()))
+            .unwrap_or(Json::Null)
+        }
+        Value::Timestamp(unit, value) => DateTime::from_timestamp_micros(to_micros(unit, value))
+            .map(|timestamp| {
+                Json::String(
+                    timestamp
+                        .naive_utc()
+                        .format("%Y-%m-%dT%H:%M:%S%.f")
+                        .to_string(),
+                )
+            })
+            .unwrap_or(Json::Null),
+        Value::Interval {
+            months,
+            days,
+            nanos,
+        } => serde_json::json!({ "months": months, "days": days, "nanos": nanos }),
+        Value::List(values) => Json::Array(values.into_iter().map(value_to_json).collect()),
+        other => Json::String(format!("{other:?}")),
+    }
+}
+
+fn float_to_json(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .unwrap_or_else(|| serde_json::Value::String(value.to_string()))
+}
+
+fn to_micros(unit: TimeUnit, value: i64) -> i64 {
+    match unit {
+        TimeUnit::Second => value * 1_000_000,
+        TimeUnit::Millisecond => value * 1_000,
+        TimeUnit::Microsecond => value,
+        TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -667,12 +1369,16 @@ mod tests {
 
         let temp_dir = tempfile::tempdir().unwrap();
         let temp_db_path = temp_dir.path().join("test.db");
-        let manager = DuckdbConnectionManager::file(&temp_db_path).unwrap();
+        let manager =
+            CheckedConnectionManager(DuckdbConnectionManager::file(&temp_db_path).unwrap());
         let pool = Pool::builder().build(manager).unwrap();
         let ducklake = DuckLake {
             catalog_config,
             storage_config,
             pool: Arc::new(pool),
+            interrupt_scope: None,
+            pinned: None,
+            checkout_failed: Arc::new(AtomicBool::new(false)),
             temp_dir: Arc::new(temp_dir),
         };
 
@@ -697,12 +1403,16 @@ mod tests {
 
         let temp_dir = tempfile::tempdir().unwrap();
         let temp_db_path = temp_dir.path().join("test.db");
-        let manager = DuckdbConnectionManager::file(&temp_db_path).unwrap();
+        let manager =
+            CheckedConnectionManager(DuckdbConnectionManager::file(&temp_db_path).unwrap());
         let pool = Pool::builder().build(manager).unwrap();
         let ducklake = DuckLake {
             catalog_config,
             storage_config,
             pool: Arc::new(pool),
+            interrupt_scope: None,
+            pinned: None,
+            checkout_failed: Arc::new(AtomicBool::new(false)),
             temp_dir: Arc::new(temp_dir),
         };
 
@@ -767,6 +1477,272 @@ mod tests {
         assert_eq!(results[1], vec!["2", "Bob"]);
     }
 
+    #[tokio::test]
+    async fn test_query_page_and_export() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let catalog_config = CatalogConfig::Sqlite {
+            path: test_dir
+                .path()
+                .join("catalog.sqlite")
+                .to_string_lossy()
+                .to_string(),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: test_dir
+                .path()
+                .join("storage")
+                .to_string_lossy()
+                .to_string(),
+        };
+        let ducklake = DuckLake::new(catalog_config, storage_config).await.unwrap();
+
+        let sql = "SELECT i AS id, 'user' || i AS name, i / 2 AS score, NULL::VARCHAR AS note, DATE '2024-01-01' + i AS day FROM range(5) t(i) ORDER BY i;";
+        let page = ducklake.query_page(sql, 0, 2).unwrap();
+
+        let columns: Vec<_> = page
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.data_type.as_str()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", "BIGINT"),
+                ("name", "VARCHAR"),
+                ("score", "DOUBLE"),
+                ("note", "VARCHAR"),
+                ("day", "DATE"),
+            ]
+        );
+        assert_eq!(
+            page.rows,
+            vec![
+                vec![
+                    serde_json::json!(0),
+                    serde_json::json!("user0"),
+                    serde_json::json!(0.0),
+                    serde_json::Value::Null,
+                    serde_json::json!("2024-01-01"),
+                ],
+                vec![
+                    serde_json::json!(1),
+                    serde_json::json!("user1"),
+                    serde_json::json!(0.5),
+                    serde_json::Value::Null,
+                    serde_json::json!("2024-01-02"),
+                ],
+            ]
+        );
+        assert_eq!(page.next_offset, Some(2));
+
+        let last = ducklake.query_page(sql, 4, 2).unwrap();
+        assert_eq!(last.rows.len(), 1);
+        assert_eq!(last.rows[0][0], serde_json::json!(4));
+        assert_eq!(last.next_offset, None);
+
+        let commented = ducklake
+            .query_page("SELECT 1 AS one -- trailing comment", 0, 10)
+            .unwrap();
+        assert_eq!(commented.rows, vec![vec![serde_json::json!(1)]]);
+
+        let explain = ducklake.query_page("EXPLAIN SELECT 1", 0, 10).unwrap();
+        assert!(!explain.columns.is_empty());
+        assert!(!explain.rows.is_empty());
+
+        let csv_path = test_dir.path().join("export.csv");
+        ducklake
+            .export_query(
+                "SELECT i AS id FROM range(3) t(i)",
+                ExportFormat::Csv,
+                &csv_path,
+            )
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&csv_path).unwrap(), "id\n0\n1\n2\n");
+    }
+
+    #[tokio::test]
+    async fn test_shared_ducklake_is_rebuilt_on_settings_change() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| test_dir.path().join(name).to_string_lossy().to_string();
+
+        let mut config = Config::new(test_dir.path().to_path_buf());
+        config.project.storage = StorageConfig::LocalFile {
+            path: path("storage"),
+        };
+        config.project.database.path = Some(path("catalog.sqlite"));
+        config.project.engine = Some(EngineConfig {
+            pool_size: Some(2),
+            ..EngineConfig::default()
+        });
+
+        let shared = SharedDuckLake::default();
+        let first = shared.get(&config).await.unwrap();
+        first
+            .execute_batch("CREATE TABLE shared_test AS SELECT 1 AS id;")
+            .unwrap();
+
+        let second = shared.get(&config).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.pool.max_size(), 2);
+
+        first.checkout_failed.store(true, Ordering::SeqCst);
+        let checked = shared.get(&config).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &checked));
+        assert!(!first.checkout_failed.load(Ordering::SeqCst));
+
+        config.project.storage = StorageConfig::LocalFile {
+            path: path("other_storage"),
+        };
+        let rebuilt = shared.get(&config).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+
+        config.project.engine = Some(EngineConfig {
+            pool_size: Some(0),
+            ..EngineConfig::default()
+        });
+        assert!(shared.get(&config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_merge_from_query() {
+        use std::fs;
+
+        let test_dir = "/tmp/ducklake_test_merge";
+        fs::remove_dir_all(test_dir).ok();
+        fs::create_dir_all(test_dir).unwrap();
+
+        let catalog_config = CatalogConfig::Sqlite {
+            path: format!("{test_dir}/test_catalog.sqlite"),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: format!("{test_dir}/test_storage"),
+        };
+
+        let ducklake = DuckLake::new(catalog_config, storage_config).await.unwrap();
+
+        ducklake
+            .create_table_from_query(
+                "users",
+                "SELECT * FROM (VALUES (1, 'Alice'), (2, 'Bob')) AS t(id, name)",
+            )
+            .unwrap();
+        ducklake
+            .merge_from_query(
+                "users",
+                "SELECT * FROM (VALUES (2, 'Robert'), (3, 'Carol')) AS t(id, name)",
+                &["id".to_string()],
+            )
+            .unwrap();
+
+        let results = ducklake.query("SELECT * FROM users ORDER BY id").unwrap();
+        assert_eq!(
+            results,
+            vec![vec!["1", "Alice"], vec!["2", "Robert"], vec!["3", "Carol"]]
+        );
+
+        assert!(ducklake.merge_from_query("users", "SELECT 1", &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_adversarial_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let test_dir = temp_dir.path().join("it's; DROP TABLE users; --");
+
+        let catalog_config = CatalogConfig::Sqlite {
+            path: test_dir
+                .join("catalog.sqlite")
+                .to_string_lossy()
+                .to_string(),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: test_dir.join("storage").to_string_lossy().to_string(),
+        };
+        let ducklake = DuckLake::new(catalog_config, storage_config).await.unwrap();
+
+        ducklake
+            .create_table_from_query("users", "SELECT 1 AS id")
+            .unwrap();
+
+        for name in [
+            "o'brien",
+            "x'; DROP TABLE users; --",
+            "quoted \"name\"",
+            "users\"; DROP TABLE users; --",
+            "select",
+            "with space",
+        ] {
+            assert!(!ducklake.table_exists(name).unwrap(), "{name}");
+            assert_eq!(ducklake.relation_type(name).unwrap(), None, "{name}");
+
+            ducklake
+                .create_table(
+                    name,
+                    &[
+                        ("it's".to_string(), "INTEGER".to_string()),
+                        ("val\"ue".to_string(), "VARCHAR".to_string()),
+                    ],
+                )
+                .unwrap();
+            ducklake
+                .insert_from_query(name, "SELECT 1 AS \"it's\", 'a' AS \"val\"\"ue\"")
+                .unwrap();
+            ducklake
+                .merge_from_query(
+                    name,
+                    "SELECT 1 AS \"it's\", 'b' AS \"val\"\"ue\"",
+                    &["it's".to_string()],
+                )
+                .unwrap();
+
+            assert!(ducklake.table_exists(name).unwrap(), "{name}");
+            assert_eq!(
+                ducklake.table_schema(name).unwrap(),
+                vec![
+                    ("it's".to_string(), "INTEGER".to_string()),
+                    ("val\"ue".to_string(), "VARCHAR".to_string()),
+                ]
+            );
+            let rows = ducklake
+                .query(&format!("SELECT * FROM {}", quote_identifier(name)))
+                .unwrap();
+            assert_eq!(rows, vec![vec!["1", "b"]], "{name}");
+
+            ducklake.drop_temp_table(name).unwrap();
+            assert!(!ducklake.table_exists(name).unwrap(), "{name}");
+        }
+
+        assert!(ducklake.table_exists("users").unwrap());
+
+        let export_path = test_dir.join("o'brien.csv");
+        ducklake
+            .export_query("SELECT * FROM users", ExportFormat::Csv, &export_path)
+            .unwrap();
+        assert!(export_path.exists());
+    }
+
+    #[test]
+    fn test_s3_secret_sql_escapes_credentials() {
+        let StorageConfig::S3(s3_config) = create_test_s3_storage_with_session_token(
+            "bucket",
+            "us-east-1'",
+            Some("http://localhost:9000".to_string()),
+            S3AuthMethod::Explicit,
+            "AKIA'",
+            "secret'); DROP TABLE users; --",
+            Some("to'ken".to_string()),
+        ) else {
+            unreachable!()
+        };
+
+        let sql = DuckLake::build_s3_secret_sql(&s3_config, "s3_secret", true);
+        assert!(sql.contains("SCOPE 's3://bucket'"));
+        assert!(sql.contains("KEY_ID 'AKIA'''"));
+        assert!(sql.contains("SECRET 'secret''); DROP TABLE users; --'"));
+        assert!(sql.contains("REGION 'us-east-1'''"));
+        assert!(sql.contains("SESSION_TOKEN 'to''ken'"));
+        assert!(!redact(&sql).contains("DROP TABLE"));
+    }
+
     #[tokio::test]
     async fn test_ducklake_configure_s3_connection_explicit() {
         use std::fs;
@@ -800,17 +1776,17 @@ mod tests {
             path_style_access: false,
         });
 
-        let configure_result = ducklake.configure_s3_connection(&s3_connection).await;
+        let configure_result = ducklake
+            .configure_s3_connection("source", &s3_connection)
+            .await;
         assert!(configure_result.is_ok());
 
-        let result = ducklake.query("SELECT current_setting('s3_region')");
-        assert!(
-            result.is_ok(),
-            "Failed to query s3_region setting: {result:?}"
-        );
-        let results = result.unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0][0], "us-east-1");
+        let results = ducklake
+            .query(
+                "SELECT name FROM duckdb_secrets() WHERE list_contains(scope, 's3://test-bucket')",
+            )
+            .unwrap();
+        assert_eq!(results, vec![vec!["s3_connection_source".to_string()]]);
     }
 
     #[tokio::test]
@@ -846,17 +1822,17 @@ mod tests {
             path_style_access: false,
         });
 
-        let configure_result = ducklake.configure_s3_connection(&s3_connection).await;
+        let configure_result = ducklake
+            .configure_s3_connection("source", &s3_connection)
+            .await;
         assert!(configure_result.is_ok());
 
-        let result = ducklake.query("SELECT current_setting('s3_region')");
-        assert!(
-            result.is_ok(),
-            "Failed to query s3_region setting: {result:?}"
-        );
-        let results = result.unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0][0], "us-west-2");
+        let results = ducklake
+            .query(
+                "SELECT name FROM duckdb_secrets() WHERE list_contains(scope, 's3://test-bucket')",
+            )
+            .unwrap();
+        assert_eq!(results, vec![vec!["s3_connection_source".to_string()]]);
     }
 
     #[tokio::test]
@@ -934,7 +1910,9 @@ mod tests {
             path_style_access: false,
         });
 
-        let configure_result = ducklake.configure_s3_connection(&s3_connection).await;
+        let configure_result = ducklake
+            .configure_s3_connection("source", &s3_connection)
+            .await;
         assert!(configure_result.is_ok());
 
         let result = ducklake.query("SELECT 1 as test_query");
@@ -977,7 +1955,9 @@ mod tests {
             path_style_access: false,
         });
 
-        let configure_result = ducklake.configure_s3_connection(&s3_connection).await;
+        let configure_result = ducklake
+            .configure_s3_connection("source", &s3_connection)
+            .await;
         assert!(configure_result.is_ok());
 
         let result = ducklake.query("SELECT 1 as test_query");
@@ -1016,7 +1996,7 @@ mod tests {
         });
 
         ducklake
-            .configure_s3_connection(&s3_connection)
+            .configure_s3_connection("source", &s3_connection)
             .await
             .unwrap();
 
@@ -1444,4 +2424,67 @@ mod tests {
             handle.join().expect("Thread failed");
         }
     }
+
+    #[tokio::test]
+    async fn test_run_interruptible() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let catalog_config = CatalogConfig::Sqlite {
+            path: test_dir
+                .path()
+                .join("catalog.sqlite")
+                .to_string_lossy()
+                .to_string(),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: test_dir
+                .path()
+                .join("storage")
+                .to_string_lossy()
+                .to_string(),
+        };
+        let ducklake = DuckLake::new(catalog_config, storage_config).await.unwrap();
+        let slow_sql = "SELECT count(*) FROM range(1000000000000) a";
+
+        let result = ducklake
+            .run_interruptible(Duration::from_secs(10), &CancellationToken::new(), |d| {
+                d.query("SELECT 42")
+            })
+            .await
+            .unwrap();
+        assert_eq!(result, vec![vec!["42".to_string()]]);
+
+        let error = ducklake
+            .run_interruptible(
+                Duration::from_millis(200),
+                &CancellationToken::new(),
+                move |d| d.query(slow_sql),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Interrupted>(),
+            Some(Interrupted::TimedOut(_))
+        ));
+
+        let cancel = CancellationToken::new();
+        let cancel_later = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            cancel_later.cancel();
+        });
+        let error = ducklake
+            .run_interruptible(Duration::from_secs(60), &cancel, move |d| d.query(slow_sql))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Interrupted>(),
+            Some(Interrupted::Cancelled)
+        ));
+
+        // The pool is still usable after interrupting its connections.
+        assert_eq!(
+            ducklake.query("SELECT 1").unwrap(),
+            vec![vec!["1".to_string()]]
+        );
+    }
 }
@@ -1,44 +1,172 @@
 use crate::{
-    config::Config,
+    config::{
+        Config,
+        adapter::ImportMode,
+        data_test::{DataTestConfig, Severity},
+    },
     dependency::Graph,
-    metadata::Metadata,
+    metadata::{ImportState, Metadata},
     pipeline::{
         adapter::Adapter,
         build::{Action, Pipeline},
-        ducklake::DuckLake,
+        data_test::run_data_tests,
+        ducklake::{DuckLake, InterruptScope, Interrupted},
+        file_processor::TimeWindow,
         model::Model,
     },
-    status::StatusManager,
+    redact::redact,
+    status::{Phase, StatusManager},
 };
 use anyhow::Result;
 use chrono::Utc;
 use std::{
     collections::{HashMap, HashSet},
+    future::Future,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    /// Reload incremental adapters from scratch instead of appending new files.
+    pub full_refresh: bool,
+    /// Overrides each adapter's `lookback_days` when bounded.
+    pub window: TimeWindow,
+    /// Nodes that only run when an upstream node of the run changed data, such as the
+    /// downstream of incremental adapters.
+    pub conditional: HashSet<String>,
+    /// Stops the run: running nodes are interrupted and waiting nodes are cancelled.
+    pub cancellation_token: CancellationToken,
+}
 
 struct ExecutionContext {
     project_dir: PathBuf,
     graph: Arc<Graph>,
     config: Arc<Config>,
-    adapters: Arc<HashMap<String, Adapter>>,
-    models: Arc<HashMap<String, Model>>,
+    ducklake: Arc<DuckLake>,
+    node_timeout: Option<Duration>,
+    options: ExecutionOptions,
 }
 
 enum TaskResult {
     Success {
         table_name: String,
         execution_start_time: chrono::DateTime<chrono::Utc>,
+        import_state: Option<ImportState>,
+        /// Whether the node's data may differ from before the run.
+        changed: bool,
     },
     Failed {
         table_name: String,
         error: anyhow::Error,
         execution_time_ms: u64,
+        /// What the import had loaded before the node failed, e.g. on a data test.
+        import_state: Option<ImportState>,
     },
 }
 
+/// Decides which nodes of a run may start. A node is ready as soon as every upstream node in
+/// the same run has finished, and starts once neither the global nor its connection's
+/// concurrency limit is reached.
+struct Scheduler {
+    waiting: Vec<String>,
+    upstream: HashMap<String, Vec<String>>,
+    finished: HashSet<String>,
+    running: HashMap<String, Option<String>>,
+    /// The connection each adapter reads from.
+    connections: HashMap<String, String>,
+    max_concurrency: Option<usize>,
+    connection_concurrency: HashMap<String, usize>,
+}
+
+impl Scheduler {
+    fn new(tables: Vec<String>, graph: &Graph, config: &Config) -> Self {
+        let in_run: HashSet<&String> = tables.iter().collect();
+        let upstream = tables
+            .iter()
+            .map(|table| {
+                let dependencies = graph
+                    .dependencies(table)
+                    .into_iter()
+                    .filter(|dependency| in_run.contains(dependency))
+                    .collect();
+                (table.clone(), dependencies)
+            })
+            .collect();
+        let engine = config.project.engine.clone().unwrap_or_default();
+
+        Self {
+            waiting: tables,
+            upstream,
+            finished: HashSet::new(),
+            running: HashMap::new(),
+            connections: config
+                .adapters
+                .iter()
+                .map(|(name, adapter)| (name.clone(), adapter.connection.clone()))
+                .collect(),
+            max_concurrency: engine.max_concurrency.map(NonZeroUsize::get),
+            connection_concurrency: engine
+                .connection_concurrency
+                .into_iter()
+                .map(|(connection, limit)| (connection, limit.get()))
+                .collect(),
+        }
+    }
+
+    /// Takes the first waiting node, in pipeline order, that may start now.
+    fn next_ready(&mut self) -> Option<String> {
+        if self
+            .max_concurrency
+            .is_some_and(|max| self.running.len() >= max)
+        {
+            return None;
+        }
+
+        let position = self.waiting.iter().position(|table| {
+            self.upstream[table]
+                .iter()
+                .all(|upstream| self.finished.contains(upstream))
+                && self.connection_available(table)
+        })?;
+        Some(self.waiting.remove(position))
+    }
+
+    fn connection_available(&self, table: &str) -> bool {
+        let Some(connection) = self.connections.get(table) else {
+            return true;
+        };
+        let Some(&limit) = self.connection_concurrency.get(connection) else {
+            return true;
+        };
+
+        self.running
+            .values()
+            .filter(|running| running.as_ref() == Some(connection))
+            .count()
+            < limit
+    }
+
+    fn start(&mut self, table: &str) {
+        let connection = self.connections.get(table).cloned();
+        self.running.insert(table.to_string(), connection);
+    }
+
+    /// Records that a node succeeded, failed or was skipped, so its downstream can be decided.
+    fn finish(&mut self, table: &str) {
+        self.running.remove(table);
+        self.finished.insert(table.to_string());
+    }
+
+    fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
 impl Pipeline {
     pub async fn execute(
         &self,
@@ -47,110 +175,191 @@ impl Pipeline {
         ducklake: &DuckLake,
         project_dir: &Path,
     ) -> Result<()> {
-        let shared_ducklake = Arc::new(ducklake.clone());
-
-        let mut adapters = HashMap::new();
-        for (table_name, adapter_config) in &config.adapters {
-            let adapter = Adapter::new(adapter_config.clone(), Arc::clone(&shared_ducklake));
-            adapters.insert(table_name.clone(), adapter);
-        }
-        let shared_adapters = Arc::new(adapters);
+        let mut status_manager = StatusManager::new(project_dir);
+        self.execute_with_status(
+            graph,
+            config,
+            ducklake,
+            project_dir,
+            &mut status_manager,
+            ExecutionOptions::default(),
+        )
+        .await
+    }
 
-        let mut models = HashMap::new();
-        for (table_name, model_config) in &config.models {
-            let model = Model::new(model_config.clone(), Arc::clone(&shared_ducklake));
-            models.insert(table_name.clone(), model);
+    pub async fn execute_with_status(
+        &self,
+        graph: &Graph,
+        config: &Config,
+        ducklake: &DuckLake,
+        project_dir: &Path,
+        status_manager: &mut StatusManager,
+        options: ExecutionOptions,
+    ) -> Result<()> {
+        // The API starts the run before spawning it so the run ID can be polled
+        // right away.
+        if !status_manager.is_started() {
+            status_manager
+                .start(
+                    Utc::now(),
+                    &self
+                        .levels
+                        .iter()
+                        .flatten()
+                        .map(|action| action.table_name.clone())
+                        .collect::<Vec<String>>(),
+                )
+                .await?;
         }
-        let shared_models = Arc::new(models);
-
-        let mut status_manager = StatusManager::new(project_dir);
-        status_manager
-            .start(
-                Utc::now(),
-                &self
-                    .levels
-                    .iter()
-                    .flatten()
-                    .map(|action| action.table_name.clone())
-                    .collect::<Vec<String>>(),
-            )
-            .await?;
 
         let context = ExecutionContext {
             project_dir: project_dir.to_path_buf(),
             graph: Arc::new(graph.clone()),
             config: Arc::new(config.clone()),
-            adapters: shared_adapters,
-            models: shared_models,
+            ducklake: Arc::new(ducklake.clone()),
+            node_timeout: config
+                .project
+                .engine
+                .as_ref()
+                .and_then(|engine| engine.node_timeout()),
+            options,
         };
 
+        let tables = self
+            .all_actions()
+            .into_iter()
+            .map(|action| action.table_name.clone())
+            .collect();
+        let mut scheduler = Scheduler::new(tables, graph, config);
         let mut failed_tasks = HashSet::new();
+        let mut changed_tasks = HashSet::new();
+        let mut tasks = JoinSet::new();
+        let mut running_tasks: HashMap<String, Arc<InterruptScope>> = HashMap::new();
+
+        loop {
+            while !context.options.cancellation_token.is_cancelled() {
+                let Some(table_name) = scheduler.next_ready() else {
+                    break;
+                };
+
+                if failed_tasks.contains(&table_name)
+                    || self.dependency_failed(&table_name, &context.graph, &failed_tasks)
+                {
+                    failed_tasks.insert(table_name.clone());
+                    scheduler.finish(&table_name);
+                    continue;
+                }
+                if !status_manager.is_waiting(&table_name).await? {
+                    scheduler.finish(&table_name);
+                    continue;
+                }
+                if context.options.conditional.contains(&table_name)
+                    && !self.dependency_changed(&table_name, &context.graph, &changed_tasks)
+                {
+                    status_manager.skip_task(&table_name).await?;
+                    scheduler.finish(&table_name);
+                    continue;
+                }
 
-        for level in &self.levels {
-            let mut filtered_level = Vec::new();
-            for action in level.clone().into_iter() {
-                if status_manager.is_waiting(&action.table_name).await? {
-                    filtered_level.push(action);
+                status_manager
+                    .start_tasks(std::slice::from_ref(&table_name))
+                    .await?;
+
+                let action = Action {
+                    table_name: table_name.clone(),
+                };
+                let scope = Arc::new(InterruptScope::default());
+                match self.spawn_task(&action, &context, Arc::clone(&scope)) {
+                    Ok(handle) => {
+                        running_tasks.insert(table_name.clone(), scope);
+                        scheduler.start(&table_name);
+                        tasks.spawn(async move { (table_name, handle.await) });
+                    }
+                    Err(error) => {
+                        self.handle_task_failure(
+                            &table_name,
+                            error,
+                            &context,
+                            status_manager,
+                            &mut failed_tasks,
+                        )
+                        .await?;
+                        scheduler.finish(&table_name);
+                    }
                 }
             }
-            let level = filtered_level;
 
-            status_manager
-                .start_tasks(
-                    &level
-                        .iter()
-                        .map(|action| action.table_name.clone())
-                        .collect::<Vec<String>>(),
-                )
-                .await?;
+            if !scheduler.is_running() {
+                break;
+            }
 
-            let results = self
-                .execute_level(&level, &context, &mut failed_tasks)
-                .await?;
+            let joined = tokio::select! {
+                joined = tasks.join_next() => joined,
+                _ = context.options.cancellation_token.cancelled() => break,
+            };
+            let Some(Ok((table_name, joined))) = joined else {
+                break;
+            };
+
+            running_tasks.remove(&table_name);
+            let result = joined.unwrap_or_else(|join_error| TaskResult::Failed {
+                table_name: table_name.clone(),
+                error: anyhow::anyhow!("Task join error: {join_error}"),
+                execution_time_ms: 0,
+                import_state: None,
+            });
+            self.record_result(
+                result,
+                &context,
+                status_manager,
+                &mut failed_tasks,
+                &mut changed_tasks,
+            )
+            .await?;
+            scheduler.finish(&table_name);
+        }
 
-            for result in results {
-                match result {
-                    TaskResult::Success {
-                        table_name,
-                        execution_start_time,
-                    } => {
-                        status_manager.complete_task(&table_name).await?;
+        if context.options.cancellation_token.is_cancelled() {
+            for scope in running_tasks.values() {
+                scope.interrupt();
+            }
 
-                        if let Err(e) = crate::dependency::update_node_timestamp(
-                            &context.project_dir,
-                            &table_name,
-                            execution_start_time,
+            // Interrupted nodes may have written rows before they stopped, so their
+            // results are still recorded.
+            while let Some(joined) = tasks.join_next().await {
+                let Ok((table_name, joined)) = joined else {
+                    continue;
+                };
+                match joined {
+                    Ok(result @ TaskResult::Success { .. }) => {
+                        self.record_result(
+                            result,
+                            &context,
+                            status_manager,
+                            &mut failed_tasks,
+                            &mut changed_tasks,
                         )
-                        .await
-                        {
-                            eprintln!("Failed to update timestamp for {table_name}: {e}");
-                        }
+                        .await?;
                     }
-                    TaskResult::Failed {
-                        table_name,
-                        error,
-                        execution_time_ms,
-                    } => {
-                        eprintln!(
-                            "Task failed for table '{table_name}': {error} ({execution_time_ms}ms)"
-                        );
-
-                        status_manager
-                            .fail_task(&table_name, error.to_string())
-                            .await?;
-
-                        failed_tasks.insert(table_name.clone());
-                        self.mark_downstream_as_failed(
-                            &table_name,
-                            &context.graph,
-                            &mut failed_tasks,
-                        );
+                    Ok(TaskResult::Failed { import_state, .. }) => {
+                        if let Some(import_state) = import_state {
+                            record_appended_files(&context, &table_name, &import_state).await;
+                        }
+                        status_manager.cancel_task(&table_name).await?;
                     }
+                    Err(_) => status_manager.cancel_task(&table_name).await?,
                 }
             }
+
+            status_manager.cancel_waiting_tasks().await?;
+            status_manager.finish(Phase::Cancelled).await?;
+
+            return Err(anyhow::anyhow!("Pipeline execution was cancelled"));
         }
 
         if !failed_tasks.is_empty() {
+            status_manager.finish(Phase::Failed).await?;
+
             let error_message =
                 format!("Pipeline execution had {} failed tasks", failed_tasks.len());
 
@@ -164,45 +373,119 @@ impl Pipeline {
         }
         metadata.save(project_dir).await?;
 
+        status_manager.finish(Phase::Completed).await?;
+
         Ok(())
     }
 
-    async fn execute_level(
+    async fn record_result(
         &self,
-        actions: &[Action],
+        result: TaskResult,
         context: &ExecutionContext,
+        status_manager: &mut StatusManager,
         failed_tasks: &mut HashSet<String>,
-    ) -> Result<Vec<TaskResult>> {
-        let mut task_handles = Vec::new();
+        changed_tasks: &mut HashSet<String>,
+    ) -> Result<()> {
+        match result {
+            TaskResult::Success {
+                table_name,
+                execution_start_time,
+                import_state,
+                changed,
+            } => {
+                if let Some(import_state) = import_state
+                    && let Err(e) = crate::dependency::update_import_state(
+                        &context.project_dir,
+                        &table_name,
+                        import_state,
+                    )
+                    .await
+                {
+                    eprintln!("Failed to record import state for {table_name}: {e}");
+                }
 
-        for action in actions {
-            if failed_tasks.contains(&action.table_name) {
-                continue;
-            }
+                status_manager.complete_task(&table_name).await?;
+                if changed {
+                    changed_tasks.insert(table_name.clone());
+                }
+
+                if let Err(e) = crate::dependency::update_node_timestamp(
+                    &context.project_dir,
+                    &table_name,
+                    execution_start_time,
+                )
+                .await
+                {
+                    eprintln!("Failed to update timestamp for {table_name}: {e}");
+                }
 
-            let dependency_failed =
-                self.dependency_failed(&action.table_name, &context.graph, failed_tasks);
-            if dependency_failed {
-                failed_tasks.insert(action.table_name.clone());
-                continue;
+                if let Err(e) = crate::dependency::update_node_config(
+                    &context.project_dir,
+                    &table_name,
+                    &context.config,
+                )
+                .await
+                {
+                    eprintln!("Failed to update config snapshot for {table_name}: {e}");
+                }
             }
+            TaskResult::Failed {
+                table_name,
+                error,
+                execution_time_ms,
+                import_state,
+            } => {
+                eprintln!(
+                    "Task failed for table '{table_name}': {} ({execution_time_ms}ms)",
+                    redact(&error.to_string())
+                );
+
+                if let Some(import_state) = import_state {
+                    record_appended_files(context, &table_name, &import_state).await;
+                }
 
-            let handle = self.spawn_task(action, context)?;
-            task_handles.push(handle);
+                self.handle_task_failure(&table_name, error, context, status_manager, failed_tasks)
+                    .await?;
+            }
         }
 
-        let mut results = Vec::new();
-        for handle in task_handles {
-            match handle.await {
-                Ok(result) => results.push(result),
-                Err(join_error) => {
-                    let error_message = format!("Task join error: {join_error}");
-                    eprintln!("{error_message}");
-                }
-            }
+        Ok(())
+    }
+
+    async fn handle_task_failure(
+        &self,
+        table_name: &str,
+        error: anyhow::Error,
+        context: &ExecutionContext,
+        status_manager: &mut StatusManager,
+        failed_tasks: &mut HashSet<String>,
+    ) -> Result<()> {
+        status_manager
+            .fail_task(table_name, error.to_string())
+            .await?;
+
+        if let Err(e) =
+            crate::dependency::clear_node_timestamp(&context.project_dir, table_name).await
+        {
+            eprintln!("Failed to clear timestamp for {table_name}: {e}");
         }
 
-        Ok(results)
+        failed_tasks.insert(table_name.to_string());
+        self.mark_downstream_as_failed(table_name, &context.graph, failed_tasks);
+
+        Ok(())
+    }
+
+    fn dependency_changed(
+        &self,
+        table_name: &str,
+        graph: &Graph,
+        changed_tasks: &HashSet<String>,
+    ) -> bool {
+        graph
+            .edges
+            .iter()
+            .any(|edge| edge.to == table_name && changed_tasks.contains(&edge.from))
     }
 
     fn dependency_failed(
@@ -219,61 +502,129 @@ impl Pipeline {
         false
     }
 
+    /// Spawns the node's work, including its data tests, on a `DuckLake` tracked by `scope`,
+    /// so its statements can be interrupted on cancellation or when it exceeds the node
+    /// timeout.
     fn spawn_task(
         &self,
         action: &Action,
         context: &ExecutionContext,
+        scope: Arc<InterruptScope>,
     ) -> Result<JoinHandle<TaskResult>> {
         let table_name = action.table_name.clone();
         let connections = context.config.project.connections.clone();
         let project_dir = context.project_dir.clone();
-
-        if let Some(adapter) = context.adapters.get(&action.table_name).cloned() {
-            Ok(tokio::spawn(async move {
-                let start_time = std::time::Instant::now();
-                let execution_start_time = chrono::Utc::now();
-                match adapter
-                    .execute_import(&table_name, Some(&connections))
-                    .await
-                {
-                    Ok(_) => TaskResult::Success {
-                        table_name,
-                        execution_start_time,
-                    },
-                    Err(error) => TaskResult::Failed {
-                        table_name,
-                        error,
-                        execution_time_ms: start_time.elapsed().as_millis() as u64,
-                    },
-                }
-            }))
-        } else if let Some(model) = context.models.get(&action.table_name).cloned() {
+        let ducklake = Arc::new(context.ducklake.with_interrupt_scope(Arc::clone(&scope)));
+
+        if let Some(adapter_config) = context.config.adapters.get(&action.table_name) {
+            let adapter = Adapter::new(adapter_config.clone(), Arc::clone(&ducklake));
+            let tests = adapter_config.tests.clone();
+            let options = context.options.clone();
+            let incremental = adapter_config.mode == ImportMode::Incremental;
+
+            Ok(spawn_with_timeout(
+                table_name.clone(),
+                scope,
+                context.node_timeout,
+                async move {
+                    let start_time = std::time::Instant::now();
+                    let execution_start_time = chrono::Utc::now();
+
+                    let previous_state =
+                        crate::dependency::get_import_state(&project_dir, &table_name)
+                            .await
+                            .unwrap_or_default();
+
+                    let result = adapter
+                        .execute_import(&table_name, Some(&connections), &previous_state, &options)
+                        .await
+                        .map(|import_state| {
+                            let tested = check_data_tests(&ducklake, &table_name, &tests);
+                            (import_state, tested)
+                        });
+
+                    match result {
+                        Ok((import_state, Ok(()))) => TaskResult::Success {
+                            table_name,
+                            execution_start_time,
+                            changed: !incremental
+                                || options.full_refresh
+                                || import_state != previous_state,
+                            import_state: Some(import_state),
+                        },
+                        Ok((import_state, Err(error))) => TaskResult::Failed {
+                            table_name,
+                            error,
+                            execution_time_ms: start_time.elapsed().as_millis() as u64,
+                            import_state: Some(import_state),
+                        },
+                        Err(error) => TaskResult::Failed {
+                            table_name,
+                            error,
+                            execution_time_ms: start_time.elapsed().as_millis() as u64,
+                            import_state: None,
+                        },
+                    }
+                },
+            ))
+        } else if let Some(model_config) = context.config.models.get(&action.table_name) {
+            let model = Model::new(model_config.clone(), Arc::clone(&ducklake));
+            let tests = model_config.tests.clone();
             let graph = Arc::clone(&context.graph);
             let table_name_for_deps = table_name.clone();
+            let model_config = model_config.clone();
+            let mut options = context.options.clone();
+
+            Ok(spawn_with_timeout(
+                table_name.clone(),
+                scope,
+                context.node_timeout,
+                async move {
+                    let start_time = std::time::Instant::now();
+
+                    let dependency_timestamp = crate::dependency::get_oldest_dependency_timestamp(
+                        &project_dir,
+                        &table_name_for_deps,
+                        &graph,
+                    )
+                    .await
+                    .unwrap_or(None);
+
+                    // An incremental model whose SQL changed can't append to the table the
+                    // old SQL built.
+                    if crate::dependency::model_config_changed(
+                        &project_dir,
+                        &table_name,
+                        &model_config,
+                    )
+                    .await
+                    .unwrap_or(false)
+                    {
+                        options.full_refresh = true;
+                    }
 
-            Ok(tokio::spawn(async move {
-                let start_time = std::time::Instant::now();
-
-                let dependency_timestamp = crate::dependency::get_oldest_dependency_timestamp(
-                    &project_dir,
-                    &table_name_for_deps,
-                    &graph,
-                )
-                .await
-                .unwrap_or(None);
-
-                match model.execute_transform(&table_name).await {
-                    Ok(_) => TaskResult::Success {
-                        table_name,
-                        execution_start_time: dependency_timestamp.unwrap_or(chrono::Utc::now()),
-                    },
-                    Err(error) => TaskResult::Failed {
-                        table_name,
-                        error,
-                        execution_time_ms: start_time.elapsed().as_millis() as u64,
-                    },
-                }
-            }))
+                    let result = model
+                        .execute_transform(&table_name, &options)
+                        .await
+                        .and_then(|_| check_data_tests(&ducklake, &table_name, &tests));
+
+                    match result {
+                        Ok(_) => TaskResult::Success {
+                            table_name,
+                            execution_start_time: dependency_timestamp
+                                .unwrap_or(chrono::Utc::now()),
+                            import_state: None,
+                            changed: true,
+                        },
+                        Err(error) => TaskResult::Failed {
+                            table_name,
+                            error,
+                            execution_time_ms: start_time.elapsed().as_millis() as u64,
+                            import_state: None,
+                        },
+                    }
+                },
+            ))
         } else {
             Err(anyhow::anyhow!(
                 "Table '{}' not found in adapters or models",
@@ -302,6 +653,97 @@ impl Pipeline {
     }
 }
 
+/// The files a failed or cancelled run appended stay in the table, so the next run must
+/// not append them again.
+async fn record_appended_files(
+    context: &ExecutionContext,
+    table_name: &str,
+    import_state: &ImportState,
+) {
+    if let Err(e) =
+        crate::dependency::record_appended_files(&context.project_dir, table_name, import_state)
+            .await
+    {
+        eprintln!("Failed to record appended files for {table_name}: {e}");
+    }
+}
+
+/// Runs the data tests declared on a freshly built node. Warnings are only reported;
+/// any error-level failure fails the node.
+fn check_data_tests(ducklake: &DuckLake, table_name: &str, tests: &[DataTestConfig]) -> Result<()> {
+    let mut errors = Vec::new();
+    for failure in run_data_tests(ducklake, table_name, tests) {
+        match failure.severity {
+            Severity::Warn => eprintln!(
+                "Warning: data test {} failed for table '{table_name}': {}",
+                failure.test, failure.message
+            ),
+            Severity::Error => errors.push(format!("{}: {}", failure.test, failure.message)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("Data tests failed: {}", errors.join("; ")));
+    }
+
+    Ok(())
+}
+
+/// Spawns a node's work. If it runs longer than `timeout`, its statements are interrupted
+/// and it fails with [`Interrupted::TimedOut`].
+///
+/// The work makes blocking DuckDB calls, so it runs on a blocking thread like
+/// [`DuckLake::run_interruptible`] does; otherwise it could hold the worker thread the
+/// watchdog needs.
+fn spawn_with_timeout(
+    table_name: String,
+    scope: Arc<InterruptScope>,
+    timeout: Option<Duration>,
+    work: impl Future<Output = TaskResult> + Send + 'static,
+) -> JoinHandle<TaskResult> {
+    tokio::spawn(async move {
+        let watchdog = timeout.map(|timeout| {
+            let scope = Arc::clone(&scope);
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                scope.interrupt();
+            })
+        });
+
+        let start_time = std::time::Instant::now();
+        let runtime = tokio::runtime::Handle::current();
+        let result = tokio::task::spawn_blocking(move || runtime.block_on(work))
+            .await
+            .unwrap_or_else(|join_error| TaskResult::Failed {
+                table_name,
+                error: anyhow::anyhow!("Node work did not finish: {join_error}"),
+                execution_time_ms: start_time.elapsed().as_millis() as u64,
+                import_state: None,
+            });
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+
+        match (result, timeout) {
+            (
+                TaskResult::Failed {
+                    table_name,
+                    execution_time_ms,
+                    import_state,
+                    ..
+                },
+                Some(timeout),
+            ) if scope.is_interrupted() => TaskResult::Failed {
+                table_name,
+                error: Interrupted::TimedOut(timeout).into(),
+                execution_time_ms,
+                import_state,
+            },
+            (result, _) => result,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,6 +752,223 @@ mod tests {
         pipeline::build::Pipeline,
     };
 
+    fn scheduler(tables: &[&str], edges: &[(&str, &str)]) -> Scheduler {
+        let graph = Graph {
+            nodes: tables
+                .iter()
+                .map(|table| Node {
+                    name: table.to_string(),
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|(from, to)| Edge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+                .collect(),
+        };
+        let tables = tables.iter().map(|table| table.to_string()).collect();
+        Scheduler::new(tables, &graph, &Config::new(PathBuf::new()))
+    }
+
+    #[test]
+    fn test_scheduler_starts_nodes_when_their_upstream_finishes() {
+        let mut scheduler = scheduler(
+            &["slow_adapter", "fast_adapter", "model_a", "model_b"],
+            &[("slow_adapter", "model_a"), ("fast_adapter", "model_b")],
+        );
+
+        assert_eq!(scheduler.next_ready().as_deref(), Some("slow_adapter"));
+        scheduler.start("slow_adapter");
+        assert_eq!(scheduler.next_ready().as_deref(), Some("fast_adapter"));
+        scheduler.start("fast_adapter");
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.finish("fast_adapter");
+        assert_eq!(scheduler.next_ready().as_deref(), Some("model_b"));
+        scheduler.start("model_b");
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.finish("slow_adapter");
+        assert_eq!(scheduler.next_ready().as_deref(), Some("model_a"));
+    }
+
+    #[test]
+    fn test_scheduler_concurrency_limits() {
+        let mut scheduler = scheduler(&["pg_a", "pg_b", "pg_c", "csv_a"], &[]);
+        scheduler.connections = HashMap::from([
+            ("pg_a".to_string(), "warehouse".to_string()),
+            ("pg_b".to_string(), "warehouse".to_string()),
+            ("pg_c".to_string(), "warehouse".to_string()),
+            ("csv_a".to_string(), "files".to_string()),
+        ]);
+        scheduler.max_concurrency = Some(3);
+        scheduler.connection_concurrency = HashMap::from([("warehouse".to_string(), 2)]);
+
+        for table in ["pg_a", "pg_b", "csv_a"] {
+            assert_eq!(scheduler.next_ready().as_deref(), Some(table));
+            scheduler.start(table);
+        }
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.finish("csv_a");
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.finish("pg_a");
+        assert_eq!(scheduler.next_ready().as_deref(), Some("pg_c"));
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout_interrupts_blocking_work() {
+        let scope = Arc::new(InterruptScope::default());
+        let work_scope = Arc::clone(&scope);
+        let timeout = Duration::from_millis(100);
+
+        let handle = spawn_with_timeout(
+            "slow_import".to_string(),
+            scope,
+            Some(timeout),
+            async move {
+                // Holds the thread until interrupted, like a long DuckDB statement.
+                while !work_scope.is_interrupted() {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                TaskResult::Failed {
+                    table_name: "slow_import".to_string(),
+                    error: anyhow::anyhow!("Interrupted"),
+                    execution_time_ms: 0,
+                    import_state: None,
+                }
+            },
+        );
+
+        let result = tokio::time::timeout(Duration::from_secs(10), handle)
+            .await
+            .expect("node timeout did not interrupt the work")
+            .unwrap();
+        let TaskResult::Failed { error, .. } = result else {
+            panic!("timed out node should fail");
+        };
+        assert_eq!(
+            error.downcast_ref::<Interrupted>(),
+            Some(&Interrupted::TimedOut(timeout))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_panicking_node_work_fails_the_node() {
+        let scope = Arc::new(InterruptScope::default());
+        let handle = spawn_with_timeout("broken".to_string(), scope, None, async {
+            panic!("node work panicked")
+        });
+
+        let TaskResult::Failed {
+            table_name, error, ..
+        } = handle.await.unwrap()
+        else {
+            panic!("panicking node should fail");
+        };
+        assert_eq!(table_name, "broken");
+        assert!(
+            error.to_string().contains("Node work did not finish"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_incremental_adapter_is_not_appended_again() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path().to_path_buf();
+        let data_dir = project_dir.join("data");
+        std::fs::create_dir_all(&data_dir)?;
+        std::fs::write(data_dir.join("day1.csv"), "id,name\n1,alice\n")?;
+
+        let mut config = Config::new(project_dir.clone());
+        config.project.storage = crate::config::project::StorageConfig::LocalFile {
+            path: project_dir.join("storage").to_string_lossy().to_string(),
+        };
+        config.project.database.path = Some(
+            project_dir
+                .join("catalog.sqlite")
+                .to_string_lossy()
+                .to_string(),
+        );
+        let adapter = crate::config::adapter::parse_adapter_config(&format!(
+            "connection: local\nmode: incremental\ncolumns: []\nsource:\n  type: file\n  file:\n    path: {}/*.csv\n  format:\n    type: csv\n    has_header: true",
+            data_dir.display()
+        ))?;
+        config.adapters.insert("events".to_string(), adapter);
+
+        let graph = Graph {
+            nodes: vec![Node {
+                name: "events".to_string(),
+            }],
+            edges: vec![],
+        };
+        let pipeline = Arc::new(Pipeline::from_graph(&graph)?);
+        let ducklake = DuckLake::from_config(&config).await?;
+        let run = |config: Config, options: ExecutionOptions| {
+            let (pipeline, graph, ducklake) =
+                (Arc::clone(&pipeline), graph.clone(), ducklake.clone());
+            tokio::spawn(async move {
+                let mut status_manager = StatusManager::new(&config.project_dir);
+                pipeline
+                    .execute_with_status(
+                        &graph,
+                        &config,
+                        &ducklake,
+                        &config.project_dir,
+                        &mut status_manager,
+                        options,
+                    )
+                    .await
+            })
+        };
+        let count = || -> Result<String> {
+            Ok(ducklake.query("SELECT COUNT(*) FROM events")?[0][0].clone())
+        };
+
+        run(config.clone(), ExecutionOptions::default()).await??;
+        assert_eq!(count()?, "1");
+
+        // The data test keeps the node busy after day2 is appended, until it is cancelled.
+        std::fs::write(data_dir.join("day2.csv"), "id,name\n2,bob\n")?;
+        let mut slow_config = config.clone();
+        slow_config.adapters.get_mut("events").unwrap().tests = vec![DataTestConfig {
+            check: crate::config::data_test::DataCheck::Sql {
+                sql: "SELECT * FROM range(1000000000000) WHERE range < 0".to_string(),
+            },
+            severity: Severity::Error,
+        }];
+        let options = ExecutionOptions::default();
+        let cancellation_token = options.cancellation_token.clone();
+        let cancelled = run(slow_config, options);
+
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while count().ok().as_deref() != Some("2") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+        cancellation_token.cancel();
+        assert!(cancelled.await?.is_err());
+
+        let metadata = Metadata::load(&project_dir).await?;
+        let state = &metadata.get_node("events").unwrap().import_state;
+        assert!(
+            state
+                .appended_files
+                .iter()
+                .any(|file| file.ends_with("day2.csv"))
+        );
+
+        run(config, ExecutionOptions::default()).await??;
+        assert_eq!(count()?, "2");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_parallel_execution_basic() -> Result<()> {
         let graph = Graph {
@@ -339,7 +998,7 @@ mod tests {
             ],
         };
 
-        let pipeline = Pipeline::from_graph(&graph);
+        let pipeline = Pipeline::from_graph(&graph).unwrap();
 
         assert_eq!(pipeline.levels.len(), 2);
         assert_eq!(pipeline.levels[0].len(), 2);
@@ -455,6 +1114,10 @@ mod tests {
         failed_tasks.insert("B".to_string());
         assert!(pipeline.dependency_failed("C", &graph, &failed_tasks));
 
+        let changed_tasks = HashSet::from(["A".to_string()]);
+        assert!(pipeline.dependency_changed("B", &graph, &changed_tasks));
+        assert!(!pipeline.dependency_changed("C", &graph, &changed_tasks));
+
         Ok(())
     }
 
@@ -487,7 +1150,7 @@ mod tests {
             ],
         };
 
-        let pipeline = Pipeline::from_graph(&graph);
+        let pipeline = Pipeline::from_graph(&graph).unwrap();
 
         assert_eq!(pipeline.levels.len(), 2);
         assert_eq!(pipeline.levels[0].len(), 2);
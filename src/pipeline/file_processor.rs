@@ -1,11 +1,32 @@
 use crate::{
     config::{adapter::AdapterConfig, project::ConnectionConfig},
+    metadata::LoadedFile,
     s3_client,
 };
 use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeDelta, Timelike, Utc};
 use regex::Regex;
 use std::path::Path;
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub path: String,
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+    pub etag: Option<String>,
+    pub partition_time: Option<DateTime<Utc>>,
+}
+
+impl FileEntry {
+    pub fn fingerprint(&self) -> LoadedFile {
+        LoadedFile {
+            size: self.size,
+            modified: self.modified,
+            etag: self.etag.clone(),
+        }
+    }
+}
+
 pub enum FileSystem {
     Local { base_path: Option<String> },
     S3 { client: s3_client::S3Client },
@@ -40,6 +61,15 @@ impl FileSystem {
     }
 
     pub async fn list_files(&self, pattern: &str) -> Result<Vec<String>> {
+        Ok(self
+            .list_file_entries(pattern)
+            .await?
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect())
+    }
+
+    pub async fn list_file_entries(&self, pattern: &str) -> Result<Vec<FileEntry>> {
         match self {
             Self::Local { base_path } => {
                 let resolved_pattern = if let Some(base) = base_path {
@@ -64,13 +94,189 @@ impl FileSystem {
                     existing_paths.push(resolved_pattern);
                 }
 
-                Ok(existing_paths)
+                Ok(existing_paths
+                    .into_iter()
+                    .map(|path| {
+                        let metadata = std::fs::metadata(&path).ok();
+                        FileEntry {
+                            size: metadata.as_ref().map(|m| m.len()),
+                            modified: metadata
+                                .and_then(|m| m.modified().ok())
+                                .map(DateTime::<Utc>::from),
+                            etag: None,
+                            partition_time: None,
+                            path,
+                        }
+                    })
+                    .collect())
             }
-            Self::S3 { client } => client.list_objects_matching_pattern(pattern).await,
+            Self::S3 { client } => Ok(client
+                .list_object_entries_matching_pattern(pattern)
+                .await?
+                .into_iter()
+                .map(|object| FileEntry {
+                    path: object.key,
+                    size: object.size,
+                    modified: object.last_modified,
+                    etag: object.etag,
+                    partition_time: None,
+                })
+                .collect()),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DateToken {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+}
+
+/// A file path pattern containing `{YYYY}`, `{MM}`, `{DD}`, `{HH}` or `{mm}` placeholders.
+#[derive(Debug, Clone)]
+pub struct DatePattern {
+    regex: Regex,
+    tokens: Vec<DateToken>,
+}
+
+impl DatePattern {
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let token_re = Regex::new(r"\{(YYYY|MM|DD|HH|mm)\}").unwrap();
+
+        let mut tokens = Vec::new();
+        let mut regex = String::new();
+        let mut last = 0;
+        for captures in token_re.captures_iter(pattern) {
+            let token = captures.get(0).unwrap();
+            regex.push_str(&Self::glob_to_regex(&pattern[last..token.start()]));
+
+            let (token, digits) = match &captures[1] {
+                "YYYY" => (DateToken::Year, 4),
+                "MM" => (DateToken::Month, 2),
+                "DD" => (DateToken::Day, 2),
+                "HH" => (DateToken::Hour, 2),
+                _ => (DateToken::Minute, 2),
+            };
+            tokens.push(token);
+            regex.push_str(&format!(r"(\d{{{digits}}})"));
+            last = captures.get(0).unwrap().end();
+        }
+
+        if tokens.is_empty() {
+            return None;
+        }
+
+        regex.push_str(&Self::glob_to_regex(&pattern[last..]));
+        regex.push('$');
+
+        Some(Self {
+            regex: Regex::new(&regex).ok()?,
+            tokens,
+        })
+    }
+
+    fn glob_to_regex(glob: &str) -> String {
+        regex::escape(glob)
+            .replace(r"\*\*", ".*")
+            .replace(r"\*", "[^/]*")
+            .replace(r"\?", "[^/]")
+    }
+
+    /// Returns the start of the partition encoded in `path`. Missing parts default to the
+    /// start of the period, and repeated placeholders must agree.
+    pub fn extract(&self, path: &str) -> Option<DateTime<Utc>> {
+        let captures = self.regex.captures(path)?;
+
+        let mut parts: [Option<u32>; 5] = [None; 5];
+        for (index, token) in self.tokens.iter().enumerate() {
+            let value: u32 = captures.get(index + 1)?.as_str().parse().ok()?;
+            let slot = &mut parts[*token as usize];
+            if slot.is_some_and(|existing| existing != value) {
+                return None;
+            }
+            *slot = Some(value);
+        }
+
+        let [year, month, day, hour, minute] = parts;
+        NaiveDate::from_ymd_opt(year? as i32, month.unwrap_or(1), day.unwrap_or(1))?
+            .and_hms_opt(hour.unwrap_or(0), minute.unwrap_or(0), 0)
+            .map(|datetime| datetime.and_utc())
+    }
+
+    /// Returns the start of the partition containing `time`, based on the finest placeholder
+    /// in the pattern.
+    pub fn partition_start(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let finest = *self.tokens.iter().max()?;
+        let month = if finest >= DateToken::Month {
+            time.month()
+        } else {
+            1
+        };
+        let day = if finest >= DateToken::Day {
+            time.day()
+        } else {
+            1
+        };
+        let hour = if finest >= DateToken::Hour {
+            time.hour()
+        } else {
+            0
+        };
+        let minute = if finest >= DateToken::Minute {
+            time.minute()
+        } else {
+            0
+        };
+
+        NaiveDate::from_ymd_opt(time.year(), month, day)?
+            .and_hms_opt(hour, minute, 0)
+            .map(|datetime| datetime.and_utc())
+    }
+
+    /// Returns the exclusive end of the partition starting at `start`, based on the finest
+    /// placeholder in the pattern.
+    pub fn partition_end(&self, start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.tokens.iter().max()? {
+            DateToken::Year => start.checked_add_months(Months::new(12)),
+            DateToken::Month => start.checked_add_months(Months::new(1)),
+            DateToken::Day => start.checked_add_signed(TimeDelta::days(1)),
+            DateToken::Hour => start.checked_add_signed(TimeDelta::hours(1)),
+            DateToken::Minute => start.checked_add_signed(TimeDelta::minutes(1)),
+        }
+    }
+}
+
+/// A half-open `[from, to)` time range used to select date-partitioned files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl TimeWindow {
+    pub fn new(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Self> {
+        if let (Some(from), Some(to)) = (from, to)
+            && from >= to
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid time window: '{from}' must be before '{to}'"
+            ));
+        }
+        Ok(Self { from, to })
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| end > from) && self.to.is_none_or(|to| start < to)
+    }
+}
+
 pub struct FileProcessor;
 
 impl FileProcessor {
@@ -92,20 +298,75 @@ impl FileProcessor {
     pub async fn find_matching_files(
         pattern: &str,
         filesystem: &FileSystem,
-    ) -> Result<Vec<String>> {
-        let pattern = if Self::has_date_pattern(pattern) {
-            Self::convert_date_pattern_to_wildcard(pattern)
-        } else {
-            pattern.to_string()
+    ) -> Result<Vec<FileEntry>> {
+        let legacy_re = Regex::new(r"<(YYYY|MM|DD|HH|mm)>").unwrap();
+        if let Some(legacy) = legacy_re.captures(pattern) {
+            return Err(anyhow::anyhow!(
+                "Unsupported date placeholder '{}' in '{}', use '{{{}}}' instead",
+                &legacy[0],
+                pattern,
+                &legacy[1]
+            ));
+        }
+
+        let Some(date_pattern) = DatePattern::parse(pattern) else {
+            return filesystem.list_file_entries(pattern).await;
         };
 
-        filesystem.list_files(&pattern).await
+        let entries = filesystem
+            .list_file_entries(&Self::convert_date_pattern_to_wildcard(pattern))
+            .await?;
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|mut entry| {
+                entry.partition_time = Some(date_pattern.extract(&entry.path)?);
+                Some(entry)
+            })
+            .collect())
+    }
+
+    pub async fn files_in_window(
+        adapter: &AdapterConfig,
+        filesystem: &FileSystem,
+        window: &TimeWindow,
+    ) -> Result<Vec<FileEntry>> {
+        let files = Self::files_for_processing(adapter, filesystem).await?;
+        if window.is_unbounded() {
+            return Ok(files);
+        }
+
+        let Some(date_pattern) = Self::date_pattern(adapter) else {
+            return Err(anyhow::anyhow!(
+                "A time window requires date placeholders such as {{YYYY}} in the file path"
+            ));
+        };
+
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                file.partition_time.is_some_and(|start| {
+                    date_pattern
+                        .partition_end(start)
+                        .is_some_and(|end| window.overlaps(start, end))
+                })
+            })
+            .collect())
+    }
+
+    pub fn date_pattern(adapter: &AdapterConfig) -> Option<DatePattern> {
+        match &adapter.source {
+            crate::config::adapter::AdapterSource::File { file, .. } => {
+                DatePattern::parse(&file.path)
+            }
+            _ => None,
+        }
     }
 
     pub async fn files_for_processing(
         adapter: &AdapterConfig,
         filesystem: &FileSystem,
-    ) -> Result<Vec<String>> {
+    ) -> Result<Vec<FileEntry>> {
         let pattern = match &adapter.source {
             crate::config::adapter::AdapterSource::File { file, .. } => &file.path,
             _ => {
@@ -118,6 +379,36 @@ impl FileProcessor {
         Self::find_matching_files(pattern, filesystem).await
     }
 
+    pub fn batch_files(files: Vec<FileEntry>, max_batch_size: Option<u64>) -> Vec<Vec<FileEntry>> {
+        let Some(max_batch_size) = max_batch_size else {
+            return if files.is_empty() {
+                Vec::new()
+            } else {
+                vec![files]
+            };
+        };
+
+        let mut batches = Vec::new();
+        let mut current_batch: Vec<FileEntry> = Vec::new();
+        let mut current_size = 0;
+
+        for file in files {
+            let size = file.size.unwrap_or(0);
+            if !current_batch.is_empty() && current_size + size > max_batch_size {
+                batches.push(std::mem::take(&mut current_batch));
+                current_size = 0;
+            }
+            current_size += size;
+            current_batch.push(file);
+        }
+
+        if !current_batch.is_empty() {
+            batches.push(current_batch);
+        }
+
+        batches
+    }
+
     fn has_date_pattern(pattern: &str) -> bool {
         pattern.contains("{YYYY}")
             || pattern.contains("{MM}")
@@ -139,22 +430,47 @@ impl FileProcessor {
     }
 }
 
+pub fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let split_at = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid size '{value}'"))?;
+    let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "K" => 1024,
+        "MB" | "M" => 1024 * 1024,
+        "GB" | "G" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow::anyhow!("Invalid size unit in '{value}'")),
+    };
+
+    Ok((number * multiplier as f64) as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::TempDir;
 
     use super::*;
-    use crate::config::adapter::{FileConfig, FormatConfig};
+    use crate::config::adapter::{FileConfig, FormatConfig, ImportMode};
 
     fn create_test_adapter(path: &str) -> AdapterConfig {
         AdapterConfig {
             connection: "test".to_string(),
+            mode: ImportMode::Full,
+            tests: vec![],
             description: None,
             source: crate::config::adapter::AdapterSource::File {
                 file: FileConfig {
                     path: path.to_string(),
                     compression: None,
                     max_batch_size: None,
+                    lookback_days: None,
+                    partition_column: None,
                 },
                 format: FormatConfig {
                     ty: "csv".to_string(),
@@ -232,6 +548,174 @@ mod tests {
         )
         .await;
 
-        assert_eq!(result.unwrap(), vec![format!("{tmppath}/users.csv")]);
+        let paths: Vec<String> = result.unwrap().into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, vec![format!("{tmppath}/users.csv")]);
+    }
+
+    #[rstest::rstest]
+    #[case("1024", 1024)]
+    #[case("10B", 10)]
+    #[case("2KB", 2048)]
+    #[case("100MB", 100 * 1024 * 1024)]
+    #[case("1.5 GB", 1024 * 1024 * 1536)]
+    #[case("3m", 3 * 1024 * 1024)]
+    fn test_parse_size(#[case] input: &str, #[case] expected: u64) {
+        assert_eq!(parse_size(input).unwrap(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("MB")]
+    #[case("10TB")]
+    fn test_parse_size_invalid(#[case] input: &str) {
+        assert!(parse_size(input).is_err());
+    }
+
+    #[test]
+    fn test_batch_files() {
+        let file = |path: &str, size: u64| FileEntry {
+            path: path.to_string(),
+            size: Some(size),
+            modified: None,
+            etag: None,
+            partition_time: None,
+        };
+        let files = vec![file("a", 40), file("b", 50), file("c", 200), file("d", 10)];
+
+        let paths = |batches: Vec<Vec<FileEntry>>| -> Vec<Vec<String>> {
+            batches
+                .into_iter()
+                .map(|batch| batch.into_iter().map(|f| f.path).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            paths(FileProcessor::batch_files(files.clone(), None)),
+            vec![vec!["a", "b", "c", "d"]]
+        );
+        assert_eq!(
+            paths(FileProcessor::batch_files(files, Some(100))),
+            vec![vec!["a", "b"], vec!["c"], vec!["d"]]
+        );
+        assert!(FileProcessor::batch_files(vec![], Some(100)).is_empty());
+    }
+
+    #[rstest::rstest]
+    #[case(
+        "logs/{YYYY}/{MM}/{DD}/*.json",
+        "logs/2024/03/15/app.json",
+        Some("2024-03-15T00:00:00Z")
+    )]
+    #[case(
+        "logs/{YYYY}{MM}{DD}T{HH}{mm}.csv",
+        "/base/logs/20240315T0830.csv",
+        Some("2024-03-15T08:30:00Z")
+    )]
+    #[case(
+        "{YYYY}/{MM}/*_{YYYY}{MM}{DD}.log",
+        "2024/03/app_20240301.log",
+        Some("2024-03-01T00:00:00Z")
+    )]
+    #[case("{YYYY}/{MM}/*_{YYYY}{MM}{DD}.log", "2024/03/app_20230301.log", None)]
+    #[case("logs/{YYYY}/{MM}/{DD}/*.json", "logs/latest/03/15/app.json", None)]
+    #[case("logs/{YYYY}/{MM}/{DD}/*.json", "logs/2024/02/30/app.json", None)]
+    fn test_date_pattern_extract(
+        #[case] pattern: &str,
+        #[case] path: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let date_pattern = DatePattern::parse(pattern).unwrap();
+        assert_eq!(
+            date_pattern.extract(path),
+            expected.map(|value| value.parse::<DateTime<Utc>>().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_date_pattern_partition_end() {
+        let start = "2024-01-31T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let monthly = DatePattern::parse("{YYYY}/{MM}/*.csv").unwrap();
+        assert_eq!(
+            monthly.partition_end(start),
+            Some("2024-02-29T00:00:00Z".parse().unwrap())
+        );
+
+        let hourly = DatePattern::parse("{YYYY}-{MM}-{DD}T{HH}.csv").unwrap();
+        assert_eq!(
+            hourly.partition_end(start),
+            Some("2024-01-31T01:00:00Z".parse().unwrap())
+        );
+
+        let time = "2024-01-31T13:45:10Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            monthly.partition_start(time),
+            Some("2024-01-01T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            hourly.partition_start(time),
+            Some("2024-01-31T13:00:00Z".parse().unwrap())
+        );
+
+        assert!(DatePattern::parse("static/*.csv").is_none());
+    }
+
+    #[test]
+    fn test_time_window() {
+        let time = |value: &str| value.parse::<DateTime<Utc>>().unwrap();
+        let window = TimeWindow::new(
+            Some(time("2024-01-10T00:00:00Z")),
+            Some(time("2024-01-12T00:00:00Z")),
+        )
+        .unwrap();
+
+        assert!(!window.overlaps(time("2024-01-09T00:00:00Z"), time("2024-01-10T00:00:00Z")));
+        assert!(window.overlaps(time("2024-01-10T00:00:00Z"), time("2024-01-11T00:00:00Z")));
+        assert!(window.overlaps(time("2024-01-01T00:00:00Z"), time("2024-02-01T00:00:00Z")));
+        assert!(!window.overlaps(time("2024-01-12T00:00:00Z"), time("2024-01-13T00:00:00Z")));
+        assert!(TimeWindow::default().is_unbounded());
+        assert!(TimeWindow::new(window.to, window.from).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_files_in_window() {
+        let tmpdir = TempDir::new().unwrap();
+        let tmppath = tmpdir.path().to_str().unwrap();
+        for day in ["2024-01-09", "2024-01-10", "2024-01-11"] {
+            let dir = format!("{tmppath}/logs/{}", day.replace('-', "/"));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(format!("{dir}/events.csv"), "id\n1\n").unwrap();
+        }
+        std::fs::create_dir_all(format!("{tmppath}/logs/latest/01/11")).unwrap();
+        std::fs::write(format!("{tmppath}/logs/latest/01/11/events.csv"), "id\n1\n").unwrap();
+
+        let adapter = create_test_adapter("logs/{YYYY}/{MM}/{DD}/*.csv");
+        let filesystem = FileSystem::new_local(Some(tmppath.to_string()));
+
+        let all = FileProcessor::files_in_window(&adapter, &filesystem, &TimeWindow::default())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|file| file.partition_time.is_some()));
+
+        let window = TimeWindow::new(Some("2024-01-10T12:00:00Z".parse().unwrap()), None).unwrap();
+        let files = FileProcessor::files_in_window(&adapter, &filesystem, &window)
+            .await
+            .unwrap();
+        let mut paths: Vec<String> = files.into_iter().map(|f| f.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                format!("{tmppath}/logs/2024/01/10/events.csv"),
+                format!("{tmppath}/logs/2024/01/11/events.csv"),
+            ]
+        );
+
+        let legacy = create_test_adapter("logs/<YYYY>/<MM>/<DD>/*.csv");
+        let error = FileProcessor::files_for_processing(&legacy, &filesystem)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("{YYYY}"));
     }
 }
@@ -1,5 +1,6 @@
 pub mod adapter;
 pub mod build;
+pub mod data_test;
 pub mod ducklake;
 pub mod execution;
 pub mod file_processor;
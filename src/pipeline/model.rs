@@ -1,4 +1,8 @@
-use crate::{config::model::ModelConfig, pipeline::ducklake::DuckLake};
+use crate::{
+    config::model::{Materialization, ModelConfig},
+    pipeline::{ducklake::DuckLake, execution::ExecutionOptions},
+    sql::quote_identifier,
+};
 use anyhow::{Context, Result};
 use std::sync::Arc;
 
@@ -13,15 +17,27 @@ impl Model {
         Self { config, ducklake }
     }
 
-    pub async fn execute_transform(&self, table_name: &str) -> Result<()> {
-        self.transform_model(table_name)?;
-        Ok(())
+    pub async fn execute_transform(
+        &self,
+        table_name: &str,
+        options: &ExecutionOptions,
+    ) -> Result<()> {
+        match self.config.materialization {
+            Materialization::Table => self.transform_model(table_name),
+            Materialization::View => self.create_view(table_name),
+            Materialization::IncrementalAppend | Materialization::IncrementalMerge => {
+                self.transform_incremental(table_name, options)
+            }
+        }
     }
 
     fn transform_model(&self, model_name: &str) -> Result<()> {
+        self.drop_relation_of_type(model_name, "VIEW")?;
+
+        let sql = self.config.render_sql(model_name, false)?;
         let create_table_sql = format!(
-            "CREATE OR REPLACE TABLE {} AS ({});",
-            model_name, self.config.sql
+            "CREATE OR REPLACE TABLE {} AS ({sql});",
+            quote_identifier(model_name)
         );
 
         self.ducklake
@@ -32,6 +48,59 @@ impl Model {
 
         Ok(())
     }
+
+    fn create_view(&self, model_name: &str) -> Result<()> {
+        self.drop_relation_of_type(model_name, "BASE TABLE")?;
+
+        let sql = self.config.render_sql(model_name, false)?;
+        let create_view_sql = format!(
+            "CREATE OR REPLACE VIEW {} AS ({sql});",
+            quote_identifier(model_name)
+        );
+
+        self.ducklake
+            .execute_batch(&create_view_sql)
+            .with_context(|| format!("Failed to create model view. SQL: {create_view_sql}"))?;
+
+        Ok(())
+    }
+
+    fn transform_incremental(&self, model_name: &str, options: &ExecutionOptions) -> Result<()> {
+        let merge = self.config.materialization == Materialization::IncrementalMerge;
+        if merge && self.config.unique_key.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Model '{model_name}' uses incremental_merge but has no unique_key"
+            ));
+        }
+
+        let is_table = self.ducklake.relation_type(model_name)?.as_deref() == Some("BASE TABLE");
+        if options.full_refresh || !is_table {
+            return self.transform_model(model_name);
+        }
+
+        let sql = self.config.render_sql(model_name, true)?;
+        if merge {
+            self.ducklake
+                .merge_from_query(model_name, &sql, &self.config.unique_key)
+        } else {
+            self.ducklake.insert_from_query(model_name, &sql)
+        }
+    }
+
+    fn drop_relation_of_type(&self, name: &str, relation_type: &str) -> Result<()> {
+        if self.ducklake.relation_type(name)?.as_deref() != Some(relation_type) {
+            return Ok(());
+        }
+
+        let kind = if relation_type == "VIEW" {
+            "VIEW"
+        } else {
+            "TABLE"
+        };
+        self.ducklake
+            .execute_batch(&format!("DROP {kind} {};", quote_identifier(name)))
+            .with_context(|| format!("Failed to drop existing {} '{name}'", kind.to_lowercase()))
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +113,9 @@ mod tests {
         ModelConfig {
             sql: "SELECT * FROM test_table".to_string(),
             description: None,
+            materialization: Materialization::Table,
+            unique_key: vec![],
+            tests: vec![],
         }
     }
 
@@ -64,4 +136,145 @@ mod tests {
         let model = Model::new(config, ducklake);
         assert_eq!(model.config.sql, "SELECT * FROM test_table");
     }
+
+    async fn create_test_ducklake(test_dir: &str) -> Arc<DuckLake> {
+        use crate::pipeline::ducklake::CatalogConfig;
+
+        std::fs::remove_dir_all(test_dir).ok();
+        std::fs::create_dir_all(test_dir).unwrap();
+
+        let catalog_config = CatalogConfig::Sqlite {
+            path: format!("{test_dir}/test_catalog.sqlite"),
+        };
+        let storage_config = StorageConfig::LocalFile {
+            path: format!("{test_dir}/test_storage"),
+        };
+
+        Arc::new(DuckLake::new(catalog_config, storage_config).await.unwrap())
+    }
+
+    fn incremental_model_config(materialization: Materialization) -> ModelConfig {
+        ModelConfig {
+            sql: r#"
+                SELECT id, name FROM events
+                {% if is_incremental() %}
+                WHERE id >= (SELECT MAX(id) FROM {{ this }})
+                {% endif %}
+            "#
+            .to_string(),
+            description: None,
+            materialization,
+            unique_key: vec!["id".to_string()],
+            tests: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_incremental_materializations() {
+        let ducklake = create_test_ducklake("/tmp/model_test_incremental").await;
+        let options = ExecutionOptions::default();
+
+        ducklake
+            .create_table_from_query(
+                "events",
+                "SELECT * FROM (VALUES (1, 'a'), (2, 'b')) AS t(id, name)",
+            )
+            .unwrap();
+
+        let append = Model::new(
+            incremental_model_config(Materialization::IncrementalAppend),
+            Arc::clone(&ducklake),
+        );
+        let merge = Model::new(
+            incremental_model_config(Materialization::IncrementalMerge),
+            Arc::clone(&ducklake),
+        );
+
+        append
+            .execute_transform("appended", &options)
+            .await
+            .unwrap();
+        merge.execute_transform("merged", &options).await.unwrap();
+
+        ducklake
+            .execute_batch(
+                "UPDATE events SET name = 'B' WHERE id = 2; INSERT INTO events VALUES (3, 'c');",
+            )
+            .unwrap();
+
+        append
+            .execute_transform("appended", &options)
+            .await
+            .unwrap();
+        merge.execute_transform("merged", &options).await.unwrap();
+
+        let appended = ducklake
+            .query("SELECT id, name FROM appended ORDER BY id, name")
+            .unwrap();
+        assert_eq!(appended.len(), 4);
+        assert_eq!(appended[1], vec!["2", "B"]);
+        assert_eq!(appended[2], vec!["2", "b"]);
+
+        let merged = ducklake
+            .query("SELECT id, name FROM merged ORDER BY id")
+            .unwrap();
+        assert_eq!(merged, vec![vec!["1", "a"], vec!["2", "B"], vec!["3", "c"]]);
+
+        let full_refresh = ExecutionOptions {
+            full_refresh: true,
+            ..Default::default()
+        };
+        append
+            .execute_transform("appended", &full_refresh)
+            .await
+            .unwrap();
+        let count = ducklake.query("SELECT COUNT(*) FROM appended").unwrap();
+        assert_eq!(count[0][0], "3");
+    }
+
+    #[tokio::test]
+    async fn test_view_materialization_replaces_table() {
+        let ducklake = create_test_ducklake("/tmp/model_test_view").await;
+
+        ducklake
+            .create_table_from_query("source", "SELECT 1 AS id")
+            .unwrap();
+
+        let mut config = create_test_model_config();
+        config.sql = "SELECT * FROM source".to_string();
+        let model = Model::new(config.clone(), Arc::clone(&ducklake));
+        model
+            .execute_transform("output", &ExecutionOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            ducklake.relation_type("output").unwrap().as_deref(),
+            Some("BASE TABLE")
+        );
+
+        config.materialization = Materialization::View;
+        let model = Model::new(config, Arc::clone(&ducklake));
+        model
+            .execute_transform("output", &ExecutionOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            ducklake.relation_type("output").unwrap().as_deref(),
+            Some("VIEW")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_incremental_merge_requires_unique_key() {
+        let ducklake = create_test_ducklake("/tmp/model_test_merge_key").await;
+
+        let mut config = incremental_model_config(Materialization::IncrementalMerge);
+        config.unique_key.clear();
+        let model = Model::new(config, ducklake);
+
+        let result = model
+            .execute_transform("merged", &ExecutionOptions::default())
+            .await;
+        assert!(result.is_err());
+    }
 }
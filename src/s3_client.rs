@@ -4,6 +4,13 @@ use aws_config::Region;
 use aws_sdk_s3::Client;
 use regex::Regex;
 
+pub struct S3Object {
+    pub key: String,
+    pub size: Option<u64>,
+    pub etag: Option<String>,
+    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 pub struct S3Client {
     client: Client,
     bucket: String,
@@ -58,14 +65,29 @@ impl S3Client {
     }
 
     pub async fn list_objects_matching_pattern(&self, pattern: &str) -> Result<Vec<String>> {
+        Ok(self
+            .list_object_entries_matching_pattern(pattern)
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            .collect())
+    }
+
+    pub async fn list_object_entries_matching_pattern(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<S3Object>> {
         let prefix = extract_prefix_from_pattern(pattern);
 
-        let all_keys = self.list_all_objects_with_prefix(&prefix).await?;
+        let all_objects = self.list_all_objects_with_prefix(&prefix).await?;
 
-        let matching_objects: Vec<String> = all_keys
+        let matching_objects: Vec<S3Object> = all_objects
             .into_iter()
-            .filter(|key| matches_pattern(pattern, key))
-            .map(|key| format!("s3://{}/{}", self.bucket, key))
+            .filter(|object| matches_pattern(pattern, &object.key))
+            .map(|object| S3Object {
+                key: format!("s3://{}/{}", self.bucket, object.key),
+                ..object
+            })
             .collect();
 
         Ok(matching_objects)
@@ -134,8 +156,8 @@ impl S3Client {
         Ok(())
     }
 
-    async fn list_all_objects_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
-        let mut all_keys = Vec::new();
+    async fn list_all_objects_with_prefix(&self, prefix: &str) -> Result<Vec<S3Object>> {
+        let mut all_objects = Vec::new();
         let mut continuation_token = None;
 
         loop {
@@ -154,7 +176,17 @@ impl S3Client {
             if let Some(contents) = result.contents {
                 for object in contents {
                     if let Some(key) = object.key {
-                        all_keys.push(key);
+                        all_objects.push(S3Object {
+                            key,
+                            size: object.size.and_then(|size| u64::try_from(size).ok()),
+                            etag: object.e_tag,
+                            last_modified: object.last_modified.and_then(|modified| {
+                                chrono::DateTime::from_timestamp(
+                                    modified.secs(),
+                                    modified.subsec_nanos(),
+                                )
+                            }),
+                        });
                     }
                 }
             }
@@ -165,7 +197,7 @@ impl S3Client {
             continuation_token = result.next_continuation_token;
         }
 
-        Ok(all_keys)
+        Ok(all_objects)
     }
 }
 
@@ -339,9 +371,9 @@ mod tests {
         if !objects.is_empty() {
             let delete_objects: Vec<_> = objects
                 .iter()
-                .map(|key| {
+                .map(|object| {
                     aws_sdk_s3::types::ObjectIdentifier::builder()
-                        .key(key)
+                        .key(&object.key)
                         .build()
                         .unwrap()
                 })
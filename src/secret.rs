@@ -1,618 +1,328 @@
-// use anyhow::{Context, Result};
-// use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
-// use regex::Regex;
-// use ring::aead::{
-//     AES_256_GCM, Aad, BoundKey, Nonce, NonceSequence, OpeningKey, SealingKey, UnboundKey,
-// };
-// use ring::rand::{SecureRandom, SystemRandom};
-// use serde::{Deserialize, Serialize};
-// use std::collections::HashMap;
-// use std::fs;
-// use std::path::{Path, PathBuf};
-//
-// struct CountingNonceSequence(u32);
-//
-// impl NonceSequence for CountingNonceSequence {
-//     fn advance(&mut self) -> Result<Nonce, ring::error::Unspecified> {
-//         let mut nonce_bytes = [0u8; 12];
-//         nonce_bytes[8..].copy_from_slice(&self.0.to_be_bytes());
-//         self.0 += 1;
-//         Nonce::try_assume_unique_for_key(&nonce_bytes)
-//     }
-// }
-//
-// #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-// struct SecretData {
-//     #[serde(flatten)]
-//     secrets: HashMap<String, String>,
-// }
-//
-// pub struct SecretManager {
-//     key_file_path: PathBuf,
-//     secrets_file_path: PathBuf,
-// }
-//
-// impl SecretManager {
-//     pub fn new(project_dir: &Path) -> Result<Self> {
-//         let key_file_path = project_dir.join(".secret.key");
-//         let secrets_file_path = project_dir.join("secrets.yml");
-//
-//         if let Some(parent) = key_file_path.parent() {
-//             fs::create_dir_all(parent)
-//                 .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
-//         }
-//
-//         Ok(Self {
-//             key_file_path,
-//             secrets_file_path,
-//         })
-//     }
-//
-//     pub fn key_exists(&self) -> bool {
-//         self.key_file_path.exists()
-//     }
-//
-//     fn load_key(&self) -> Result<[u8; 32]> {
-//         if !self.key_exists() {
-//             return Err(anyhow::anyhow!(
-//                 "Secret key not found. Run 'featherbox secret gen-key' first."
-//             ));
-//         }
-//
-//         let content = fs::read_to_string(&self.key_file_path).with_context(|| {
-//             format!("Failed to read key file: {}", self.key_file_path.display())
-//         })?;
-//
-//         let key_line = content
-//             .lines()
-//             .find(|line| !line.trim().starts_with('#') && !line.trim().is_empty())
-//             .context("No valid key found in secret file")?;
-//
-//         let key_bytes = BASE64
-//             .decode(key_line.trim())
-//             .context("Failed to decode base64 key")?;
-//
-//         if key_bytes.len() != 32 {
-//             return Err(anyhow::anyhow!("Invalid key length, expected 32 bytes"));
-//         }
-//
-//         let mut key = [0u8; 32];
-//         key.copy_from_slice(&key_bytes);
-//         Ok(key)
-//     }
-//
-//     fn encrypt(&self, data: &str) -> Result<String> {
-//         let key = self.load_key()?;
-//         let unbound_key = UnboundKey::new(&AES_256_GCM, &key)
-//             .map_err(|_| anyhow::anyhow!("Failed to create encryption key"))?;
-//
-//         let mut nonce_bytes = [0u8; 12];
-//         let rng = SystemRandom::new();
-//         rng.fill(&mut nonce_bytes)
-//             .map_err(|_| anyhow::anyhow!("Failed to generate nonce"))?;
-//
-//         let mut sealing_key = SealingKey::new(unbound_key, CountingNonceSequence(0));
-//
-//         let mut in_out = data.as_bytes().to_vec();
-//         let tag = sealing_key
-//             .seal_in_place_separate_tag(Aad::empty(), &mut in_out)
-//             .map_err(|_| anyhow::anyhow!("Failed to encrypt data"))?;
-//
-//         let mut payload = Vec::new();
-//         payload.extend_from_slice(&nonce_bytes);
-//         payload.extend_from_slice(&in_out);
-//         payload.extend_from_slice(tag.as_ref());
-//
-//         Ok(BASE64.encode(payload))
-//     }
-//
-//     fn decrypt(&self, encrypted_data: &str) -> Result<String> {
-//         let key = self.load_key()?;
-//         let payload = BASE64
-//             .decode(encrypted_data.trim())
-//             .context("Failed to decode base64 encrypted data")?;
-//
-//         if payload.len() < 28 {
-//             return Err(anyhow::anyhow!("Invalid encrypted data: too short"));
-//         }
-//
-//         let tag_start = payload.len() - 16;
-//         let ciphertext = &payload[12..tag_start];
-//
-//         let unbound_key = UnboundKey::new(&AES_256_GCM, &key)
-//             .map_err(|_| anyhow::anyhow!("Failed to create decryption key"))?;
-//         let mut opening_key = OpeningKey::new(unbound_key, CountingNonceSequence(0));
-//
-//         let mut ciphertext_and_tag = Vec::new();
-//         ciphertext_and_tag.extend_from_slice(ciphertext);
-//         ciphertext_and_tag.extend_from_slice(&payload[tag_start..]);
-//
-//         let decrypted = opening_key
-//             .open_in_place(Aad::empty(), &mut ciphertext_and_tag)
-//             .map_err(|_| anyhow::anyhow!("Failed to decrypt data"))?;
-//
-//         String::from_utf8(decrypted.to_vec()).context("Decrypted data is not valid UTF-8")
-//     }
-//
-//     fn load_secrets(&self) -> Result<SecretData> {
-//         if !self.secrets_file_path.exists() {
-//             return Ok(SecretData::default());
-//         }
-//
-//         let yaml_content = fs::read_to_string(&self.secrets_file_path).with_context(|| {
-//             format!(
-//                 "Failed to read secrets file: {}",
-//                 self.secrets_file_path.display()
-//             )
-//         })?;
-//
-//         if yaml_content.trim().is_empty() {
-//             return Ok(SecretData::default());
-//         }
-//
-//         let yaml_value: serde_yml::Value =
-//             serde_yml::from_str(&yaml_content).context("Failed to parse secrets YAML")?;
-//
-//         let mut secrets = HashMap::new();
-//
-//         if let serde_yml::Value::Mapping(map) = yaml_value {
-//             for (key, value) in map {
-//                 if let (
-//                     serde_yml::Value::String(key_str),
-//                     serde_yml::Value::String(encrypted_value),
-//                 ) = (key, value)
-//                 {
-//                     let decrypted_value = self.decrypt(&encrypted_value)?;
-//                     secrets.insert(key_str, decrypted_value);
-//                 }
-//             }
-//         }
-//
-//         Ok(SecretData { secrets })
-//     }
-//
-//     fn save_secrets(&self, data: &SecretData) -> Result<()> {
-//         let mut yaml_map = serde_yml::Mapping::new();
-//
-//         for (key, value) in &data.secrets {
-//             let encrypted_value = self.encrypt(value)?;
-//             yaml_map.insert(
-//                 serde_yml::Value::String(key.clone()),
-//                 serde_yml::Value::String(encrypted_value),
-//             );
-//         }
-//
-//         let yaml_value = serde_yml::Value::Mapping(yaml_map);
-//         let yaml_content =
-//             serde_yml::to_string(&yaml_value).context("Failed to serialize secrets to YAML")?;
-//
-//         fs::write(&self.secrets_file_path, yaml_content).with_context(|| {
-//             format!(
-//                 "Failed to write secrets file: {}",
-//                 self.secrets_file_path.display()
-//             )
-//         })?;
-//
-//         Ok(())
-//     }
-//
-//     pub fn get_secret(&self, key: &str) -> Result<Option<String>> {
-//         let data = self.load_secrets()?;
-//         Ok(data.secrets.get(key).cloned())
-//     }
-//
-//     pub fn set_secret(&self, key: &str, value: &str) -> Result<()> {
-//         let mut data = self.load_secrets()?;
-//         data.secrets.insert(key.to_string(), value.to_string());
-//         self.save_secrets(&data)
-//     }
-//
-//     pub fn delete_secret(&self, key: &str) -> Result<bool> {
-//         let mut data = self.load_secrets()?;
-//         let removed = data.secrets.remove(key).is_some();
-//         if removed {
-//             self.save_secrets(&data)?;
-//         }
-//         Ok(removed)
-//     }
-//
-//     pub fn list_secrets(&self) -> Result<Vec<String>> {
-//         let data = self.load_secrets()?;
-//         let mut keys: Vec<String> = data.secrets.keys().cloned().collect();
-//         keys.sort();
-//         Ok(keys)
-//     }
-//
-//     pub fn get_all_secrets(&self) -> Result<HashMap<String, String>> {
-//         let data = self.load_secrets()?;
-//         Ok(data.secrets)
-//     }
-// }
-//
-// pub fn expand_secrets_in_text(project_dir: &Path, text: &str) -> Result<String> {
-//     let secret_regex =
-//         Regex::new(r"\$\{SECRET_([a-zA-Z][a-zA-Z0-9_]*)\}").expect("Invalid regex pattern");
-//
-//     if !secret_regex.is_match(text) {
-//         return Ok(text.to_string());
-//     }
-//
-//     let manager = SecretManager::new(project_dir)?;
-//     let secrets = manager.get_all_secrets()?;
-//
-//     let mut result = text.to_string();
-//     for captures in secret_regex.captures_iter(text) {
-//         let full_match = captures.get(0).unwrap().as_str();
-//         let key = captures.get(1).unwrap().as_str();
-//
-//         if let Some(value) = secrets.get(key) {
-//             result = result.replace(full_match, value);
-//         } else {
-//             return Err(anyhow::anyhow!(
-//                 "Secret '{}' not found. Use 'featherbox secret new' to add it.",
-//                 key
-//             ));
-//         }
-//     }
-//
-//     Ok(result)
-// }
-
-// #[cfg(test)]
-// mod tests {
-//
-//     use super::*;
-//     use tempfile::TempDir;
-//
-//     #[test]
-//     fn test_secret_manager_generate_and_load_key() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//
-//         assert!(!manager.key_exists());
-//
-//         manager.generate_key()?;
-//         assert!(manager.key_exists());
-//
-//         let _key = manager.load_key()?;
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_secret_manager_operations() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         let keys = manager.list_secrets()?;
-//         assert!(keys.is_empty());
-//
-//         manager.set_secret("TEST_KEY", "test_value")?;
-//
-//         let keys = manager.list_secrets()?;
-//         assert_eq!(keys, vec!["TEST_KEY"]);
-//
-//         let value = manager.get_secret("TEST_KEY")?;
-//         assert_eq!(value, Some("test_value".to_string()));
-//
-//         manager.set_secret("TEST_KEY", "new_value")?;
-//
-//         let value = manager.get_secret("TEST_KEY")?;
-//         assert_eq!(value, Some("new_value".to_string()));
-//
-//         let removed = manager.delete_secret("TEST_KEY")?;
-//         assert!(removed);
-//
-//         let keys = manager.list_secrets()?;
-//         assert!(keys.is_empty());
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_secret_expansion() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         manager.set_secret("DB_HOST", "localhost")?;
-//         manager.set_secret("DB_PORT", "5432")?;
-//
-//         let text = "host: ${SECRET_DB_HOST}\nport: ${SECRET_DB_PORT}";
-//         let expanded = expand_secrets_in_text(text)?;
-//
-//         assert_eq!(expanded, "host: localhost\nport: 5432");
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_secret_expansion_missing_key() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let text = "host: ${SECRET_MISSING_KEY}";
-//         let result = expand_secrets_in_text(text);
-//
-//         assert!(result.is_err());
-//         assert!(result.unwrap_err().to_string().contains("not found"));
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_secret_expansion_no_match() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let text = "host: localhost";
-//         let expanded = expand_secrets_in_text(text)?;
-//
-//         assert_eq!(expanded, "host: localhost");
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_expand_secrets_in_text_multiple_secrets() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         manager.set_secret("API_KEY", "secret123")?;
-//         manager.set_secret("ENDPOINT", "https://api.example.com")?;
-//         manager.set_secret("VERSION", "v2")?;
-//
-//         let text = "url: ${SECRET_ENDPOINT}/${SECRET_VERSION}/data?key=${SECRET_API_KEY}";
-//         let expanded = expand_secrets_in_text(text)?;
-//
-//         assert_eq!(
-//             expanded,
-//             "url: https://api.example.com/v2/data?key=secret123"
-//         );
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_expand_secrets_in_text_same_secret_multiple_times() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         manager.set_secret("TOKEN", "abc123")?;
-//
-//         let text = "auth_header: Bearer ${SECRET_TOKEN}\nbackup_token: ${SECRET_TOKEN}";
-//         let expanded = expand_secrets_in_text(text)?;
-//
-//         assert_eq!(expanded, "auth_header: Bearer abc123\nbackup_token: abc123");
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_expand_secrets_in_text_invalid_secret_name() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         let text = "value: ${SECRET_NONEXISTENT_KEY}";
-//         let result = expand_secrets_in_text(text);
-//
-//         assert!(result.is_err());
-//         assert!(result.unwrap_err().to_string().contains("not found"));
-//
-//         Ok(())
-//     }
-//
-//     // #[test]
-//     // fn test_expand_secrets_in_hash_map_success() -> anyhow::Result<()> {
-//     //     let temp_dir = TempDir::new()?;
-//     //     std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//     //     crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//     //
-//     //     let manager = SecretManager::new()?;
-//     //     manager.generate_key()?;
-//     //
-//     //     manager.set_secret("USERNAME", "testuser")?;
-//     //     manager.set_secret("PASSWORD", "testpass")?;
-//     //
-//     //     let mut input_map = HashMap::new();
-//     //     input_map.insert("user".to_string(), "${SECRET_USERNAME}".to_string());
-//     //     input_map.insert("pass".to_string(), "${SECRET_PASSWORD}".to_string());
-//     //     input_map.insert("host".to_string(), "localhost".to_string());
-//     //
-//     //     let expanded = expand_secrets_in_hash_map(&input_map, temp_dir.path())?;
-//     //
-//     //     assert_eq!(expanded.get("user").unwrap(), "testuser");
-//     //     assert_eq!(expanded.get("pass").unwrap(), "testpass");
-//     //     assert_eq!(expanded.get("host").unwrap(), "localhost");
-//     //
-//     //     Ok(())
-//     // }
-//
-//     // #[test]
-//     // fn test_expand_secrets_in_hash_map_empty_map() -> anyhow::Result<()> {
-//     //     let temp_dir = TempDir::new()?;
-//     //     std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//     //     crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//     //
-//     //     let input_map = HashMap::new();
-//     //
-//     //     let expanded = expand_secrets_in_hash_map(&input_map, temp_dir.path())?;
-//     //
-//     //     assert!(expanded.is_empty());
-//     //
-//     //     Ok(())
-//     // }
-//
-//     // #[test]
-//     // fn test_expand_secrets_in_hash_map_no_secrets() -> anyhow::Result<()> {
-//     //     let temp_dir = TempDir::new()?;
-//     //     std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//     //     crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//     //
-//     //     let mut input_map = HashMap::new();
-//     //     input_map.insert("config1".to_string(), "value1".to_string());
-//     //     input_map.insert("config2".to_string(), "value2".to_string());
-//     //
-//     //     let expanded = expand_secrets_in_hash_map(&input_map, temp_dir.path())?;
-//     //
-//     //     assert_eq!(expanded.get("config1").unwrap(), "value1");
-//     //     assert_eq!(expanded.get("config2").unwrap(), "value2");
-//     //
-//     //     Ok(())
-//     // }
-//
-//     // #[test]
-//     // fn test_expand_secrets_in_hash_map_missing_secret() -> anyhow::Result<()> {
-//     //     let temp_dir = TempDir::new()?;
-//     //     std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//     //     crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//     //
-//     //     let manager = SecretManager::new()?;
-//     //     manager.generate_key()?;
-//     //
-//     //     let mut input_map = HashMap::new();
-//     //     input_map.insert("key".to_string(), "${SECRET_NONEXISTENT}".to_string());
-//     //
-//     //     let result = expand_secrets_in_hash_map(&input_map, temp_dir.path());
-//     //
-//     //     assert!(result.is_err());
-//     //     assert!(result.unwrap_err().to_string().contains("not found"));
-//     //
-//     //     Ok(())
-//     // }
-//
-//     // #[test]
-//     // fn test_expand_secrets_in_hash_map_mixed_values() -> anyhow::Result<()> {
-//     //     let temp_dir = TempDir::new()?;
-//     //     std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//     //     crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//     //
-//     //     let manager = SecretManager::new()?;
-//     //     manager.generate_key()?;
-//     //
-//     //     manager.set_secret("DATABASE_URL", "postgres://localhost:5432/db")?;
-//     //
-//     //     let mut input_map = HashMap::new();
-//     //     input_map.insert("db_url".to_string(), "${SECRET_DATABASE_URL}".to_string());
-//     //     input_map.insert("timeout".to_string(), "30".to_string());
-//     //     input_map.insert("ssl_mode".to_string(), "require".to_string());
-//     //     input_map.insert(
-//     //         "connection_string".to_string(),
-//     //         "server=${SECRET_DATABASE_URL};timeout=30".to_string(),
-//     //     );
-//     //
-//     //     let expanded = expand_secrets_in_hash_map(&input_map, temp_dir.path())?;
-//     //
-//     //     assert_eq!(
-//     //         expanded.get("db_url").unwrap(),
-//     //         "postgres://localhost:5432/db"
-//     //     );
-//     //     assert_eq!(expanded.get("timeout").unwrap(), "30");
-//     //     assert_eq!(expanded.get("ssl_mode").unwrap(), "require");
-//     //     assert_eq!(
-//     //         expanded.get("connection_string").unwrap(),
-//     //         "server=postgres://localhost:5432/db;timeout=30"
-//     //     );
-//     //
-//     //     Ok(())
-//     // }
-//
-//     #[test]
-//     fn test_yaml_secret_file_format() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         manager.set_secret("API_KEY", "secret123")?;
-//         manager.set_secret("DB_PASSWORD", "password456")?;
-//
-//         let yaml_content = std::fs::read_to_string(temp_dir.path().join("secrets.yml"))?;
-//
-//         assert!(yaml_content.contains("API_KEY:"));
-//         assert!(yaml_content.contains("DB_PASSWORD:"));
-//         assert!(!yaml_content.contains("secret123"));
-//         assert!(!yaml_content.contains("password456"));
-//
-//         let retrieved_api_key = manager.get_secret("API_KEY")?;
-//         let retrieved_db_password = manager.get_secret("DB_PASSWORD")?;
-//
-//         assert_eq!(retrieved_api_key, Some("secret123".to_string()));
-//         assert_eq!(retrieved_db_password, Some("password456".to_string()));
-//
-//         Ok(())
-//     }
-//
-//     #[test]
-//     fn test_yaml_file_manual_inspection() -> anyhow::Result<()> {
-//         let temp_dir = TempDir::new()?;
-//         std::fs::write(temp_dir.path().join("project.yml"), "test: true")?;
-//         crate::workspace::set_project_dir(temp_dir.path().to_path_buf());
-//
-//         let manager = SecretManager::new()?;
-//         manager.generate_key()?;
-//
-//         manager.set_secret("AWS_SECRET_KEY", "AKIA1234567890")?;
-//         manager.set_secret("DB_PASSWORD", "mypassword")?;
-//
-//         let yaml_content = std::fs::read_to_string(temp_dir.path().join("secrets.yml"))?;
-//         let yaml_lines: Vec<&str> = yaml_content.lines().collect();
-//
-//         assert_eq!(yaml_lines.len(), 2);
-//
-//         let mut found_aws = false;
-//         let mut found_db = false;
-//
-//         for line in &yaml_lines {
-//             if line.starts_with("AWS_SECRET_KEY: ") {
-//                 found_aws = true;
-//                 assert!(!line.contains("AKIA1234567890"));
-//                 let encrypted_part = line.strip_prefix("AWS_SECRET_KEY: ").unwrap();
-//                 assert!(!encrypted_part.is_empty());
-//                 assert_ne!(encrypted_part, "AKIA1234567890");
-//             } else if line.starts_with("DB_PASSWORD: ") {
-//                 found_db = true;
-//                 assert!(!line.contains("mypassword"));
-//                 let encrypted_part = line.strip_prefix("DB_PASSWORD: ").unwrap();
-//                 assert!(!encrypted_part.is_empty());
-//                 assert_ne!(encrypted_part, "mypassword");
-//             }
-//         }
-//
-//         assert!(found_aws, "AWS_SECRET_KEY not found in YAML");
-//         assert!(found_db, "DB_PASSWORD not found in YAML");
-//
-//         let retrieved_aws = manager.get_secret("AWS_SECRET_KEY")?;
-//         let retrieved_db = manager.get_secret("DB_PASSWORD")?;
-//         assert_eq!(retrieved_aws, Some("AKIA1234567890".to_string()));
-//         assert_eq!(retrieved_db, Some("mypassword".to_string()));
-//
-//         Ok(())
-//     }
-// }
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use regex::Regex;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TAG_LEN: usize = 16;
+
+/// Stores secrets in `secrets.yml`, each value encrypted with AES-256-GCM under the
+/// project's `.secret.key`.
+pub struct SecretManager {
+    key_file_path: PathBuf,
+    secrets_file_path: PathBuf,
+}
+
+impl SecretManager {
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            key_file_path: project_dir.join(".secret.key"),
+            secrets_file_path: project_dir.join("secrets.yml"),
+        }
+    }
+
+    pub fn key_exists(&self) -> bool {
+        self.key_file_path.exists()
+    }
+
+    fn load_key(&self) -> Result<LessSafeKey> {
+        if !self.key_exists() {
+            return Err(anyhow::anyhow!(
+                "Secret key not found. Run 'featherbox secret gen-key' first."
+            ));
+        }
+
+        let content = fs::read_to_string(&self.key_file_path).with_context(|| {
+            format!("Failed to read key file: {}", self.key_file_path.display())
+        })?;
+
+        let key_line = content
+            .lines()
+            .find(|line| !line.trim().starts_with('#') && !line.trim().is_empty())
+            .context("No valid key found in secret file")?;
+
+        let key_bytes = BASE64
+            .decode(key_line.trim())
+            .context("Failed to decode base64 key")?;
+
+        if key_bytes.len() != 32 {
+            return Err(anyhow::anyhow!("Invalid key length, expected 32 bytes"));
+        }
+
+        let unbound_key = UnboundKey::new(&AES_256_GCM, &key_bytes)
+            .map_err(|_| anyhow::anyhow!("Failed to create encryption key"))?;
+        Ok(LessSafeKey::new(unbound_key))
+    }
+
+    fn encrypt(key: &LessSafeKey, data: &str) -> Result<String> {
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce_bytes)
+            .map_err(|_| anyhow::anyhow!("Failed to generate nonce"))?;
+
+        let mut in_out = data.as_bytes().to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt data"))?;
+
+        let mut payload = nonce_bytes.to_vec();
+        payload.extend_from_slice(&in_out);
+
+        Ok(BASE64.encode(payload))
+    }
+
+    fn decrypt(key: &LessSafeKey, encrypted_data: &str) -> Result<String> {
+        let payload = BASE64
+            .decode(encrypted_data.trim())
+            .context("Failed to decode base64 encrypted data")?;
+
+        if payload.len() < NONCE_LEN + TAG_LEN {
+            return Err(anyhow::anyhow!("Invalid encrypted data: too short"));
+        }
+
+        let (nonce_bytes, ciphertext) = payload.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+            .map_err(|_| anyhow::anyhow!("Invalid nonce in encrypted data"))?;
+
+        let mut in_out = ciphertext.to_vec();
+        let decrypted = key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt data"))?;
+
+        String::from_utf8(decrypted.to_vec()).context("Decrypted data is not valid UTF-8")
+    }
+
+    fn load_secrets(&self) -> Result<BTreeMap<String, String>> {
+        if !self.secrets_file_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let yaml_content = fs::read_to_string(&self.secrets_file_path).with_context(|| {
+            format!(
+                "Failed to read secrets file: {}",
+                self.secrets_file_path.display()
+            )
+        })?;
+
+        if yaml_content.trim().is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let encrypted: BTreeMap<String, String> =
+            serde_yml::from_str(&yaml_content).context("Failed to parse secrets YAML")?;
+
+        let key = self.load_key()?;
+        encrypted
+            .into_iter()
+            .map(|(name, value)| {
+                let decrypted = Self::decrypt(&key, &value)
+                    .with_context(|| format!("Failed to decrypt secret '{name}'"))?;
+                Ok((name, decrypted))
+            })
+            .collect()
+    }
+
+    fn save_secrets(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let key = self.load_key()?;
+        let encrypted = secrets
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), Self::encrypt(&key, value)?)))
+            .collect::<Result<BTreeMap<String, String>>>()?;
+
+        let yaml_content =
+            serde_yml::to_string(&encrypted).context("Failed to serialize secrets to YAML")?;
+
+        fs::write(&self.secrets_file_path, yaml_content).with_context(|| {
+            format!(
+                "Failed to write secrets file: {}",
+                self.secrets_file_path.display()
+            )
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_secret(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.load_secrets()?.remove(name))
+    }
+
+    pub fn set_secret(&self, name: &str, value: &str) -> Result<()> {
+        if !is_valid_secret_name(name) {
+            return Err(anyhow::anyhow!(
+                "Invalid secret name '{name}'. Use letters, digits and underscores, starting with a letter."
+            ));
+        }
+
+        let mut secrets = self.load_secrets()?;
+        secrets.insert(name.to_string(), value.to_string());
+        self.save_secrets(&secrets)
+    }
+
+    pub fn delete_secret(&self, name: &str) -> Result<bool> {
+        let mut secrets = self.load_secrets()?;
+        let removed = secrets.remove(name).is_some();
+        if removed {
+            self.save_secrets(&secrets)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn list_secrets(&self) -> Result<Vec<String>> {
+        Ok(self.load_secrets()?.into_keys().collect())
+    }
+
+    pub fn get_all_secrets(&self) -> Result<BTreeMap<String, String>> {
+        self.load_secrets()
+    }
+
+    /// Replaces every `${secret:NAME}` in `text`. The secrets file is only read when a
+    /// reference is present, so projects without secrets don't need a key.
+    pub fn expand(&self, text: &str) -> Result<String> {
+        let secret_regex = Regex::new(r"\$\{secret:([^}]*)\}").unwrap();
+        if !secret_regex.is_match(text) {
+            return Ok(text.to_string());
+        }
+
+        let secrets = self.load_secrets()?;
+        let mut missing = None;
+        let expanded = secret_regex.replace_all(text, |captures: &regex::Captures| {
+            let name = &captures[1];
+            match secrets.get(name) {
+                Some(value) => value.clone(),
+                None => {
+                    missing.get_or_insert_with(|| name.to_string());
+                    String::new()
+                }
+            }
+        });
+
+        if let Some(name) = missing {
+            return Err(anyhow::anyhow!(
+                "Secret '{name}' not found. Use 'featherbox secret set {name}' to add it."
+            ));
+        }
+
+        Ok(expanded.into_owned())
+    }
+}
+
+pub fn is_valid_secret_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 64
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::new::create_secret_key;
+    use tempfile::TempDir;
+
+    fn create_manager() -> Result<(TempDir, SecretManager)> {
+        let temp_dir = TempDir::new()?;
+        create_secret_key(temp_dir.path())?;
+        let manager = SecretManager::new(temp_dir.path());
+        Ok((temp_dir, manager))
+    }
+
+    #[test]
+    fn test_secret_manager_operations() -> Result<()> {
+        let (_temp_dir, manager) = create_manager()?;
+
+        assert!(manager.key_exists());
+        assert!(manager.list_secrets()?.is_empty());
+
+        manager.set_secret("TEST_KEY", "test_value")?;
+        assert_eq!(manager.list_secrets()?, vec!["TEST_KEY"]);
+        assert_eq!(
+            manager.get_secret("TEST_KEY")?,
+            Some("test_value".to_string())
+        );
+
+        manager.set_secret("TEST_KEY", "new_value")?;
+        assert_eq!(
+            manager.get_secret("TEST_KEY")?,
+            Some("new_value".to_string())
+        );
+
+        assert!(manager.delete_secret("TEST_KEY")?);
+        assert!(!manager.delete_secret("TEST_KEY")?);
+        assert!(manager.list_secrets()?.is_empty());
+
+        assert!(manager.set_secret("invalid-name", "value").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_secrets_file_is_encrypted_with_unique_nonces() -> Result<()> {
+        let (temp_dir, manager) = create_manager()?;
+
+        manager.set_secret("FIRST", "same_value")?;
+        manager.set_secret("SECOND", "same_value")?;
+
+        let yaml_content = fs::read_to_string(temp_dir.path().join("secrets.yml"))?;
+        assert!(!yaml_content.contains("same_value"));
+
+        let encrypted: BTreeMap<String, String> = serde_yml::from_str(&yaml_content)?;
+        assert_ne!(encrypted["FIRST"], encrypted["SECOND"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() -> Result<()> {
+        let (temp_dir, manager) = create_manager()?;
+        manager.set_secret("DB_PASSWORD", "password456")?;
+
+        create_secret_key(temp_dir.path())?;
+        assert!(manager.get_secret("DB_PASSWORD").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand() -> Result<()> {
+        let (_temp_dir, manager) = create_manager()?;
+
+        manager.set_secret("DB_HOST", "localhost")?;
+        manager.set_secret("TOKEN", "abc123")?;
+
+        let expanded = manager.expand("${secret:DB_HOST}:${secret:TOKEN}/${secret:TOKEN}")?;
+        assert_eq!(expanded, "localhost:abc123/abc123");
+
+        let missing = manager.expand("host: ${secret:MISSING}");
+        assert!(missing.unwrap_err().to_string().contains("not found"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_without_references_needs_no_key() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let manager = SecretManager::new(temp_dir.path());
+
+        assert_eq!(manager.expand("host: localhost")?, "host: localhost");
+        assert!(manager.expand("${secret:ANY}").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_valid_secret_name() {
+        assert!(is_valid_secret_name("VALID_KEY"));
+        assert!(is_valid_secret_name("a"));
+        assert!(!is_valid_secret_name(""));
+        assert!(!is_valid_secret_name("123invalid"));
+        assert!(!is_valid_secret_name("_invalid"));
+        assert!(!is_valid_secret_name("invalid-key"));
+        assert!(!is_valid_secret_name(&"a".repeat(65)));
+    }
+}
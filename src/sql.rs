@@ -0,0 +1,83 @@
+//! Building SQL from names, paths and credentials that come from configuration.
+//!
+//! Identifiers are double-quoted and string literals single-quoted with embedded quotes
+//! doubled, so a value can never end the token it is written into. Values compared in
+//! `WHERE` clauses should be bound as parameters with [`BoundQuery`] instead.
+
+/// Quotes a single identifier, e.g. a table or column name. DuckDB still matches quoted
+/// identifiers case-insensitively, so quoting never changes which relation is found.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes each dot-separated part of a possibly qualified name such as `schema.table`.
+pub fn quote_qualified(name: &str) -> String {
+    name.split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Quotes a string literal.
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quotes a value of a libpq-style `key=value` connection string, which DuckDB's
+/// PostgreSQL and MySQL extensions parse. Plain values are left as they are.
+pub fn connection_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '\'' || c == '\\' || c == '=');
+    if !needs_quotes {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// A statement with `?` placeholders and the values bound to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundQuery {
+    pub sql: String,
+    pub params: Vec<String>,
+}
+
+impl BoundQuery {
+    pub fn new(sql: impl Into<String>, params: Vec<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            params,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote_identifier("users"), "\"users\"");
+        assert_eq!(
+            quote_identifier("a\"; DROP TABLE users; --"),
+            "\"a\"\"; DROP TABLE users; --\""
+        );
+        assert_eq!(quote_qualified("public.orders"), "\"public\".\"orders\"");
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(
+            quote_literal("x'; DROP TABLE users; --"),
+            "'x''; DROP TABLE users; --'"
+        );
+    }
+
+    #[test]
+    fn test_connection_value() {
+        assert_eq!(connection_value("analytics"), "analytics");
+        assert_eq!(connection_value("p@ss:word"), "p@ss:word");
+        assert_eq!(connection_value("two words"), "'two words'");
+        assert_eq!(connection_value("it's\\"), "'it\\'s\\\\'");
+        assert_eq!(connection_value("x host=evil"), "'x host=evil'");
+        assert_eq!(connection_value(""), "''");
+    }
+}
@@ -1,5 +1,7 @@
+use crate::config::project::HistoryConfig;
+use crate::redact::redact;
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -33,6 +35,10 @@ impl TaskStatus {
             error: None,
         }
     }
+
+    pub fn duration_ms(&self) -> Option<i64> {
+        duration_ms(self.started_at, self.completed_at)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -42,6 +48,9 @@ pub enum Phase {
     Completed,
     Failed,
     Waiting,
+    Cancelled,
+    /// Not run, because no upstream node of the run changed any data.
+    Skipped,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +67,24 @@ pub struct PipelineStatus {
     pub tasks: HashMap<String, TaskStatus>,
 }
 
+impl PipelineStatus {
+    pub fn duration_ms(&self) -> Option<i64> {
+        duration_ms(self.started_at, self.completed_at)
+    }
+}
+
+fn duration_ms(
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+) -> Option<i64> {
+    match (started_at, completed_at) {
+        (Some(started_at), Some(completed_at)) => {
+            Some((completed_at - started_at).num_milliseconds())
+        }
+        _ => None,
+    }
+}
+
 pub struct StatusManager {
     pub path: PathBuf,
 }
@@ -66,12 +93,42 @@ impl StatusManager {
     pub fn new(project_dir: &Path) -> Self {
         let status_dir = Self::get_status_dir(project_dir);
         let now = Utc::now();
-        let filename = now.format("%Y-%m-%d-%H-%M-%S.json").to_string();
+        let filename = now.format("%Y-%m-%d-%H-%M-%S-%3f.json").to_string();
         let path = status_dir.join(filename);
 
         Self { path }
     }
 
+    pub fn from_run_id(project_dir: &Path, run_id: &str) -> Option<Self> {
+        let valid = !run_id.is_empty()
+            && run_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return None;
+        }
+
+        let path = Self::get_status_dir(project_dir).join(format!("{run_id}.json"));
+        if !path.exists() {
+            return None;
+        }
+
+        Some(Self { path })
+    }
+
+    pub fn run_id(&self) -> String {
+        self.path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Whether the status file of this run has already been written.
+    pub fn is_started(&self) -> bool {
+        self.path.exists()
+    }
+
     pub async fn start(
         &self,
         started_at: DateTime<Utc>,
@@ -94,13 +151,17 @@ impl StatusManager {
         Ok(status)
     }
 
-    async fn load(&self) -> Result<PipelineStatus> {
+    pub async fn load(&self) -> Result<PipelineStatus> {
         let content = fs::read_to_string(&self.path).await?;
         let status: PipelineStatus = serde_json::from_str(&content)?;
         Ok(status)
     }
 
     async fn save(&self, pipeline_status: &PipelineStatus) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
         let content = serde_json::to_string_pretty(pipeline_status)?;
         fs::write(&self.path, content).await?;
         Ok(())
@@ -144,6 +205,67 @@ impl StatusManager {
         }
     }
 
+    pub async fn list_runs(project_dir: &Path) -> Result<Vec<(String, PipelineStatus)>> {
+        let status_dir = Self::get_status_dir(project_dir);
+
+        if !status_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+        let mut entries = fs::read_dir(&status_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                paths.push(path);
+            }
+        }
+        paths.sort_by(|a, b| b.cmp(a));
+
+        let mut runs = Vec::new();
+        for path in paths {
+            let status_manager = Self { path };
+            match status_manager.load().await {
+                Ok(status) => runs.push((status_manager.run_id(), status)),
+                Err(e) => tracing::warn!(
+                    "Skipping unreadable status file {}: {}",
+                    status_manager.path.display(),
+                    e
+                ),
+            }
+        }
+
+        Ok(runs)
+    }
+
+    pub async fn prune(project_dir: &Path, history: &HistoryConfig) -> Result<usize> {
+        let runs = Self::list_runs(project_dir).await?;
+        let cutoff = history
+            .max_age_days
+            .map(|days| Utc::now() - Duration::days(i64::from(days)));
+
+        let mut removed = 0;
+        for (index, (run_id, status)) in runs.iter().enumerate() {
+            if status.phase == Phase::Running {
+                continue;
+            }
+
+            let over_count = history.max_runs.is_some_and(|max_runs| index >= max_runs);
+            let too_old = match (cutoff, status.started_at) {
+                (Some(cutoff), Some(started_at)) => started_at < cutoff,
+                _ => false,
+            };
+
+            if over_count || too_old {
+                let path = Self::get_status_dir(project_dir).join(format!("{run_id}.json"));
+                fs::remove_file(&path).await?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
     pub async fn start_tasks(&mut self, tables: &[String]) -> Result<()> {
         let mut status = self.load().await?;
 
@@ -192,12 +314,148 @@ impl StatusManager {
         let mut status = self.load().await?;
         if let Some(task) = status.tasks.get_mut(table_name) {
             task.phase = Phase::Failed;
+            task.completed_at = Some(Utc::now());
             task.error = Some(ErrorInfo {
-                message: error_message,
+                message: redact(&error_message),
                 at: Utc::now(),
             });
         }
         self.save(&status).await?;
         Ok(())
     }
+
+    pub async fn cancel_task(&mut self, table_name: &str) -> Result<()> {
+        let mut status = self.load().await?;
+        if let Some(task) = status.tasks.get_mut(table_name) {
+            task.phase = Phase::Cancelled;
+            task.completed_at = Some(Utc::now());
+        }
+        self.save(&status).await?;
+        Ok(())
+    }
+
+    pub async fn skip_task(&mut self, table_name: &str) -> Result<()> {
+        let mut status = self.load().await?;
+        if let Some(task) = status.tasks.get_mut(table_name) {
+            task.phase = Phase::Skipped;
+        }
+        self.save(&status).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_waiting_tasks(&mut self) -> Result<()> {
+        let mut status = self.load().await?;
+        for task in status.tasks.values_mut() {
+            if task.phase == Phase::Waiting {
+                task.phase = Phase::Cancelled;
+            }
+        }
+        self.save(&status).await?;
+        Ok(())
+    }
+
+    pub async fn finish(&mut self, phase: Phase) -> Result<()> {
+        let mut status = self.load().await?;
+        status.phase = phase;
+        status.completed_at = Some(Utc::now());
+        self.save(&status).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_lifecycle_and_lookup() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path();
+
+        let mut status_manager = StatusManager::new(project_dir);
+        assert!(!status_manager.is_started());
+        status_manager
+            .start(Utc::now(), &["users".to_string(), "orders".to_string()])
+            .await?;
+        assert!(status_manager.is_started());
+        status_manager.start_tasks(&["users".to_string()]).await?;
+        status_manager.cancel_task("users").await?;
+        status_manager.cancel_waiting_tasks().await?;
+        status_manager.finish(Phase::Cancelled).await?;
+
+        let run_id = status_manager.run_id();
+        let found = StatusManager::from_run_id(project_dir, &run_id).unwrap();
+        let status = found.load().await?;
+
+        assert_eq!(status.phase, Phase::Cancelled);
+        assert!(status.completed_at.is_some());
+        assert_eq!(status.tasks["users"].phase, Phase::Cancelled);
+        assert_eq!(status.tasks["orders"].phase, Phase::Cancelled);
+
+        assert!(StatusManager::from_run_id(project_dir, "../metadata").is_none());
+        assert!(StatusManager::from_run_id(project_dir, "missing").is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_and_prune_runs() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path();
+        let status_dir = StatusManager::get_status_dir(project_dir);
+
+        for (run_id, days_ago) in [
+            ("2024-01-01-00-00-00-000", 400),
+            ("2024-06-01-00-00-00-000", 200),
+            ("2025-01-01-00-00-00-000", 1),
+        ] {
+            let mut status_manager = StatusManager {
+                path: status_dir.join(format!("{run_id}.json")),
+            };
+            status_manager
+                .start(
+                    Utc::now() - Duration::days(days_ago),
+                    &["users".to_string()],
+                )
+                .await?;
+            status_manager.finish(Phase::Completed).await?;
+        }
+
+        let runs = StatusManager::list_runs(project_dir).await?;
+        let run_ids: Vec<&str> = runs.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(
+            run_ids,
+            vec![
+                "2025-01-01-00-00-00-000",
+                "2024-06-01-00-00-00-000",
+                "2024-01-01-00-00-00-000"
+            ]
+        );
+
+        let removed = StatusManager::prune(
+            project_dir,
+            &HistoryConfig {
+                max_runs: None,
+                max_age_days: Some(300),
+            },
+        )
+        .await?;
+        assert_eq!(removed, 1);
+
+        let removed = StatusManager::prune(
+            project_dir,
+            &HistoryConfig {
+                max_runs: Some(1),
+                max_age_days: None,
+            },
+        )
+        .await?;
+        assert_eq!(removed, 1);
+
+        let runs = StatusManager::list_runs(project_dir).await?;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].0, "2025-01-01-00-00-00-000");
+
+        Ok(())
+    }
 }
@@ -1,4 +1,7 @@
 use anyhow::Result;
+use axum::Router;
+
+use crate::api::ServerConfig;
 
 #[cfg(debug_assertions)]
 mod debug_server {
@@ -6,7 +9,9 @@ mod debug_server {
     use std::process::Stdio;
     use tokio::process::Command as TokioCommand;
 
-    pub async fn start() -> Result<()> {
+    use crate::api::ServerConfig;
+
+    pub async fn start(server: &ServerConfig) -> Result<()> {
         // Get the featherbox binary directory and construct UI path
         let binary_path =
             std::env::current_exe().context("Failed to get current executable path")?;
@@ -20,18 +25,25 @@ mod debug_server {
 
         let ui_dir = binary_dir.join("src/ui");
 
-        println!("Starting UI development server on port 8015...");
+        println!(
+            "Starting UI development server on port {}...",
+            server.ui_port
+        );
 
+        // Read by vite.config.ts for the dev server address and the /api proxy target.
         let mut child = TokioCommand::new("pnpm")
             .args(["run", "dev"])
             .current_dir(&ui_dir)
+            .env("FEATHERBOX_UI_HOST", &server.host)
+            .env("FEATHERBOX_UI_PORT", server.ui_port.to_string())
+            .env("FEATHERBOX_API_URL", server.api_url())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
             .context("Failed to start pnpm dev server. Make sure pnpm is installed and dependencies are installed.")?;
 
         tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-        println!("✓ UI development server started on http://localhost:8015");
+        println!("✓ UI development server started on {}", server.ui_url());
 
         let _ = child.wait().await;
 
@@ -41,14 +53,12 @@ mod debug_server {
 
 #[cfg(not(debug_assertions))]
 mod production_server {
-    use anyhow::{Context, Result};
     use axum::{
         Router,
         http::{StatusCode, Uri, header},
         response::{IntoResponse, Response},
     };
     use rust_embed::RustEmbed;
-    use tokio::net::TcpListener;
 
     #[derive(RustEmbed)]
     #[folder = "src/ui/build/"]
@@ -88,33 +98,36 @@ mod production_server {
         }
     }
 
-    pub async fn start() -> Result<()> {
-        println!("Starting UI production server on port 8015...");
-
-        let app = Router::new().fallback(static_handler);
-
-        let listener = TcpListener::bind("localhost:8015")
-            .await
-            .context("Failed to bind UI server to localhost:8015")?;
-
-        println!("✓ UI production server started on http://localhost:8015");
+    pub fn with_ui(app: Router) -> Router {
+        app.fallback(static_handler)
+    }
+}
 
-        axum::serve(listener, app)
-            .await
-            .context("UI server error")?;
+/// Adds the embedded UI to `app`. Debug builds serve the UI from the Vite dev server instead.
+pub fn with_ui(app: Router) -> Router {
+    #[cfg(debug_assertions)]
+    {
+        println!("⚠ Debug builds don't embed the UI; run the Vite dev server separately");
+        app
+    }
 
-        Ok(())
+    #[cfg(not(debug_assertions))]
+    {
+        production_server::with_ui(app)
     }
 }
 
-pub async fn start_ui_server() -> Result<()> {
+/// Serves the UI on its own port. In release builds that listener also serves `api`, so the
+/// UI can call the API with relative URLs.
+pub async fn start_ui_server(server: &ServerConfig, api: Router) -> Result<()> {
     #[cfg(debug_assertions)]
     {
-        debug_server::start().await
+        let _ = api;
+        debug_server::start(server).await
     }
 
     #[cfg(not(debug_assertions))]
     {
-        production_server::start().await
+        crate::api::serve(with_ui(api), server, server.ui_port).await
     }
 }
@@ -1 +1,51 @@
-export const API_BASE_URL = 'http://localhost:3015';
+// The API is served from the same origin as the UI (proxied by Vite in development).
+export const API_BASE_URL = '';
+
+const API_TOKEN_STORAGE_KEY = 'featherbox.apiToken';
+
+export function setApiToken(token: string | null) {
+  if (token) {
+    localStorage.setItem(API_TOKEN_STORAGE_KEY, token);
+  } else {
+    localStorage.removeItem(API_TOKEN_STORAGE_KEY);
+  }
+}
+
+// fetch() with the stored API token, for projects with `api.auth` enabled.
+export function apiFetch(input: RequestInfo | URL, init: RequestInit = {}) {
+  const token = localStorage.getItem(API_TOKEN_STORAGE_KEY);
+  if (!token) {
+    return fetch(input, init);
+  }
+
+  const headers = new Headers(init.headers);
+  headers.set('Authorization', `Bearer ${token}`);
+  return fetch(input, { ...init, headers });
+}
+
+// Every API error response has this shape.
+export interface ApiError {
+  code: string;
+  message: string;
+  details?: Record<string, unknown>;
+}
+
+export async function readApiError(response: Response): Promise<ApiError> {
+  const body = await response.json().catch(() => null);
+  if (body && typeof body.message === 'string') {
+    return body;
+  }
+  return { code: 'error', message: response.statusText || `HTTP ${response.status}` };
+}
+
+// "message (line 1, column 8)" when the error points at a place in the SQL.
+export function formatApiError(error: ApiError): string {
+  const line = error.details?.line;
+  const column = error.details?.column;
+  if (typeof line === 'number') {
+    return typeof column === 'number'
+      ? `${error.message} (line ${line}, column ${column})`
+      : `${error.message} (line ${line})`;
+  }
+  return error.message;
+}
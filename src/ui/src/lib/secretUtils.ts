@@ -1,4 +1,4 @@
-import { API_BASE_URL } from './config';
+import { API_BASE_URL, apiFetch } from './config';
 
 export interface SecretSummary {
   key: string;
@@ -10,7 +10,7 @@ export async function createSecret(
   value: string,
 ): Promise<boolean> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets`, {
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets`, {
       method: 'POST',
       headers: {
         'Content-Type': 'application/json',
@@ -30,7 +30,7 @@ export async function updateSecret(
   value: string,
 ): Promise<boolean> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets/${key}`, {
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets/${key}`, {
       method: 'PUT',
       headers: {
         'Content-Type': 'application/json',
@@ -47,7 +47,7 @@ export async function updateSecret(
 
 export async function deleteSecret(key: string): Promise<boolean> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets/${key}`, {
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets/${key}`, {
       method: 'DELETE',
     });
 
@@ -62,7 +62,7 @@ export async function getSecretInfo(
   key: string,
 ): Promise<SecretSummary | null> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets/${key}`);
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets/${key}`);
 
     if (response.ok) {
       return await response.json();
@@ -76,7 +76,7 @@ export async function getSecretInfo(
 
 export async function listSecrets(): Promise<SecretSummary[]> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets`);
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets`);
 
     if (response.ok) {
       return await response.json();
@@ -94,7 +94,7 @@ export async function generateUniqueSecretKey(
   fieldType: string,
 ): Promise<string> {
   try {
-    const response = await fetch(`${API_BASE_URL}/api/secrets/generate-key`, {
+    const response = await apiFetch(`${API_BASE_URL}/api/secrets/generate-key`, {
       method: 'POST',
       headers: {
         'Content-Type': 'application/json',
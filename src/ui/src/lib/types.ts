@@ -73,6 +73,8 @@ export interface AdapterConfig {
   description?: string;
   source: AdapterSource;
   columns: ColumnConfig[];
+  mode?: 'full' | 'incremental';
+  tests?: DataTestConfig[];
 }
 
 export interface AdapterSource {
@@ -80,12 +82,17 @@ export interface AdapterSource {
   file?: FileConfig;
   format?: FormatConfig;
   table_name?: string;
+  query?: string;
+  cursor_column?: string;
+  primary_key?: string[];
 }
 
 export interface FileConfig {
   path: string;
   compression?: string;
   max_batch_size?: string;
+  lookback_days?: number;
+  partition_column?: string;
 }
 
 export interface FormatConfig {
@@ -115,9 +122,25 @@ export interface ModelSummary {
 export interface ModelConfig {
   sql: string;
   description?: string;
+  materialization?: 'table' | 'view' | 'incremental_append' | 'incremental_merge';
+  unique_key?: string[];
+  tests?: DataTestConfig[];
   depends?: string[];
 }
 
+export interface DataTestConfig {
+  type: 'not_null' | 'unique' | 'accepted_values' | 'relationships' | 'row_count' | 'sql';
+  severity?: 'warn' | 'error';
+  column?: string;
+  columns?: string[];
+  values?: string[];
+  to?: string;
+  field?: string;
+  min?: number;
+  max?: number;
+  sql?: string;
+}
+
 export interface ModelDetails {
   name: string;
   path: string;
@@ -171,3 +194,28 @@ export interface PipelineStatus {
   completed_at?: string;
   tasks: TaskStatus[];
 }
+
+export interface SecretSummary {
+  key: string;
+  masked_value: string;
+}
+
+export interface LoginResponse {
+  auth_enabled: boolean;
+  name?: string;
+  role: 'read_only' | 'admin';
+}
+
+export interface QueryColumn {
+  name: string;
+  data_type: string;
+}
+
+export interface QueryPage {
+  columns: QueryColumn[];
+  rows: unknown[][];
+  offset: number;
+  next_offset: number | null;
+}
+
+export type ExportFormat = 'csv' | 'jsonl' | 'parquet';
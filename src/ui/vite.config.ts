@@ -5,13 +5,14 @@ import { svelte } from '@sveltejs/vite-plugin-svelte';
 export default defineConfig({
   plugins: [svelte()],
   server: {
-    port: 8015,
-    host: 'localhost',
+    port: Number(process.env.FEATHERBOX_UI_PORT ?? 8015),
+    host: process.env.FEATHERBOX_UI_HOST ?? 'localhost',
     allowedHosts: ['desktop.tail74e0bd.ts.net'],
     proxy: {
       '/api': {
-        target: 'http://localhost:3015',
+        target: process.env.FEATHERBOX_API_URL ?? 'http://localhost:3015',
         changeOrigin: true,
+        secure: false,
       },
     },
   },